@@ -35,6 +35,13 @@ echo "✓ Installed PolicyKit policy"
 install -D -m 0644 assets/icon.svg /usr/share/icons/hicolor/scalable/apps/cpu-power-manager.svg
 echo "✓ Installed icon"
 
+# Members of this group may use the background service's control socket
+if ! getent group cpu-power-manager &> /dev/null; then
+    groupadd --system cpu-power-manager
+    echo "✓ Created group cpu-power-manager"
+    echo "  To use 'run' without sudo: sudo usermod -aG cpu-power-manager \$USER"
+fi
+
 # Install systemd service (optional)
 if [ -d "/etc/systemd/system" ]; then
     install -D -m 0644 assets/cpu-power-manager.service /etc/systemd/system/cpu-power-manager.service
@@ -4,13 +4,35 @@ use gtk4::{
     Frame, GestureClick, Grid, HeaderBar, Label, LevelBar, License, MenuButton, Notebook,
     Orientation, Popover, Scale, ScrolledWindow, Separator, SpinButton, StringList, Switch, DropDown,
 };
-use crate::backend::{CpuManager, HwmonReader, PowerSupplyReader, RaplTracker};
+use crate::backend::{BatteryEstimator, BatteryInfo, ChargeThresholds, CpuManager, EmergencyGuard, FanController, FanCurve, HwmonReader, PowerSourceWatcher, PowerSupplyReader, RaplTracker, RaplZone, RuleEngine, ThrottleForecaster};
 use crate::backend::cpu::{CpuInfo, CpuDriver, CpuUsageTracker, PerCoreCpuUsageTracker};
-use crate::backend::thermal::ThermalManager;
+use crate::backend::emergency::EmergencyEvent;
+use crate::backend::fan::CurvePoint;
+use crate::backend::hwmon::{HwmonDevice, SensorKind};
+use crate::backend::power_supply::{DomainPower, EnergyDomainKind};
+use crate::backend::thermal::{CoolingDevice, TempSource, ThermalManager, ThermalZone};
+use crate::backend::thermal_forecast::format_duration;
+use crate::backend::thermal_limiter::{LimiterActuator, ThermalLimiter};
 use crate::backend::profile::{Profile, ProfileManager};
+use crate::backend::schedule::{ResumeDetector, ScheduleRule};
+use crate::backend::service_status::ServiceStatus;
+use crate::backend::auto_tune::{self, BatteryRule, Selection, SwitchHistory};
+use crate::backend::benchmark::BenchmarkReport;
+use crate::backend::calibrate::EfficiencyCurve;
+use crate::backend::fixed_freq::{self, FixedLock, LockOptions, Stabiliser};
+use crate::backend::overrides::{self, OverrideStack, OverrideWatcher};
+use crate::backend::rules::Inputs;
 use crate::config::{ConfigManager, set_autostart};
+use crate::utils::smoothing::{Smoother, SmoothingMethod};
+use crate::utils::units::{fmt_temp, TempUnit};
+use std::cell::RefCell;
+use std::rc::Rc;
 use std::sync::{Arc, Mutex};
 
+/// Seconds between battery card refreshes while the power source holds;
+/// a change refreshes it at once.
+const BATTERY_REFRESH_TICKS: u64 = 10;
+
 pub struct AppWindow {
     window: ApplicationWindow,
     cpu_manager: Arc<Mutex<CpuManager>>,
@@ -22,9 +44,11 @@ pub struct AppWindow {
     freq_label: Label,
     usage_label: Label,
     temp_label: Label,
+    throttle_label: Label,
     governor_label: Label,
     turbo_label: Label,
     power_label: Label,
+    power_detail_label: Label,
     battery_label: Label,
     fan_label: Label,
     active_profile_label: Label,
@@ -34,10 +58,18 @@ pub struct AppWindow {
     cpu_usage_tracker: Arc<Mutex<CpuUsageTracker>>,
     per_core_tracker: Arc<Mutex<PerCoreCpuUsageTracker>>,
     rapl_tracker: Arc<Mutex<RaplTracker>>,
+    fan_controller: Arc<Mutex<FanController>>,
+    thermal_limiter: Arc<Mutex<Option<ThermalLimiter>>>,
+    emergency_guard: Arc<Mutex<EmergencyGuard>>,
+    limiter_label: Label,
     // Per-core panel container
     per_core_box: Box,
-    // Last AC status for auto-switch detection
-    last_ac_status: Arc<Mutex<Option<bool>>>,
+    battery_box: Box,
+    // Last auto-switch target, and recent switches with their reasons
+    last_auto_switch: Arc<Mutex<Option<Selection>>>,
+    switch_history_label: Label,
+    // Power source from the rule engine's watcher; None until its first event
+    on_ac: Arc<Mutex<Option<bool>>>,
 }
 
 impl AppWindow {
@@ -45,12 +77,17 @@ impl AppWindow {
         let config_manager = Arc::new(Mutex::new(
             ConfigManager::new().unwrap_or_else(|_| ConfigManager::new().unwrap()),
         ));
+        TempUnit::set_current(TempUnit::from_config(
+            &config_manager.lock().unwrap().get_config().general.temperature_unit,
+        ));
         let cpu_manager = Arc::new(Mutex::new(
             CpuManager::new().expect("Failed to initialize CPU manager"),
         ));
-        let thermal_manager = Arc::new(Mutex::new(
-            ThermalManager::new().expect("Failed to initialize thermal manager"),
-        ));
+        let thermal_manager = Arc::new(Mutex::new({
+            let mut tm = ThermalManager::new().expect("Failed to initialize thermal manager");
+            tm.set_source(config_manager.lock().unwrap().get_config().thermal.cpu_temp_source.clone());
+            tm
+        }));
 
         // Build profile manager merging built-ins + custom profiles from config
         let custom = config_manager.lock().unwrap().get_config().custom_profiles.clone();
@@ -77,14 +114,17 @@ impl AppWindow {
 
         let freq_label    = Label::new(Some("-- MHz"));
         let usage_label   = Label::new(Some("-- %"));
-        let temp_label    = Label::new(Some("--°C"));
+        let temp_label    = Label::new(Some(&format!("--{}", TempUnit::current().symbol())));
+        let throttle_label = Label::new(None);
         let governor_label = Label::new(Some("--"));
         let turbo_label   = Label::new(Some("--"));
         let power_label   = Label::new(Some("-- W"));
+        let power_detail_label = Label::new(None);
         let battery_label = Label::new(Some("--"));
         let fan_label     = Label::new(Some("-- RPM"));
         let active_profile_label = Label::new(Some("--"));
         let per_core_box  = Box::new(Orientation::Vertical, 4);
+        let battery_box   = Box::new(Orientation::Vertical, 8);
 
         let cpu_usage_area    = gtk4::DrawingArea::new();
         cpu_usage_area.set_content_width(600);
@@ -93,14 +133,33 @@ impl AppWindow {
         let cpu_usage_tracker = Arc::new(Mutex::new(CpuUsageTracker::new()));
         let per_core_tracker  = Arc::new(Mutex::new(PerCoreCpuUsageTracker::new(core_count)));
         let rapl_tracker      = Arc::new(Mutex::new(RaplTracker::new()));
-        let last_ac_status    = Arc::new(Mutex::new(None::<bool>));
+        let fan_controller    = Arc::new(Mutex::new(FanController::new()));
+        let last_auto_switch  = Arc::new(Mutex::new(None::<Selection>));
+        let switch_history_label = Label::new(Some("No switches yet"));
+        let on_ac             = Arc::new(Mutex::new(None::<bool>));
+        let thermal_limiter   = Arc::new(Mutex::new(None::<ThermalLimiter>));
+        let limiter_label     = Label::new(Some("Inactive"));
+        let emergency_guard   = Arc::new(Mutex::new(EmergencyGuard::new()));
+
+        // Put back the limits the emergency clamp and the limiter were moving.
+        {
+            let (limiter, guard, cpu) = (thermal_limiter.clone(), emergency_guard.clone(), cpu_manager.clone());
+            app.connect_shutdown(move |_| {
+                let cpu = cpu.lock().unwrap();
+                guard.lock().unwrap().release(&cpu);
+                if let Some(mut l) = limiter.lock().unwrap().take() {
+                    l.release(&cpu);
+                }
+            });
+        }
 
         let app_window = Self {
             window, cpu_manager, thermal_manager, profile_manager, config_manager,
-            cpu_info, freq_label, usage_label, temp_label, governor_label, turbo_label,
-            power_label, battery_label, fan_label, active_profile_label, per_core_box,
+            cpu_info, freq_label, usage_label, temp_label, throttle_label, governor_label, turbo_label,
+            power_label, power_detail_label, battery_label, fan_label, active_profile_label, per_core_box, battery_box,
             cpu_usage_area, cpu_usage_history, cpu_usage_tracker, per_core_tracker,
-            rapl_tracker, last_ac_status,
+            rapl_tracker, fan_controller, thermal_limiter, emergency_guard, limiter_label, last_auto_switch,
+            switch_history_label, on_ac,
         };
 
         app_window.setup_ui();
@@ -211,6 +270,38 @@ impl AppWindow {
                 pop_box.append(&btn);
             }
         }
+
+        // Temporary overrides on top of whatever is active
+        pop_box.append(&Separator::new(Orientation::Horizontal));
+        let ovr_title = Label::new(Some("Temporary Override"));
+        ovr_title.add_css_class("title");
+        pop_box.append(&ovr_title);
+        for (i, (label, _)) in overrides::presets().iter().enumerate() {
+            let btn = Button::with_label(label);
+            let handler = self.override_handler();
+            let pop_c = profile_popover.clone();
+            btn.connect_clicked(move |_| {
+                handler(Some(i));
+                pop_c.popdown();
+            });
+            pop_box.append(&btn);
+        }
+        let clear_btn = Button::with_label("Clear Overrides");
+        let handler = self.override_handler();
+        let pop_c = profile_popover.clone();
+        clear_btn.connect_clicked(move |_| {
+            handler(None);
+            pop_c.popdown();
+        });
+        pop_box.append(&clear_btn);
+        let ovr_list = Label::new(None);
+        ovr_list.add_css_class("subtitle");
+        ovr_list.set_xalign(0.0);
+        pop_box.append(&ovr_list);
+        profile_popover.connect_show(move |_| {
+            let lines = OverrideStack::load().lines(chrono::Local::now().timestamp());
+            ovr_list.set_text(&if lines.is_empty() { "No overrides active".to_string() } else { lines.join("\n") });
+        });
         profile_popover.set_child(Some(&pop_box));
         profile_menu_btn.set_popover(Some(&profile_popover));
         header.pack_end(&profile_menu_btn);
@@ -239,6 +330,9 @@ impl AppWindow {
         mon_box.set_margin_start(12); mon_box.set_margin_end(12);
         mon_box.append(&self.create_dashboard());
         mon_box.append(&self.create_graph());
+        if PowerSupplyReader::read().present {
+            mon_box.append(&self.create_battery_section());
+        }
         mon_box.append(&self.create_per_core_section());
         mon_scroll.set_child(Some(&mon_box));
         notebook.append_page(&mon_scroll, Some(&Label::new(Some("📊 Monitor"))));
@@ -251,12 +345,25 @@ impl AppWindow {
         ctrl_box.set_margin_start(12); ctrl_box.set_margin_end(12);
         ctrl_box.append(&self.create_profile_buttons());
         ctrl_box.append(&self.create_freq_sliders());
+        ctrl_box.append(&self.create_power_limits_section());
+        ctrl_box.append(&self.create_fan_control_section());
+        ctrl_box.append(&self.create_thermal_limiter_section());
         ctrl_box.append(&self.create_advanced_controls());
+        ctrl_box.append(&self.create_fixed_freq_section());
         ctrl_box.append(&self.create_ac_battery_section());
         ctrl_scroll.set_child(Some(&ctrl_box));
         notebook.append_page(&ctrl_scroll, Some(&Label::new(Some("🎛 Control"))));
 
-        // Tab 3: Settings
+        // Tab 3: Sensors
+        notebook.append_page(&self.create_sensors_tab(), Some(&Label::new(Some("📡 Sensors"))));
+
+        // Tab 4: Thermal
+        notebook.append_page(&self.create_thermal_tab(), Some(&Label::new(Some("🌡 Thermal"))));
+
+        // Tab 5: Benchmarks
+        notebook.append_page(&self.create_benchmarks_tab(), Some(&Label::new(Some("📈 Benchmarks"))));
+
+        // Tab 6: Settings
         let set_scroll = ScrolledWindow::new();
         set_scroll.set_vexpand(true);
         let set_box = Box::new(Orientation::Vertical, 12);
@@ -264,6 +371,8 @@ impl AppWindow {
         set_box.set_margin_start(12); set_box.set_margin_end(12);
         set_box.append(&self.create_system_settings());
         set_box.append(&self.create_app_settings());
+        set_box.append(&self.create_schedule_section());
+        set_box.append(&self.create_temp_source_section());
         set_box.append(&self.create_custom_profiles_section());
         set_box.append(&self.create_system_info());
         set_scroll.set_child(Some(&set_box));
@@ -271,6 +380,7 @@ impl AppWindow {
 
         self.window.set_child(Some(&notebook));
         self.setup_updates();
+        self.setup_rule_engine();
     }
 
     // ── Dashboard (2×4 grid) ─────────────────────────────────────────────────────
@@ -286,19 +396,20 @@ impl AppWindow {
         grid.set_margin_top(12); grid.set_margin_bottom(12);
         grid.set_margin_start(12); grid.set_margin_end(12);
 
-        let cards: Vec<(&str, &Label, Option<&str>, Option<&str>)> = vec![
+        // (title, value label, css class, optional detail label shown underneath)
+        let cards: Vec<(&str, &Label, Option<&str>, Option<&Label>)> = vec![
             ("Avg Frequency", &self.freq_label, Some("freq-value"), None),
             ("CPU Usage",     &self.usage_label, Some("usage-value"), None),
-            ("Temperature",   &self.temp_label, None, None),
+            ("Temperature",   &self.temp_label, None, Some(&self.throttle_label)),
             ("Governor",      &self.governor_label, Some("value"), None),
             ("Turbo Boost",   &self.turbo_label, None, None),
-            ("Power Draw",    &self.power_label, Some("value"), None),
+            ("Power Draw",    &self.power_label, Some("value"), Some(&self.power_detail_label)),
             ("Battery",       &self.battery_label, None, None),
             ("Fan Speed",     &self.fan_label, Some("value"), None),
         ];
 
         // Only place the first 7 cards (skip index 7 / Fan Speed — it lives in the CPU card below)
-        for (i, (title, lbl, css, _)) in cards.iter().take(7).enumerate() {
+        for (i, (title, lbl, css, detail)) in cards.iter().take(7).enumerate() {
             let col = (i % 4) as i32;
             let row = (i / 4) as i32;
             let card = Box::new(Orientation::Vertical, 4);
@@ -311,6 +422,12 @@ impl AppWindow {
             lbl.set_halign(gtk4::Align::Start);
             lbl.set_ellipsize(gtk4::pango::EllipsizeMode::End);
             card.append(*lbl);
+            if let Some(d) = detail {
+                d.add_css_class("subtitle");
+                d.set_halign(gtk4::Align::Start);
+                d.set_wrap(true);
+                card.append(*d);
+            }
             grid.attach(&card, col, row, 1, 1);
         }
 
@@ -435,12 +552,26 @@ impl AppWindow {
         frame
     }
 
+    fn create_battery_section(&self) -> Frame {
+        let frame = Frame::new(Some("Battery"));
+        frame.add_css_class("card");
+        self.battery_box.set_margin_top(8);
+        self.battery_box.set_margin_bottom(8);
+        self.battery_box.set_margin_start(12);
+        self.battery_box.set_margin_end(12);
+        rebuild_battery_panel(&self.battery_box, &PowerSupplyReader::read(), None);
+        frame.set_child(Some(&self.battery_box));
+        frame
+    }
+
     fn rebuild_per_core_panel(&self, usages: &[f32]) {
         while let Some(child) = self.per_core_box.first_child() {
             self.per_core_box.remove(&child);
         }
         let cpu = self.cpu_manager.lock().unwrap();
         let core_temps = HwmonReader::get_per_core_temps();
+        // AMD has no per-core sensors; fall back to the CCD each core sits on
+        let ccd_temps = if core_temps.is_empty() { HwmonReader::get_ccd_temps_by_cpu() } else { Vec::new() };
         let grid = Grid::new();
         grid.set_row_spacing(4);
         grid.set_column_spacing(8);
@@ -482,10 +613,16 @@ impl AppWindow {
 
                 // Per-core temp if available
                 let temp_lbl = if let Some(&(_, temp)) = core_temps.iter().find(|(id, _)| *id == status.core_id) {
-                    let l = Label::new(Some(&format!("{:.0}°C", temp)));
+                    let l = Label::new(Some(&fmt_temp(temp, 0)));
                     l.add_css_class("subtitle");
                     l.set_width_chars(6);
                     Some(l)
+                } else if let Some(&(_, ccd, temp)) = ccd_temps.iter().find(|(cpu, _, _)| *cpu == status.core_id) {
+                    let l = Label::new(Some(&format!("CCD{} {}", ccd, fmt_temp(temp, 0))));
+                    l.set_tooltip_text(Some(&format!("Tccd{} — shared by every core on this CCD", ccd)));
+                    l.add_css_class("subtitle");
+                    l.set_width_chars(11);
+                    Some(l)
                 } else { None };
 
                 // Online toggle (core 0 can't be taken offline)
@@ -659,6 +796,485 @@ impl AppWindow {
         frame
     }
 
+    // ── RAPL power limits ─────────────────────────────────────────────────────────
+
+    fn create_power_limits_section(&self) -> Frame {
+        let frame = Frame::new(Some("Package Power Limits (RAPL)"));
+        frame.add_css_class("card");
+        let vbox = Box::new(Orientation::Vertical, 12);
+        vbox.set_margin_top(12); vbox.set_margin_bottom(12);
+        vbox.set_margin_start(12); vbox.set_margin_end(12);
+
+        let zones = RaplZone::packages();
+        if zones.is_empty() {
+            let na = Label::new(Some("RAPL power limits are not available on this system."));
+            na.add_css_class("subtitle");
+            na.set_halign(gtk4::Align::Start);
+            vbox.append(&na);
+            frame.set_child(Some(&vbox));
+            return frame;
+        }
+
+        for zone in zones {
+            let zone_title = Label::new(Some(&format!("{} ({})", zone.name, zone.id)));
+            zone_title.add_css_class("title");
+            zone_title.set_halign(gtk4::Align::Start);
+            vbox.append(&zone_title);
+
+            // (constraint id, watts spinner, time-window spinner in seconds)
+            let mut editors: Vec<(usize, SpinButton, Option<SpinButton>)> = Vec::new();
+            for c in &zone.constraints {
+                let row = Box::new(Orientation::Horizontal, 12);
+                row.add_css_class("settings-row");
+                let lbl_box = Box::new(Orientation::Vertical, 2);
+                lbl_box.set_hexpand(true);
+                let title = match c.name.as_str() {
+                    "long_term"  => "PL1 — Long Term".to_string(),
+                    "short_term" => "PL2 — Short Term".to_string(),
+                    "peak_power" => "PL4 — Peak Power".to_string(),
+                    other        => other.to_string(),
+                };
+                let t = Label::new(Some(&title));
+                t.set_halign(gtk4::Align::Start);
+                let sub = match c.max_power_w() {
+                    Some(max) => format!("Hardware maximum {:.0} W", max),
+                    None      => "No hardware maximum reported".to_string(),
+                };
+                let s = Label::new(Some(&sub));
+                s.add_css_class("subtitle"); s.set_halign(gtk4::Align::Start);
+                lbl_box.append(&t); lbl_box.append(&s);
+
+                let upper = c.max_power_w().unwrap_or(250.0).max(c.power_limit_w()) as f64;
+                let watts_spin = SpinButton::with_range(1.0, upper, 1.0);
+                watts_spin.set_value(c.power_limit_w() as f64);
+                watts_spin.set_valign(gtk4::Align::Center);
+                let w_lbl = Label::new(Some("W"));
+
+                row.append(&lbl_box);
+                row.append(&watts_spin);
+                row.append(&w_lbl);
+
+                let window_spin = c.time_window_us.map(|us| {
+                    let spin = SpinButton::with_range(0.001, 1000.0, 0.1);
+                    spin.set_digits(3);
+                    spin.set_value(us as f64 / 1_000_000.0);
+                    spin.set_valign(gtk4::Align::Center);
+                    row.append(&spin);
+                    row.append(&Label::new(Some("s")));
+                    spin
+                });
+
+                vbox.append(&row);
+                editors.push((c.id, watts_spin, window_spin));
+            }
+
+            let apply_btn = Button::with_label("Apply Power Limits");
+            apply_btn.add_css_class("suggested-action");
+            apply_btn.set_halign(gtk4::Align::Center);
+            apply_btn.connect_clicked(move |btn| {
+                let limits: Vec<(usize, f32)> = editors.iter()
+                    .map(|(id, w, _)| (*id, w.value() as f32))
+                    .collect();
+                let result = zone.set_limits(&limits).and_then(|_| {
+                    for (id, _, win) in &editors {
+                        if let Some(win) = win {
+                            zone.set_time_window(*id, (win.value() * 1_000_000.0).round() as u64)?;
+                        }
+                    }
+                    Ok(())
+                });
+                match result {
+                    Ok(_) => btn.set_label("✓ Applied"),
+                    Err(e) => {
+                        log::error!("RAPL limits: {:#}", e);
+                        btn.set_label("✗ Failed — see log");
+                    }
+                }
+                let bc = btn.clone();
+                glib::timeout_add_seconds_local(2, move || { bc.set_label("Apply Power Limits"); glib::ControlFlow::Break });
+            });
+            vbox.append(&apply_btn);
+        }
+
+        let note = Label::new(Some("PL1 caps sustained package power; PL2 caps short bursts. Limits reset on reboot unless set by a profile."));
+        note.add_css_class("subtitle");
+        note.set_wrap(true);
+        vbox.append(&note);
+
+        frame.set_child(Some(&vbox));
+        frame
+    }
+
+    // ── Fan control ───────────────────────────────────────────────────────────────
+
+    fn create_fan_control_section(&self) -> Frame {
+        let frame = Frame::new(Some("Fan Control"));
+        frame.add_css_class("card");
+        let vbox = Box::new(Orientation::Vertical, 10);
+        vbox.set_margin_top(12); vbox.set_margin_bottom(12);
+        vbox.set_margin_start(12); vbox.set_margin_end(12);
+
+        let keys: Vec<String> = self.fan_controller.lock().unwrap()
+            .channels().iter().map(|c| c.key()).collect();
+        if keys.is_empty() {
+            let na = Label::new(Some("No controllable PWM fan outputs found."));
+            na.add_css_class("subtitle");
+            na.set_halign(gtk4::Align::Start);
+            vbox.append(&na);
+            frame.set_child(Some(&vbox));
+            return frame;
+        }
+
+        let cfg = self.config_manager.lock().unwrap().get_config().clone();
+
+        // Enable toggle
+        let enable_row = Box::new(Orientation::Horizontal, 12);
+        enable_row.add_css_class("settings-row");
+        let en_lbl_box = Box::new(Orientation::Vertical, 2);
+        en_lbl_box.set_hexpand(true);
+        let en_title = Label::new(Some("Custom Fan Curves"));
+        en_title.set_halign(gtk4::Align::Start);
+        let en_sub = Label::new(Some("Drive PWM fans from CPU temperature; firmware control is restored when off or on exit"));
+        en_sub.add_css_class("subtitle"); en_sub.set_halign(gtk4::Align::Start);
+        en_sub.set_wrap(true);
+        en_lbl_box.append(&en_title); en_lbl_box.append(&en_sub);
+        let enable_sw = gtk4::Switch::new();
+        enable_sw.set_active(cfg.thermal.fan_control_enabled);
+        enable_sw.set_valign(gtk4::Align::Center);
+        let cfg_c = self.config_manager.clone();
+        enable_sw.connect_state_set(move |_, state| {
+            let mut c = cfg_c.lock().unwrap();
+            c.get_config_mut().thermal.fan_control_enabled = state;
+            let _ = c.save();
+            glib::Propagation::Proceed
+        });
+        enable_row.append(&en_lbl_box);
+        enable_row.append(&enable_sw);
+        vbox.append(&enable_row);
+
+        // Channel picker
+        let saved_curve = |cfg: &crate::config::Config, key: &str| -> FanCurve {
+            cfg.thermal.fan_curves.iter().find(|c| c.channel == key).cloned()
+                .unwrap_or_else(|| FanCurve::new(key))
+        };
+        let curve = Rc::new(RefCell::new(saved_curve(&cfg, &keys[0])));
+
+        let ch_row = Box::new(Orientation::Horizontal, 12);
+        ch_row.add_css_class("settings-row");
+        let ch_lbl = Label::new(Some("PWM Channel"));
+        ch_lbl.set_halign(gtk4::Align::Start);
+        ch_lbl.set_hexpand(true);
+        let sl = StringList::new(&keys.iter().map(|k| k.as_str()).collect::<Vec<_>>());
+        let ch_dd = DropDown::new(Some(sl), None::<gtk4::Expression>);
+        ch_dd.set_valign(gtk4::Align::Center);
+        ch_row.append(&ch_lbl);
+        ch_row.append(&ch_dd);
+        vbox.append(&ch_row);
+
+        // Curve plot: 20–100 °C across, 0–100 % duty up
+        let graph = gtk4::DrawingArea::new();
+        graph.set_content_width(600);
+        graph.set_content_height(160);
+        let curve_d = curve.clone();
+        graph.set_draw_func(move |_area, cr, width, height| {
+            let c = curve_d.borrow();
+            let (w, h) = (width as f64, height as f64);
+            let x_of = |t: f32| (t as f64 - 20.0) / 80.0 * w;
+            let y_of = |d: u8| h - d as f64 / 100.0 * h;
+            // Dracula bg_secondary #1e1f29
+            cr.set_source_rgb(0.118, 0.122, 0.161);
+            let _ = cr.paint();
+            cr.set_font_size(10.0);
+            for i in 0..=4 {
+                let y = (i as f64 / 4.0) * h;
+                cr.set_source_rgba(0.267, 0.278, 0.353, 0.7);
+                cr.set_line_width(1.0);
+                cr.move_to(0.0, y);
+                cr.line_to(w, y);
+                let _ = cr.stroke();
+                cr.set_source_rgba(0.384, 0.447, 0.643, 0.9);
+                cr.move_to(4.0, y - 2.0);
+                let _ = cr.show_text(&format!("{}%", 100 - i * 25));
+            }
+            for t in (30..100).step_by(10) {
+                cr.move_to(x_of(t as f32) - 10.0, h - 4.0);
+                let _ = cr.show_text(&fmt_temp(t as f32, 0));
+            }
+            // Dracula cyan #8be9fd curve, sampled so the min-duty floor shows
+            cr.set_source_rgb(0.545, 0.914, 0.992);
+            cr.set_line_width(2.0);
+            cr.move_to(0.0, y_of(c.duty_at(20.0)));
+            for px in (0..=width).step_by(2) {
+                let t = 20.0 + px as f32 / width.max(1) as f32 * 80.0;
+                cr.line_to(px as f64, y_of(c.duty_at(t)));
+            }
+            let _ = cr.stroke();
+            // Dracula pink #ff79c6 control points
+            cr.set_source_rgb(1.0, 0.475, 0.776);
+            for p in &c.points {
+                cr.arc(x_of(p.temp_celsius), y_of(p.duty_percent), 4.0, 0.0, std::f64::consts::TAU);
+                let _ = cr.fill();
+            }
+        });
+        vbox.append(&graph);
+
+        // Curve points
+        let points_box = Box::new(Orientation::Vertical, 6);
+        rebuild_fan_points(&points_box, &curve, &graph);
+        vbox.append(&points_box);
+
+        let add_btn = Button::with_label("+ Add Point");
+        add_btn.set_halign(gtk4::Align::Start);
+        {
+            let (curve, points_box, graph) = (curve.clone(), points_box.clone(), graph.clone());
+            add_btn.connect_clicked(move |_| {
+                {
+                    let mut c = curve.borrow_mut();
+                    let last = c.points.iter().copied()
+                        .max_by(|a, b| a.temp_celsius.total_cmp(&b.temp_celsius))
+                        .unwrap_or(CurvePoint { temp_celsius: 30.0, duty_percent: 0 });
+                    c.points.push(CurvePoint {
+                        temp_celsius: (last.temp_celsius + 10.0).min(110.0),
+                        duty_percent: last.duty_percent.saturating_add(20).min(100),
+                    });
+                }
+                rebuild_fan_points(&points_box, &curve, &graph);
+            });
+        }
+        vbox.append(&add_btn);
+
+        // Curve parameters
+        let params = Grid::new();
+        params.set_row_spacing(8); params.set_column_spacing(12);
+        let param_spin = |row: i32, title: &str, min: f64, max: f64, step: f64| {
+            let lbl = Label::new(Some(title));
+            lbl.set_halign(gtk4::Align::End);
+            params.attach(&lbl, 0, row, 1, 1);
+            let spin = SpinButton::with_range(min, max, step);
+            params.attach(&spin, 1, row, 1, 1);
+            spin
+        };
+        let unit = TempUnit::current();
+        let hyst_title = format!("Hysteresis ({}):", unit.symbol());
+        let hyst_spin   = param_spin(0, &hyst_title, 0.0, unit.delta_from_celsius(20.0) as f64, 0.5);
+        hyst_spin.set_digits(1);
+        let min_spin    = param_spin(1, "Minimum duty (%):", 0.0, 100.0, 1.0);
+        let spinup_spin = param_spin(2, "Spin-up duty (%):", 0.0, 100.0, 1.0);
+        let spinup_ms   = param_spin(3, "Spin-up time (ms):", 0.0, 10000.0, 100.0);
+        let load_params = {
+            let (h, m, sd, sm) = (hyst_spin.clone(), min_spin.clone(), spinup_spin.clone(), spinup_ms.clone());
+            move |c: &FanCurve| {
+                h.set_value(unit.delta_from_celsius(c.hysteresis_celsius) as f64);
+                m.set_value(c.min_duty_percent as f64);
+                sd.set_value(c.spinup_duty_percent as f64);
+                sm.set_value(c.spinup_ms as f64);
+            }
+        };
+        load_params(&curve.borrow());
+        {
+            let (c, g) = (curve.clone(), graph.clone());
+            hyst_spin.connect_value_changed(move |s| { c.borrow_mut().hysteresis_celsius = unit.delta_to_celsius(s.value() as f32); g.queue_draw(); });
+        }
+        {
+            let (c, g) = (curve.clone(), graph.clone());
+            min_spin.connect_value_changed(move |s| { c.borrow_mut().min_duty_percent = s.value() as u8; g.queue_draw(); });
+        }
+        {
+            let c = curve.clone();
+            spinup_spin.connect_value_changed(move |s| c.borrow_mut().spinup_duty_percent = s.value() as u8);
+        }
+        {
+            let c = curve.clone();
+            spinup_ms.connect_value_changed(move |s| c.borrow_mut().spinup_ms = s.value() as u64);
+        }
+        vbox.append(&params);
+
+        {
+            let (curve, points_box, graph) = (curve.clone(), points_box.clone(), graph.clone());
+            let cfg_c = self.config_manager.clone();
+            let keys_c = keys.clone();
+            ch_dd.connect_selected_notify(move |dd| {
+                let Some(key) = keys_c.get(dd.selected() as usize) else { return };
+                let loaded = saved_curve(cfg_c.lock().unwrap().get_config(), key);
+                // Swap first: the param spins' handlers write into `curve`.
+                *curve.borrow_mut() = loaded.clone();
+                load_params(&loaded);
+                rebuild_fan_points(&points_box, &curve, &graph);
+            });
+        }
+
+        let save_btn = Button::with_label("Save Fan Curve");
+        save_btn.add_css_class("suggested-action");
+        save_btn.set_halign(gtk4::Align::Center);
+        {
+            let (curve, points_box, graph) = (curve.clone(), points_box.clone(), graph.clone());
+            let cfg_c = self.config_manager.clone();
+            save_btn.connect_clicked(move |btn| {
+                let saved = {
+                    let mut c = curve.borrow_mut();
+                    c.points.sort_by(|a, b| a.temp_celsius.total_cmp(&b.temp_celsius));
+                    c.clone()
+                };
+                rebuild_fan_points(&points_box, &curve, &graph);
+                let mut mgr = cfg_c.lock().unwrap();
+                let curves = &mut mgr.get_config_mut().thermal.fan_curves;
+                curves.retain(|c| c.channel != saved.channel);
+                curves.push(saved);
+                match mgr.save() {
+                    Ok(_) => btn.set_label("✓ Saved"),
+                    Err(e) => { log::error!("Fan curve save: {}", e); btn.set_label("✗ Failed — see log"); }
+                }
+                let bc = btn.clone();
+                glib::timeout_add_seconds_local(2, move || { bc.set_label("Save Fan Curve"); glib::ControlFlow::Break });
+            });
+        }
+        vbox.append(&save_btn);
+
+        let note = Label::new(Some("Duty rises immediately with temperature and falls only after the hysteresis margin. Non-zero duties below the minimum are raised to it."));
+        note.add_css_class("subtitle");
+        note.set_wrap(true);
+        vbox.append(&note);
+
+        frame.set_child(Some(&vbox));
+        frame
+    }
+
+    // ── Thermal limiter ───────────────────────────────────────────────────────────
+
+    fn create_thermal_limiter_section(&self) -> Frame {
+        let frame = Frame::new(Some("Thermal Limiter"));
+        frame.add_css_class("card");
+        let vbox = Box::new(Orientation::Vertical, 10);
+        vbox.set_margin_top(12); vbox.set_margin_bottom(12);
+        vbox.set_margin_start(12); vbox.set_margin_end(12);
+
+        let cfg = self.config_manager.lock().unwrap().get_config().thermal.limiter.clone();
+        let unit = TempUnit::current();
+
+        let enable_row = Box::new(Orientation::Horizontal, 12);
+        enable_row.add_css_class("settings-row");
+        let en_lbl_box = Box::new(Orientation::Vertical, 2);
+        en_lbl_box.set_hexpand(true);
+        let en_title = Label::new(Some("Hold Temperature at Target"));
+        en_title.set_halign(gtk4::Align::Start);
+        let en_sub = Label::new(Some("Lowers max frequency or PL1 just enough to stay at the target; original limits are restored when off or on exit"));
+        en_sub.add_css_class("subtitle"); en_sub.set_halign(gtk4::Align::Start);
+        en_sub.set_wrap(true);
+        en_lbl_box.append(&en_title); en_lbl_box.append(&en_sub);
+        let enable_sw = gtk4::Switch::new();
+        enable_sw.set_active(cfg.enabled);
+        enable_sw.set_valign(gtk4::Align::Center);
+        let cfg_c = self.config_manager.clone();
+        enable_sw.connect_state_set(move |_, state| {
+            let mut c = cfg_c.lock().unwrap();
+            c.get_config_mut().thermal.limiter.enabled = state;
+            let _ = c.save();
+            glib::Propagation::Proceed
+        });
+        enable_row.append(&en_lbl_box);
+        enable_row.append(&enable_sw);
+        vbox.append(&enable_row);
+
+        let params = Grid::new();
+        params.set_row_spacing(8); params.set_column_spacing(12);
+        let add_row = |row: i32, title: &str, widget: &gtk4::Widget| {
+            let lbl = Label::new(Some(title));
+            lbl.set_halign(gtk4::Align::End);
+            params.attach(&lbl, 0, row, 1, 1);
+            params.attach(widget, 1, row, 1, 1);
+            lbl
+        };
+
+        let target_spin = SpinButton::with_range(
+            unit.convert(50.0) as f64, unit.convert(100.0) as f64, 1.0,
+        );
+        target_spin.set_value(unit.convert(cfg.target_celsius) as f64);
+        add_row(0, &format!("Target ({}):", unit.symbol()), target_spin.upcast_ref());
+
+        const ACTUATORS: [(LimiterActuator, &str); 3] = [
+            (LimiterActuator::Auto, "Auto (PL1 if available)"),
+            (LimiterActuator::Frequency, "Max frequency"),
+            (LimiterActuator::PowerLimit, "RAPL PL1"),
+        ];
+        let act_dd = DropDown::new(
+            Some(StringList::new(&ACTUATORS.iter().map(|(_, n)| *n).collect::<Vec<_>>())),
+            None::<gtk4::Expression>,
+        );
+        act_dd.set_selected(ACTUATORS.iter().position(|(a, _)| *a == cfg.actuator).unwrap_or(0) as u32);
+        add_row(1, "Actuator:", act_dd.upcast_ref());
+
+        let gain_spin = |value: f32| {
+            let spin = SpinButton::with_range(0.0, 1.0, 0.005);
+            spin.set_digits(3);
+            spin.set_value(value as f64);
+            spin
+        };
+        let kp_spin = gain_spin(cfg.kp);
+        let ki_spin = gain_spin(cfg.ki);
+        let kd_spin = gain_spin(cfg.kd);
+        let kp_lbl = add_row(2, "", kp_spin.upcast_ref());
+        let ki_lbl = add_row(3, "", ki_spin.upcast_ref());
+        let kd_lbl = add_row(4, "", kd_spin.upcast_ref());
+        let rate_spin = SpinButton::with_range(1.0, 100.0, 1.0);
+        rate_spin.set_value((cfg.max_step_per_second * 100.0) as f64);
+        add_row(5, "Max change (%/s):", rate_spin.upcast_ref());
+        let lead_spin = SpinButton::with_range(0.0, 120.0, 5.0);
+        lead_spin.set_value(cfg.lead_seconds as f64);
+        lead_spin.set_tooltip_text(Some("Act on the temperature forecast this far ahead, capping clocks before the target is reached (0 = current reading)"));
+        add_row(6, "Look-ahead (s):", lead_spin.upcast_ref());
+        // With a look-ahead the gains scale the forecast, not the reading.
+        let word_gains = move |lead: f64| {
+            let temp = if lead > 0.0 { "forecast °C" } else { "°C" };
+            kp_lbl.set_text(&format!("Kp (per {} over target):", temp));
+            ki_lbl.set_text(&format!("Ki (per {}·s over target):", temp));
+            kd_lbl.set_text(&format!("Kd (per {}/s rise):", temp));
+        };
+        word_gains(lead_spin.value());
+        lead_spin.connect_value_changed(move |s| word_gains(s.value()));
+        vbox.append(&params);
+
+        let status_row = Box::new(Orientation::Horizontal, 12);
+        let status_title = Label::new(Some("Output:"));
+        status_title.add_css_class("subtitle");
+        self.limiter_label.set_halign(gtk4::Align::Start);
+        status_row.append(&status_title);
+        status_row.append(&self.limiter_label);
+        vbox.append(&status_row);
+
+        let save_btn = Button::with_label("Save Limiter Settings");
+        save_btn.add_css_class("suggested-action");
+        save_btn.set_halign(gtk4::Align::Center);
+        {
+            let cfg_c = self.config_manager.clone();
+            save_btn.connect_clicked(move |btn| {
+                let mut mgr = cfg_c.lock().unwrap();
+                let lim = &mut mgr.get_config_mut().thermal.limiter;
+                lim.target_celsius = unit.to_celsius(target_spin.value() as f32);
+                lim.actuator = ACTUATORS.get(act_dd.selected() as usize).map(|(a, _)| *a).unwrap_or_default();
+                lim.kp = kp_spin.value() as f32;
+                lim.ki = ki_spin.value() as f32;
+                lim.kd = kd_spin.value() as f32;
+                lim.max_step_per_second = rate_spin.value() as f32 / 100.0;
+                lim.lead_seconds = lead_spin.value() as f32;
+                match mgr.save() {
+                    Ok(_) => btn.set_label("✓ Saved"),
+                    Err(e) => { log::error!("Limiter save: {}", e); btn.set_label("✗ Failed — see log"); }
+                }
+                let bc = btn.clone();
+                glib::timeout_add_seconds_local(2, move || { bc.set_label("Save Limiter Settings"); glib::ControlFlow::Break });
+            });
+        }
+        vbox.append(&save_btn);
+
+        let note = Label::new(Some("When the background service is running it drives fan curves and the limiter from /etc/cpu-power-manager/config.toml instead of this window."));
+        note.add_css_class("subtitle");
+        note.set_wrap(true);
+        vbox.append(&note);
+
+        frame.set_child(Some(&vbox));
+        frame
+    }
+
     // ── Advanced controls ─────────────────────────────────────────────────────────
 
     fn create_advanced_controls(&self) -> Frame {
@@ -755,6 +1371,129 @@ impl AppWindow {
         frame
     }
 
+    // ── Fixed-frequency benchmark mode ────────────────────────────────────────────
+
+    fn create_fixed_freq_section(&self) -> Frame {
+        let frame = Frame::new(Some("Fixed-Frequency Benchmark Mode"));
+        frame.add_css_class("card");
+        let vbox = Box::new(Orientation::Vertical, 10);
+        vbox.set_margin_top(12); vbox.set_margin_bottom(12);
+        vbox.set_margin_start(12); vbox.set_margin_end(12);
+
+        let sub = Label::new(Some(
+            "Pins every core to one frequency with turbo off for reproducible benchmarks. \
+             Everything is restored on unlock or when the app exits.",
+        ));
+        sub.add_css_class("subtitle");
+        sub.set_halign(gtk4::Align::Start);
+        sub.set_wrap(true);
+        vbox.append(&sub);
+
+        let row = |title: &str, subtitle: &str, widget: &gtk4::Widget| {
+            let r = Box::new(Orientation::Horizontal, 12);
+            r.add_css_class("settings-row");
+            let lbl = Box::new(Orientation::Vertical, 2); lbl.set_hexpand(true);
+            let t = Label::new(Some(title)); t.set_halign(gtk4::Align::Start);
+            let s = Label::new(Some(subtitle));
+            s.add_css_class("subtitle"); s.set_halign(gtk4::Align::Start);
+            lbl.append(&t); lbl.append(&s);
+            widget.set_valign(gtk4::Align::Center);
+            r.append(&lbl); r.append(widget);
+            r
+        };
+        let (hw_min, hw_max) = {
+            let cpu = self.cpu_manager.lock().unwrap();
+            (cpu.get_hardware_min_freq(0).unwrap_or(800), cpu.get_hardware_max_freq(0).unwrap_or(4000))
+        };
+        let freq_spin = SpinButton::with_range(hw_min as f64, hw_max as f64, 100.0);
+        freq_spin.set_value(((hw_min + hw_max) / 2 / 100 * 100) as f64);
+        let cstates_sw = Switch::new();
+        let smt_sw = Switch::new();
+        vbox.append(&row("Frequency (MHz)", "Snapped to the nearest frequency the driver offers", freq_spin.upcast_ref()));
+        vbox.append(&row("Disable deep C-states", "Idle states past C1, for steadier wake-up latency", cstates_sw.upcast_ref()));
+        vbox.append(&row("Disable SMT", "Takes sibling threads offline", smt_sw.upcast_ref()));
+
+        let lock_btn = Button::with_label("Lock");
+        lock_btn.add_css_class("suggested-action");
+        lock_btn.set_halign(gtk4::Align::Start);
+        vbox.append(&lock_btn);
+        let status = Label::new(None);
+        status.set_halign(gtk4::Align::Start);
+        status.set_wrap(true);
+        vbox.append(&status);
+        frame.set_child(Some(&vbox));
+
+        // Temperature readings since this window took the lock.
+        let settle: Rc<RefCell<Option<(Stabiliser, std::time::Instant)>>> = Rc::new(RefCell::new(None));
+        {
+            let (cpu_c, pm_c, settle) = (self.cpu_manager.clone(), self.profile_manager.clone(), settle.clone());
+            let (spin, cs, smt, status) = (freq_spin.clone(), cstates_sw.clone(), smt_sw.clone(), status.clone());
+            lock_btn.connect_clicked(move |_| {
+                let (cpu, profiles) = (cpu_c.lock().unwrap(), pm_c.lock().unwrap());
+                let result = if FixedLock::load().is_some() {
+                    fixed_freq::unlock(&profiles, &cpu).map(|_| ())
+                } else {
+                    let opts = LockOptions {
+                        freq_mhz: spin.value() as u32,
+                        disable_deep_cstates: cs.is_active(),
+                        disable_smt: smt.is_active(),
+                    };
+                    overrides::Expiry::process(std::process::id() as i32)
+                        .and_then(|owner| fixed_freq::lock(opts, owner, &profiles, &cpu))
+                        .map(|_| *settle.borrow_mut() = Some((Stabiliser::new(10, 0.5), std::time::Instant::now())))
+                };
+                if let Err(e) = result {
+                    log::error!("Fixed-frequency lock: {:#}", e);
+                    status.set_text(&format!("{:#}", e));
+                    status.add_css_class("status-warning");
+                }
+            });
+        }
+
+        // Follows the lock wherever it was taken (CLI or here), waits for the
+        // temperature to settle and then keeps reading the lock back.
+        let (cpu_c, tm_c) = (self.cpu_manager.clone(), self.thermal_manager.clone());
+        let (btn, controls) = (lock_btn.clone(), [freq_spin.upcast::<gtk4::Widget>(), cstates_sw.upcast(), smt_sw.upcast()]);
+        let mut was_locked = None;
+        glib::timeout_add_seconds_local(1, move || {
+            let lock = FixedLock::load();
+            let mut settling = settle.borrow_mut();
+            if was_locked != Some(lock.is_some()) {
+                was_locked = Some(lock.is_some());
+                btn.set_label(if lock.is_some() { "Unlock" } else { "Lock" });
+                for w in &controls { w.set_sensitive(lock.is_none()); }
+                status.remove_css_class("status-ok"); status.remove_css_class("status-warning");
+                if lock.is_none() {
+                    *settling = None;
+                    status.set_text("");
+                }
+            }
+            let Some(lock) = lock else { return glib::ControlFlow::Continue };
+            let temp = tm_c.lock().unwrap().get_cpu_temperature().ok();
+            if let Some((stab, started)) = settling.as_mut() {
+                let stable = temp.is_some_and(|t| stab.push(t));
+                if !stable && started.elapsed() < std::time::Duration::from_secs(120) {
+                    status.set_text(&format!(
+                        "Locked at {} — waiting for the temperature to settle ({})",
+                        lock.describe(), temp.map(|t| fmt_temp(t, 1)).unwrap_or_else(|| "n/a".into()),
+                    ));
+                    return glib::ControlFlow::Continue;
+                }
+                *settling = None;
+            }
+            if !btn.is_mapped() { return glib::ControlFlow::Continue; }
+            let check = fixed_freq::verify(&cpu_c.lock().unwrap(), lock.options.freq_mhz);
+            status.set_text(&format!(
+                "Locked at {} · {}\n{}",
+                lock.describe(), temp.map(|t| fmt_temp(t, 1)).unwrap_or_else(|| "n/a".into()), check.summary(),
+            ));
+            status.remove_css_class(if check.held() { "status-warning" } else { "status-ok" });
+            status.add_css_class(if check.held() { "status-ok" } else { "status-warning" });
+            glib::ControlFlow::Continue
+        });
+        frame
+    }
+
     // ── AC / Battery auto-switch ──────────────────────────────────────────────────
 
     fn create_ac_battery_section(&self) -> Frame {
@@ -830,10 +1569,188 @@ impl AppWindow {
         vbox.append(&enable_row);
         vbox.append(&ac_row);
         vbox.append(&bat_row);
+        vbox.append(&self.create_battery_rules_editor(&profile_names));
+
+        let hist_title = Label::new(Some("Recent Switches"));
+        hist_title.set_halign(gtk4::Align::Start);
+        self.switch_history_label.add_css_class("subtitle");
+        self.switch_history_label.set_halign(gtk4::Align::Start);
+        self.switch_history_label.set_xalign(0.0);
+        self.switch_history_label.set_wrap(true);
+        vbox.append(&hist_title);
+        vbox.append(&self.switch_history_label);
+        if let Some(row) = self.create_charge_threshold_row() {
+            vbox.append(&row);
+        }
         frame.set_child(Some(&vbox));
         frame
     }
 
+    /// Ordered low-charge rules that override the battery profile.
+    fn create_battery_rules_editor(&self, profile_names: &[String]) -> Box {
+        let vbox = Box::new(Orientation::Vertical, 6);
+        let title = Label::new(Some("Low-Battery Rules"));
+        title.set_halign(gtk4::Align::Start);
+        let sub = Label::new(Some("On battery, the first rule whose charge limit is reached wins — list the lowest first"));
+        sub.add_css_class("subtitle"); sub.set_halign(gtk4::Align::Start);
+        vbox.append(&title);
+        vbox.append(&sub);
+
+        let rules = Rc::new(RefCell::new(
+            self.config_manager.lock().unwrap().get_config().auto_tune.battery_rules.clone(),
+        ));
+        let names: Rc<[String]> = profile_names.into();
+        let rules_box = Box::new(Orientation::Vertical, 6);
+        rebuild_battery_rules(&rules_box, &rules, &names);
+        vbox.append(&rules_box);
+
+        let buttons = Box::new(Orientation::Horizontal, 8);
+        let add_btn = Button::with_label("+ Add Rule");
+        {
+            let (rules, rules_box, names) = (rules.clone(), rules_box.clone(), names.clone());
+            add_btn.connect_clicked(move |_| {
+                {
+                    let mut r = rules.borrow_mut();
+                    let below = r.last().map(|l| (l.below_percent + 20.0).min(100.0)).unwrap_or(20.0);
+                    let profile = names.iter().find(|n| n.eq_ignore_ascii_case("Power Saver"))
+                        .or(names.first()).cloned().unwrap_or_default();
+                    r.push(BatteryRule::new(below, &profile));
+                }
+                rebuild_battery_rules(&rules_box, &rules, &names);
+            });
+        }
+        let save_btn = Button::with_label("Save Rules");
+        save_btn.add_css_class("suggested-action");
+        {
+            let (rules, cfg_c) = (rules.clone(), self.config_manager.clone());
+            save_btn.connect_clicked(move |btn| {
+                let mut mgr = cfg_c.lock().unwrap();
+                mgr.get_config_mut().auto_tune.battery_rules = rules.borrow().clone();
+                match mgr.save() {
+                    Ok(_) => btn.set_label("✓ Saved"),
+                    Err(e) => { log::error!("Battery rules save: {}", e); btn.set_label("✗ Failed — see log"); }
+                }
+                let bc = btn.clone();
+                glib::timeout_add_seconds_local(2, move || { bc.set_label("Save Rules"); glib::ControlFlow::Break });
+            });
+        }
+        buttons.append(&add_btn);
+        buttons.append(&save_btn);
+        vbox.append(&buttons);
+        vbox
+    }
+
+    /// Charge start/end thresholds; `None` without a battery that has them.
+    fn create_charge_threshold_row(&self) -> Option<Box> {
+        let batteries: Vec<_> = PowerSupplyReader::batteries().into_iter()
+            .filter(|b| b.supports_charge_thresholds())
+            .collect();
+        let first = batteries.first()?;
+        let cfg = self.config_manager.lock().unwrap().get_config().battery.clone();
+        let current = if cfg.manage_charge_thresholds {
+            cfg.thresholds()
+        } else {
+            ChargeThresholds {
+                start: first.charge_start_threshold.unwrap_or(0),
+                end: first.charge_end_threshold.unwrap_or(100),
+            }
+        };
+
+        let row = Box::new(Orientation::Vertical, 8);
+        row.add_css_class("settings-row");
+        let title = Label::new(Some("Charge Thresholds"));
+        title.set_halign(gtk4::Align::Start);
+        let sub = Label::new(Some(&format!(
+            "Stop charging early to reduce wear on docked laptops ({}). Kept applied across reboots by the background service",
+            batteries.iter().map(|b| b.name.as_str()).collect::<Vec<_>>().join(", ")
+        )));
+        sub.add_css_class("subtitle"); sub.set_halign(gtk4::Align::Start);
+        sub.set_wrap(true);
+        row.append(&title);
+        row.append(&sub);
+
+        let controls = Box::new(Orientation::Horizontal, 8);
+        let mut preset_names: Vec<&str> = ChargeThresholds::PRESETS.iter().map(|(_, d, _)| *d).collect();
+        preset_names.push("Custom");
+        let preset_dd = DropDown::new(Some(StringList::new(&preset_names)), None::<gtk4::Expression>);
+        preset_dd.set_selected(
+            ChargeThresholds::PRESETS.iter().position(|(_, _, t)| *t == current)
+                .unwrap_or(ChargeThresholds::PRESETS.len()) as u32,
+        );
+        let has_start = first.charge_start_threshold.is_some();
+        let start_spin = SpinButton::with_range(0.0, 99.0, 5.0);
+        start_spin.set_value(current.start as f64);
+        start_spin.set_sensitive(has_start);
+        start_spin.set_tooltip_text(Some("Resume charging below this %"));
+        let end_spin = SpinButton::with_range(1.0, 100.0, 5.0);
+        end_spin.set_value(current.end as f64);
+        end_spin.set_tooltip_text(Some("Stop charging at this %"));
+        {
+            let (start_spin, end_spin) = (start_spin.clone(), end_spin.clone());
+            preset_dd.connect_selected_notify(move |dd| {
+                if let Some((_, _, t)) = ChargeThresholds::PRESETS.get(dd.selected() as usize) {
+                    start_spin.set_value(t.start as f64);
+                    end_spin.set_value(t.end as f64);
+                }
+            });
+        }
+        controls.append(&preset_dd);
+        controls.append(&Label::new(Some("Start")));
+        controls.append(&start_spin);
+        controls.append(&Label::new(Some("End")));
+        controls.append(&end_spin);
+
+        // Behaviour: "(unchanged)" plus whatever the driver offers
+        let mut behaviours = vec!["(unchanged)".to_string()];
+        behaviours.extend(first.charge_behaviours.iter().cloned());
+        let behaviour_dd = DropDown::new(
+            Some(StringList::new(&behaviours.iter().map(|b| b.as_str()).collect::<Vec<_>>())),
+            None::<gtk4::Expression>,
+        );
+        behaviour_dd.set_selected(behaviours.iter().position(|b| *b == cfg.charge_behaviour).unwrap_or(0) as u32);
+        behaviour_dd.set_visible(behaviours.len() > 1);
+        behaviour_dd.set_tooltip_text(Some("charge_behaviour"));
+        controls.append(&behaviour_dd);
+
+        let apply_btn = Button::with_label("Apply");
+        apply_btn.add_css_class("suggested-action");
+        {
+            let cfg_c = self.config_manager.clone();
+            apply_btn.connect_clicked(move |btn| {
+                let t = ChargeThresholds { start: start_spin.value() as u8, end: end_spin.value() as u8 };
+                let behaviour = behaviours.get(behaviour_dd.selected() as usize)
+                    .filter(|_| behaviour_dd.selected() > 0)
+                    .cloned();
+                let set = |mgr: &mut ConfigManager| {
+                    let bat = &mut mgr.get_config_mut().battery;
+                    bat.manage_charge_thresholds = true;
+                    bat.charge_start_threshold = t.start;
+                    bat.charge_end_threshold = t.end;
+                    bat.charge_behaviour = behaviour.clone().unwrap_or_default();
+                    mgr.save()
+                };
+                // The service re-applies from its own config, so save there
+                // too; this window's copy keeps the row showing the same.
+                let result = PowerSupplyReader::apply_charge_settings(t, behaviour.as_deref())
+                    .and_then(|_| set(&mut ConfigManager::service()?))
+                    .and_then(|_| set(&mut cfg_c.lock().unwrap()));
+                match result {
+                    Ok(_) => btn.set_label("✓ Applied"),
+                    Err(e) => {
+                        log::error!("Charge thresholds: {:#}", e);
+                        btn.set_label("✗ Failed — see log");
+                        btn.set_tooltip_text(Some(&format!("{:#}", e)));
+                    }
+                }
+                let bc = btn.clone();
+                glib::timeout_add_seconds_local(3, move || { bc.set_label("Apply"); glib::ControlFlow::Break });
+            });
+        }
+        controls.append(&apply_btn);
+        row.append(&controls);
+        Some(row)
+    }
+
     fn make_profile_row(
         &self,
         title: &str,
@@ -867,6 +1784,564 @@ impl AppWindow {
         row
     }
 
+    // ── Sensors tab ───────────────────────────────────────────────────────────────
+
+    fn create_sensors_tab(&self) -> ScrolledWindow {
+        let scroll = ScrolledWindow::new();
+        scroll.set_vexpand(true);
+        let sensors_box = Box::new(Orientation::Vertical, 12);
+        sensors_box.set_margin_top(12); sensors_box.set_margin_bottom(12);
+        sensors_box.set_margin_start(12); sensors_box.set_margin_end(12);
+        // Enumerate once; the timer only re-reads values into the same labels.
+        let mut devices = HwmonReader::scan();
+        let labels = build_sensors_panel(&sensors_box, &devices);
+        scroll.set_child(Some(&sensors_box));
+
+        let sb = sensors_box.clone();
+        glib::timeout_add_seconds_local(2, move || {
+            if sb.is_mapped() {
+                devices.iter_mut().for_each(|d| d.refresh());
+                update_sensor_labels(&devices, &labels);
+            }
+            glib::ControlFlow::Continue
+        });
+        scroll
+    }
+
+    // ── Thermal tab ───────────────────────────────────────────────────────────────
+
+    fn create_thermal_tab(&self) -> ScrolledWindow {
+        let scroll = ScrolledWindow::new();
+        scroll.set_vexpand(true);
+        let thermal_box = Box::new(Orientation::Vertical, 12);
+        thermal_box.set_margin_top(12); thermal_box.set_margin_bottom(12);
+        thermal_box.set_margin_start(12); thermal_box.set_margin_end(12);
+        thermal_box.append(&self.create_thermal_zones_section());
+        thermal_box.append(&self.create_cooling_devices_section());
+        thermal_box.append(&self.create_emergency_section());
+        scroll.set_child(Some(&thermal_box));
+        scroll
+    }
+
+    // ── Benchmarks tab ────────────────────────────────────────────────────────────
+
+    fn create_benchmarks_tab(&self) -> ScrolledWindow {
+        let scroll = ScrolledWindow::new();
+        scroll.set_vexpand(true);
+        let tab_box = Box::new(Orientation::Vertical, 12);
+        tab_box.set_margin_top(12); tab_box.set_margin_bottom(12);
+        tab_box.set_margin_start(12); tab_box.set_margin_end(12);
+        let frame = Frame::new(Some("Profile Benchmarks"));
+        frame.add_css_class("card");
+        let vbox = Box::new(Orientation::Vertical, 10);
+        vbox.set_margin_top(12); vbox.set_margin_bottom(12);
+        vbox.set_margin_start(12); vbox.set_margin_end(12);
+
+        let hint = Label::new(Some(
+            "Run `sudo cpu-power-manager benchmark` (or `… benchmark -- <command>`) to compare profiles on this machine.",
+        ));
+        hint.add_css_class("subtitle");
+        hint.set_halign(gtk4::Align::Start);
+        hint.set_wrap(true);
+        vbox.append(&hint);
+
+        let row = Box::new(Orientation::Horizontal, 12);
+        row.add_css_class("settings-row");
+        let run_lbl = Label::new(Some("Run"));
+        run_lbl.set_halign(gtk4::Align::Start);
+        let runs_dd = DropDown::new(Some(StringList::new(&[])), None::<gtk4::Expression>);
+        runs_dd.set_hexpand(true);
+        let refresh = Button::with_label("Refresh");
+        row.append(&run_lbl); row.append(&runs_dd); row.append(&refresh);
+        vbox.append(&row);
+
+        let table = Box::new(Orientation::Vertical, 8);
+        vbox.append(&table);
+        frame.set_child(Some(&vbox));
+        tab_box.append(&frame);
+        tab_box.append(&self.create_efficiency_section());
+        scroll.set_child(Some(&tab_box));
+
+        let reports: Rc<RefCell<Vec<BenchmarkReport>>> = Rc::new(RefCell::new(Vec::new()));
+        let (reports_s, table_s) = (reports.clone(), table.clone());
+        runs_dd.connect_selected_notify(move |dd| {
+            rebuild_benchmark_table(&table_s, reports_s.borrow().get(dd.selected() as usize));
+        });
+        // Reload whenever the tab is shown: runs happen from the CLI.
+        let reload = {
+            let (reports, dd, table) = (reports.clone(), runs_dd.clone(), table.clone());
+            move || {
+                let loaded = BenchmarkReport::load_all();
+                if *reports.borrow() == loaded && !loaded.is_empty() { return; }
+                let titles: Vec<String> = loaded.iter().map(|r| r.title()).collect();
+                *reports.borrow_mut() = loaded;
+                dd.set_model(Some(&StringList::new(&titles.iter().map(|t| t.as_str()).collect::<Vec<_>>())));
+                dd.set_sensitive(!titles.is_empty());
+                rebuild_benchmark_table(&table, reports.borrow().first());
+            }
+        };
+        let reload = Rc::new(reload);
+        let r = reload.clone();
+        refresh.connect_clicked(move |_| r());
+        scroll.connect_map(move |_| reload());
+        scroll
+    }
+
+    fn create_efficiency_section(&self) -> Frame {
+        let frame = Frame::new(Some("Frequency Efficiency Curve"));
+        frame.add_css_class("card");
+        let vbox = Box::new(Orientation::Vertical, 10);
+        vbox.set_margin_top(12); vbox.set_margin_bottom(12);
+        vbox.set_margin_start(12); vbox.set_margin_end(12);
+
+        let info = Label::new(None);
+        info.add_css_class("subtitle");
+        info.set_halign(gtk4::Align::Start);
+        info.set_wrap(true);
+        vbox.append(&info);
+
+        // Efficiency and package power against the max frequency step, each
+        // scaled to its own peak.
+        let curve: Rc<RefCell<Option<EfficiencyCurve>>> = Rc::new(RefCell::new(None));
+        let graph = gtk4::DrawingArea::new();
+        graph.set_content_width(600);
+        graph.set_content_height(180);
+        let curve_d = curve.clone();
+        graph.set_draw_func(move |_area, cr, width, height| {
+            // Dracula bg_secondary #1e1f29
+            cr.set_source_rgb(0.118, 0.122, 0.161);
+            let _ = cr.paint();
+            let c = curve_d.borrow();
+            let Some(c) = c.as_ref() else { return };
+            let (Some(first), Some(last)) = (c.points.first(), c.points.last()) else { return };
+            let (w, h) = (width as f64, height as f64 - 14.0);
+            let (lo, hi) = (first.max_freq_mhz as f64, (last.max_freq_mhz as f64).max(first.max_freq_mhz as f64 + 1.0));
+            let x_of = |mhz: u32| 8.0 + (mhz as f64 - lo) / (hi - lo) * (w - 16.0);
+            cr.set_font_size(10.0);
+            let label_every = c.points.len().div_ceil(8).max(1);
+            for (i, p) in c.points.iter().enumerate() {
+                cr.set_source_rgba(0.267, 0.278, 0.353, 0.7);
+                cr.set_line_width(1.0);
+                cr.move_to(x_of(p.max_freq_mhz), 0.0);
+                cr.line_to(x_of(p.max_freq_mhz), h);
+                let _ = cr.stroke();
+                if i % label_every == 0 {
+                    cr.set_source_rgba(0.384, 0.447, 0.643, 0.9);
+                    cr.move_to((x_of(p.max_freq_mhz) - 12.0).max(0.0), h + 11.0);
+                    let _ = cr.show_text(&p.max_freq_mhz.to_string());
+                }
+            }
+            if let Some(s) = c.sweet_spots() {
+                cr.set_line_width(2.0);
+                cr.set_dash(&[4.0, 4.0], 0.0);
+                // Dracula purple #bd93f9 Balanced, cyan #8be9fd Silent
+                for (mhz, (r, g, b)) in [(s.balanced_mhz, (0.741, 0.576, 0.976)), (s.silent_mhz, (0.545, 0.914, 0.992))] {
+                    cr.set_source_rgb(r, g, b);
+                    cr.move_to(x_of(mhz), 0.0);
+                    cr.line_to(x_of(mhz), h);
+                    let _ = cr.stroke();
+                }
+                cr.set_dash(&[], 0.0);
+            }
+            let series = [
+                // Dracula green #50fa7b efficiency, orange #ffb86c power
+                (c.points.iter().map(|p| p.efficiency()).collect::<Vec<_>>(), (0.314, 0.980, 0.482)),
+                (c.points.iter().map(|p| p.package_watts).collect(), (1.0, 0.722, 0.424)),
+            ];
+            for (values, (r, g, b)) in series {
+                let peak = values.iter().flatten().copied().fold(0.0, f64::max);
+                if peak <= 0.0 { continue; }
+                cr.set_source_rgb(r, g, b);
+                cr.set_line_width(2.0);
+                let mut started = false;
+                for (p, v) in c.points.iter().zip(&values) {
+                    let Some(v) = v else { continue };
+                    let (x, y) = (x_of(p.max_freq_mhz), h - 4.0 - v / peak * (h - 8.0));
+                    if started { cr.line_to(x, y); } else { cr.move_to(x, y); started = true; }
+                }
+                let _ = cr.stroke();
+            }
+        });
+        vbox.append(&graph);
+
+        let legend = Label::new(Some(
+            "Green: efficiency (work per joule) · Orange: package power · Dashed: suggested Balanced (purple) and Silent (cyan) max frequency",
+        ));
+        legend.add_css_class("subtitle");
+        legend.set_halign(gtk4::Align::Start);
+        legend.set_wrap(true);
+        vbox.append(&legend);
+
+        let suggestion = Label::new(None);
+        suggestion.set_halign(gtk4::Align::Start);
+        suggestion.set_wrap(true);
+        vbox.append(&suggestion);
+        frame.set_child(Some(&vbox));
+
+        // Calibration runs from the CLI; pick up a new curve when shown.
+        frame.connect_map(move |_| {
+            let loaded = EfficiencyCurve::load();
+            match &loaded {
+                Some(c) => {
+                    info.set_text(&format!(
+                        "{} · swept {} · {} steps, {} threads, turbo {}",
+                        c.machine, c.date(), c.points.len(), c.threads,
+                        c.turbo.map_or("unknown", |t| if t { "on" } else { "off" })
+                    ));
+                    suggestion.set_text(&match c.sweet_spots() {
+                        Some(s) => format!(
+                            "Suggested max frequency: Balanced {} MHz · Silent {} MHz. Save them as custom profiles under Settings to use them.",
+                            s.balanced_mhz, s.silent_mhz
+                        ),
+                        None => "No power readings in this curve; calibrate as root to get suggestions.".to_string(),
+                    });
+                }
+                None => {
+                    info.set_text("Run `sudo cpu-power-manager calibrate` to measure power, temperature and throughput at each max frequency on this machine.");
+                    suggestion.set_text("");
+                }
+            }
+            *curve.borrow_mut() = loaded;
+            graph.queue_draw();
+        });
+        frame
+    }
+
+    fn create_emergency_section(&self) -> Frame {
+        let frame = Frame::new(Some("Emergency Response"));
+        frame.add_css_class("card");
+        let vbox = Box::new(Orientation::Vertical, 10);
+        vbox.set_margin_top(12); vbox.set_margin_bottom(12);
+        vbox.set_margin_start(12); vbox.set_margin_end(12);
+
+        let thermal = self.config_manager.lock().unwrap().get_config().thermal.clone();
+        let em = &thermal.emergency;
+        let unit = TempUnit::current();
+
+        let enable_row = Box::new(Orientation::Horizontal, 12);
+        enable_row.add_css_class("settings-row");
+        let en_lbl_box = Box::new(Orientation::Vertical, 2);
+        en_lbl_box.set_hexpand(true);
+        let en_title = Label::new(Some("Emergency Clamp"));
+        en_title.set_halign(gtk4::Align::Start);
+        let en_sub = Label::new(Some("When the emergency temperature is held for the dwell time: all cores to hardware minimum, turbo off, notify. Restored below the recovery temperature"));
+        en_sub.add_css_class("subtitle"); en_sub.set_halign(gtk4::Align::Start);
+        en_sub.set_wrap(true);
+        en_lbl_box.append(&en_title); en_lbl_box.append(&en_sub);
+        let enable_sw = gtk4::Switch::new();
+        enable_sw.set_active(em.enabled);
+        enable_sw.set_valign(gtk4::Align::Center);
+        let cfg_c = self.config_manager.clone();
+        enable_sw.connect_state_set(move |_, state| {
+            let mut c = cfg_c.lock().unwrap();
+            c.get_config_mut().thermal.emergency.enabled = state;
+            let _ = c.save();
+            glib::Propagation::Proceed
+        });
+        enable_row.append(&en_lbl_box);
+        enable_row.append(&enable_sw);
+        vbox.append(&enable_row);
+
+        let params = Grid::new();
+        params.set_row_spacing(8); params.set_column_spacing(12);
+        let add_row = |row: i32, title: &str, widget: &gtk4::Widget| {
+            let lbl = Label::new(Some(title));
+            lbl.set_halign(gtk4::Align::End);
+            params.attach(&lbl, 0, row, 1, 1);
+            params.attach(widget, 1, row, 1, 1);
+        };
+        let temp_spin = |celsius: f32| {
+            let spin = SpinButton::with_range(unit.convert(50.0) as f64, unit.convert(125.0) as f64, 1.0);
+            spin.set_value(unit.convert(celsius) as f64);
+            spin
+        };
+        let trigger_spin = temp_spin(thermal.emergency_temp_celsius);
+        add_row(0, &format!("Emergency ({}):", unit.symbol()), trigger_spin.upcast_ref());
+        let recovery_spin = temp_spin(em.recovery_celsius);
+        add_row(1, &format!("Recovery ({}):", unit.symbol()), recovery_spin.upcast_ref());
+        let dwell_spin = SpinButton::with_range(0.0, 120.0, 1.0);
+        dwell_spin.set_value(em.dwell_seconds as f64);
+        add_row(2, "Dwell (s):", dwell_spin.upcast_ref());
+        let offline_chk = gtk4::CheckButton::with_label("Take non-boot cores offline");
+        offline_chk.set_active(em.offline_cores);
+        add_row(3, "Cores:", offline_chk.upcast_ref());
+        let cmd_entry = gtk4::Entry::new();
+        cmd_entry.set_text(&em.command);
+        cmd_entry.set_placeholder_text(Some("optional, e.g. systemctl suspend  ($CPM_TEMP = °C)"));
+        cmd_entry.set_hexpand(true);
+        add_row(4, "Command:", cmd_entry.upcast_ref());
+        vbox.append(&params);
+
+        let save_btn = Button::with_label("Save Emergency Settings");
+        save_btn.add_css_class("suggested-action");
+        save_btn.set_halign(gtk4::Align::Center);
+        {
+            let cfg_c = self.config_manager.clone();
+            save_btn.connect_clicked(move |btn| {
+                let trigger = unit.to_celsius(trigger_spin.value() as f32);
+                let recovery = unit.to_celsius(recovery_spin.value() as f32);
+                if recovery >= trigger {
+                    btn.set_label("✗ Recovery must be below emergency");
+                } else {
+                    let mut mgr = cfg_c.lock().unwrap();
+                    let thermal = &mut mgr.get_config_mut().thermal;
+                    thermal.emergency_temp_celsius = trigger;
+                    thermal.emergency.recovery_celsius = recovery;
+                    thermal.emergency.dwell_seconds = dwell_spin.value() as u64;
+                    thermal.emergency.offline_cores = offline_chk.is_active();
+                    thermal.emergency.command = cmd_entry.text().trim().to_string();
+                    match mgr.save() {
+                        Ok(_) => btn.set_label("✓ Saved"),
+                        Err(e) => { log::error!("Emergency save: {}", e); btn.set_label("✗ Failed — see log"); }
+                    }
+                }
+                let bc = btn.clone();
+                glib::timeout_add_seconds_local(2, move || { bc.set_label("Save Emergency Settings"); glib::ControlFlow::Break });
+            });
+        }
+        vbox.append(&save_btn);
+
+        frame.set_child(Some(&vbox));
+        frame
+    }
+
+    fn create_thermal_zones_section(&self) -> Frame {
+        let frame = Frame::new(Some("Thermal Zones"));
+        frame.add_css_class("card");
+        let vbox = Box::new(Orientation::Vertical, 10);
+        vbox.set_margin_top(12); vbox.set_margin_bottom(12);
+        vbox.set_margin_start(12); vbox.set_margin_end(12);
+
+        let zones = self.thermal_manager.lock().unwrap().get_all_zones().unwrap_or_default();
+        let unit = TempUnit::current();
+        if zones.is_empty() {
+            let na = Label::new(Some("No readable thermal zones found."));
+            na.add_css_class("subtitle");
+            na.set_halign(gtk4::Align::Start);
+            vbox.append(&na);
+            frame.set_child(Some(&vbox));
+            return frame;
+        }
+
+        // (zone id, temperature label, trip margin labels, sparkline, history)
+        type ZoneWidgets = (usize, Label, Vec<(usize, Label)>, gtk4::DrawingArea, Rc<RefCell<Vec<f32>>>);
+        let mut widgets: Vec<ZoneWidgets> = Vec::new();
+        for zone in &zones {
+            let zbox = Box::new(Orientation::Vertical, 6);
+            zbox.add_css_class("settings-row");
+
+            let header = Box::new(Orientation::Horizontal, 12);
+            let lbl_box = Box::new(Orientation::Vertical, 2);
+            lbl_box.set_hexpand(true);
+            let t = Label::new(Some(&format!("{} — {}", zone.name, zone.type_name)));
+            t.set_halign(gtk4::Align::Start);
+            let s = Label::new(Some(&format!(
+                "Mode {} · Policy {}",
+                zone.mode.as_deref().unwrap_or("n/a"),
+                zone.policy.as_deref().unwrap_or("n/a"),
+            )));
+            s.add_css_class("subtitle"); s.set_halign(gtk4::Align::Start);
+            lbl_box.append(&t); lbl_box.append(&s);
+
+            let history = Rc::new(RefCell::new(vec![zone.temp_celsius; 60]));
+            let spark = gtk4::DrawingArea::new();
+            spark.set_content_width(240);
+            spark.set_content_height(36);
+            spark.set_valign(gtk4::Align::Center);
+            let hist_d = history.clone();
+            spark.set_draw_func(move |_area, cr, width, height| {
+                let h = hist_d.borrow();
+                // Dracula bg_secondary #1e1f29
+                cr.set_source_rgb(0.118, 0.122, 0.161);
+                let _ = cr.paint();
+                if h.len() < 2 { return; }
+                let lo = h.iter().copied().fold(f32::MAX, f32::min);
+                let hi = h.iter().copied().fold(f32::MIN, f32::max).max(lo + 5.0);
+                let (w, ht) = (width as f64, height as f64);
+                let sp = w / (h.len() - 1) as f64;
+                let y_of = |v: f32| ht - 2.0 - (v - lo) as f64 / (hi - lo) as f64 * (ht - 4.0);
+                // Dracula orange #ffb86c
+                cr.set_source_rgb(1.0, 0.722, 0.424);
+                cr.set_line_width(1.5);
+                cr.move_to(0.0, y_of(h[0]));
+                for (i, &v) in h.iter().enumerate().skip(1) {
+                    cr.line_to(i as f64 * sp, y_of(v));
+                }
+                let _ = cr.stroke();
+            });
+
+            let temp_lbl = Label::new(Some(&fmt_temp(zone.temp_celsius, 1)));
+            temp_lbl.add_css_class("value");
+            temp_lbl.set_width_chars(8);
+            header.append(&lbl_box);
+            header.append(&spark);
+            header.append(&temp_lbl);
+            zbox.append(&header);
+
+            let mut margins = Vec::new();
+            if !zone.trip_points.is_empty() {
+                let grid = Grid::new();
+                grid.set_row_spacing(4); grid.set_column_spacing(16);
+                for (row, trip) in zone.trip_points.iter().enumerate() {
+                    let row = row as i32;
+                    let name = Label::new(Some(&format!("Trip {} · {}", trip.id, trip.trip_type)));
+                    name.set_halign(gtk4::Align::Start);
+                    name.set_hexpand(true);
+                    let temp = match trip.hysteresis_celsius {
+                        Some(hy) if hy > 0.0 => format!("{} (hyst {})", fmt_temp(trip.temp_celsius, 1), unit.format_delta(hy, 1)),
+                        _ => fmt_temp(trip.temp_celsius, 1),
+                    };
+                    let temp = Label::new(Some(&temp));
+                    temp.add_css_class("subtitle");
+                    let margin = Label::new(None);
+                    margin.set_width_chars(10);
+                    set_trip_margin(&margin, trip.margin(zone.temp_celsius));
+                    grid.attach(&name, 0, row, 1, 1);
+                    grid.attach(&temp, 1, row, 1, 1);
+                    grid.attach(&margin, 2, row, 1, 1);
+
+                    if trip.trip_type == "passive" && trip.writable {
+                        let spin = SpinButton::with_range(unit.convert(20.0) as f64, unit.convert(125.0) as f64, 1.0);
+                        spin.set_value(unit.convert(trip.temp_celsius) as f64);
+                        let set_btn = Button::with_label("Set");
+                        let tm_c = self.thermal_manager.clone();
+                        let (zone_id, trip_id, spin_c) = (zone.id, trip.id, spin.clone());
+                        set_btn.connect_clicked(move |btn| {
+                            match tm_c.lock().unwrap().set_trip_point(zone_id, trip_id, unit.to_celsius(spin_c.value() as f32)) {
+                                Ok(_) => btn.set_label("✓"),
+                                Err(e) => { log::error!("{:#}", e); btn.set_label("✗"); }
+                            }
+                            let bc = btn.clone();
+                            glib::timeout_add_seconds_local(2, move || { bc.set_label("Set"); glib::ControlFlow::Break });
+                        });
+                        grid.attach(&spin, 3, row, 1, 1);
+                        grid.attach(&set_btn, 4, row, 1, 1);
+                    }
+                    margins.push((trip.id, margin));
+                }
+                zbox.append(&grid);
+            }
+
+            vbox.append(&zbox);
+            widgets.push((zone.id, temp_lbl, margins, spark, history));
+        }
+
+        // Keep sampling while hidden so the sparklines have no gaps.
+        let tm_c = self.thermal_manager.clone();
+        let vb = vbox.clone();
+        glib::timeout_add_seconds_local(2, move || {
+            let tm = tm_c.lock().unwrap();
+            let visible = vb.is_mapped();
+            for (id, temp_lbl, margins, spark, history) in &widgets {
+                let Ok(zone) = tm.get_zone_info(*id) else { continue };
+                {
+                    let mut h = history.borrow_mut();
+                    h.remove(0); h.push(zone.temp_celsius);
+                }
+                if !visible { continue; }
+                temp_lbl.set_text(&fmt_temp(zone.temp_celsius, 1));
+                for (trip_id, margin) in margins {
+                    if let Some(trip) = zone.trip_points.iter().find(|t| t.id == *trip_id) {
+                        set_trip_margin(margin, trip.margin(zone.temp_celsius));
+                    }
+                }
+                spark.queue_draw();
+            }
+            glib::ControlFlow::Continue
+        });
+
+        frame.set_child(Some(&vbox));
+        frame
+    }
+
+    fn create_cooling_devices_section(&self) -> Frame {
+        let frame = Frame::new(Some("Cooling Devices"));
+        frame.add_css_class("card");
+        let vbox = Box::new(Orientation::Vertical, 10);
+        vbox.set_margin_top(12); vbox.set_margin_bottom(12);
+        vbox.set_margin_start(12); vbox.set_margin_end(12);
+
+        let (devices, zones) = {
+            let tm = self.thermal_manager.lock().unwrap();
+            (tm.get_cooling_devices().unwrap_or_default(), tm.get_all_zones().unwrap_or_default())
+        };
+        if devices.is_empty() {
+            let na = Label::new(Some("No thermal cooling devices found."));
+            na.add_css_class("subtitle");
+            na.set_halign(gtk4::Align::Start);
+            vbox.append(&na);
+            frame.set_child(Some(&vbox));
+            return frame;
+        }
+
+        // (device id, state label, bindings label), refreshed while visible
+        let mut status_labels: Vec<(usize, Label, Label)> = Vec::new();
+        for dev in &devices {
+            let row = Box::new(Orientation::Horizontal, 12);
+            row.add_css_class("settings-row");
+            let lbl_box = Box::new(Orientation::Vertical, 2);
+            lbl_box.set_hexpand(true);
+            let t = Label::new(Some(&format!("cooling_device{} — {}", dev.id, dev.type_name)));
+            t.set_halign(gtk4::Align::Start);
+            let state_lbl = Label::new(None);
+            state_lbl.set_halign(gtk4::Align::Start);
+            let bind_lbl = Label::new(None);
+            bind_lbl.add_css_class("subtitle");
+            bind_lbl.set_halign(gtk4::Align::Start);
+            bind_lbl.set_wrap(true);
+            update_cooling_labels(dev, &zones, &state_lbl, &bind_lbl);
+            lbl_box.append(&t); lbl_box.append(&state_lbl); lbl_box.append(&bind_lbl);
+
+            let spin = SpinButton::with_range(0.0, dev.max_state.max(1) as f64, 1.0);
+            spin.set_value(dev.cur_state as f64);
+            spin.set_valign(gtk4::Align::Center);
+            spin.set_sensitive(dev.max_state > 0);
+            let set_btn = Button::with_label("Set");
+            set_btn.set_valign(gtk4::Align::Center);
+            set_btn.set_sensitive(dev.max_state > 0);
+            let tm_c = self.thermal_manager.clone();
+            let (id, spin_c) = (dev.id, spin.clone());
+            set_btn.connect_clicked(move |btn| {
+                match tm_c.lock().unwrap().set_cooling_state(id, spin_c.value() as u64) {
+                    Ok(_) => btn.set_label("✓"),
+                    Err(e) => { log::error!("{:#}", e); btn.set_label("✗"); }
+                }
+                let bc = btn.clone();
+                glib::timeout_add_seconds_local(2, move || { bc.set_label("Set"); glib::ControlFlow::Break });
+            });
+
+            row.append(&lbl_box);
+            row.append(&spin);
+            row.append(&set_btn);
+            vbox.append(&row);
+            status_labels.push((dev.id, state_lbl, bind_lbl));
+        }
+
+        let note = Label::new(Some("Setting a state is for testing: the kernel's thermal governor may override it on its next poll."));
+        note.add_css_class("subtitle");
+        note.set_wrap(true);
+        vbox.append(&note);
+
+        let tm_c = self.thermal_manager.clone();
+        let vb = vbox.clone();
+        glib::timeout_add_seconds_local(2, move || {
+            if vb.is_mapped() {
+                let tm = tm_c.lock().unwrap();
+                let devices = tm.get_cooling_devices().unwrap_or_default();
+                let zones = tm.get_all_zones().unwrap_or_default();
+                for (id, state_lbl, bind_lbl) in &status_labels {
+                    if let Some(dev) = devices.iter().find(|d| d.id == *id) {
+                        update_cooling_labels(dev, &zones, state_lbl, bind_lbl);
+                    }
+                }
+            }
+            glib::ControlFlow::Continue
+        });
+
+        frame.set_child(Some(&vbox));
+        frame
+    }
+
     // ── System settings tab ───────────────────────────────────────────────────────
 
     fn create_system_settings(&self) -> Frame {
@@ -996,6 +2471,74 @@ impl AppWindow {
         frame
     }
 
+    // ── Profile schedule ──────────────────────────────────────────────────────────
+
+    fn create_schedule_section(&self) -> Frame {
+        let frame = Frame::new(Some("Profile Schedule"));
+        frame.add_css_class("card");
+        let vbox = Box::new(Orientation::Vertical, 8);
+        vbox.set_margin_top(12); vbox.set_margin_bottom(12);
+        vbox.set_margin_start(12); vbox.set_margin_end(12);
+
+        let rules = Rc::new(RefCell::new(
+            self.config_manager.lock().unwrap().get_config().auto_tune.schedule.clone(),
+        ));
+        let names: Rc<[String]> = self.profile_manager.lock().unwrap()
+            .get_profiles().iter().map(|p| p.name.clone()).collect();
+        let rules_box = Box::new(Orientation::Vertical, 6);
+        rebuild_schedule_rules(&rules_box, &rules, &names);
+        vbox.append(&rules_box);
+
+        let buttons = Box::new(Orientation::Horizontal, 8);
+        let add_btn = Button::with_label("+ Add Window");
+        {
+            let (rules, rules_box, names) = (rules.clone(), rules_box.clone(), names.clone());
+            add_btn.connect_clicked(move |_| {
+                let profile = names.iter().find(|n| n.eq_ignore_ascii_case("Silent"))
+                    .or(names.first()).cloned().unwrap_or_default();
+                rules.borrow_mut().push(ScheduleRule {
+                    name: "Night".into(), profile, turbo: None,
+                    days: "daily".into(), start: "22:00".into(), end: "07:00".into(), priority: 0,
+                });
+                rebuild_schedule_rules(&rules_box, &rules, &names);
+            });
+        }
+        let save_btn = Button::with_label("Save Schedule");
+        save_btn.add_css_class("suggested-action");
+        {
+            let (rules, cfg_c) = (rules.clone(), self.config_manager.clone());
+            save_btn.connect_clicked(move |btn| {
+                let rules = rules.borrow().clone();
+                let invalid = rules.iter().enumerate().find_map(|(i, r)| r.validate().err().map(|e| (i, e)));
+                if let Some((i, e)) = invalid {
+                    log::warn!("Schedule window {}: {:#}", i + 1, e);
+                    btn.set_label(&format!("✗ Window {}: {}", i + 1, e));
+                } else {
+                    let mut mgr = cfg_c.lock().unwrap();
+                    mgr.get_config_mut().auto_tune.schedule = rules;
+                    match mgr.save() {
+                        Ok(_) => btn.set_label("✓ Saved"),
+                        Err(e) => { log::error!("Schedule save: {}", e); btn.set_label("✗ Failed — see log"); }
+                    }
+                }
+                let bc = btn.clone();
+                glib::timeout_add_seconds_local(3, move || { bc.set_label("Save Schedule"); glib::ControlFlow::Break });
+            });
+        }
+        buttons.append(&add_btn);
+        buttons.append(&save_btn);
+        vbox.append(&buttons);
+
+        let note = Label::new(Some("Applies while Auto-Switch Profiles is on. Days: daily, weekdays, weekends, mon, tue… or ranges like mon-thu. A window ending at or before its start runs past midnight. Where windows overlap the higher priority wins; low-battery and application rules still take precedence."));
+        note.add_css_class("subtitle");
+        note.set_wrap(true);
+        note.set_xalign(0.0);
+        vbox.append(&note);
+
+        frame.set_child(Some(&vbox));
+        frame
+    }
+
     // ── App settings tab ──────────────────────────────────────────────────────────
 
     fn create_app_settings(&self) -> Frame {
@@ -1109,7 +2652,8 @@ impl AppWindow {
         scale_row.append(&scale_dd);
         vbox.append(&scale_row);
 
-        // Critical temp notification + threshold
+        // Critical temp notification + threshold (spinner in the display unit)
+        let unit = TempUnit::current();
         let notif_row = Box::new(Orientation::Horizontal, 12);
         notif_row.add_css_class("settings-row");
         let n_lbl = Box::new(Orientation::Vertical, 2); n_lbl.set_hexpand(true);
@@ -1119,10 +2663,11 @@ impl AppWindow {
         ns.add_css_class("subtitle"); ns.set_halign(gtk4::Align::Start);
         n_lbl.append(&nt); n_lbl.append(&ns);
         let n_right = Box::new(Orientation::Horizontal, 8);
-        let threshold_spin = SpinButton::with_range(60.0, 110.0, 1.0);
-        threshold_spin.set_value(cfg.thermal.max_temp_celsius as f64);
+        let threshold_spin = SpinButton::with_range(unit.convert(60.0) as f64, unit.convert(110.0) as f64, 1.0);
+        threshold_spin.set_digits(if unit == TempUnit::Kelvin { 1 } else { 0 });
+        threshold_spin.set_value(unit.convert(cfg.thermal.max_temp_celsius) as f64);
         threshold_spin.set_valign(gtk4::Align::Center);
-        let deg_lbl = Label::new(Some("°C"));
+        let deg_lbl = Label::new(Some(unit.symbol()));
         let notif_sw = gtk4::Switch::new();
         notif_sw.set_active(cfg.general.critical_temp_notify);
         notif_sw.set_valign(gtk4::Align::Center);
@@ -1131,14 +2676,14 @@ impl AppWindow {
         notif_sw.connect_state_set(move |_, state| {
             let mut cfg = cfg_c.lock().unwrap();
             cfg.get_config_mut().general.critical_temp_notify = state;
-            cfg.get_config_mut().thermal.max_temp_celsius = spin_c.value() as f32;
+            cfg.get_config_mut().thermal.max_temp_celsius = TempUnit::current().to_celsius(spin_c.value() as f32);
             let _ = cfg.save();
             glib::Propagation::Proceed
         });
         let cfg_c2 = self.config_manager.clone();
         threshold_spin.connect_value_changed(move |spin| {
             let mut cfg = cfg_c2.lock().unwrap();
-            cfg.get_config_mut().thermal.max_temp_celsius = spin.value() as f32;
+            cfg.get_config_mut().thermal.max_temp_celsius = TempUnit::current().to_celsius(spin.value() as f32);
             let _ = cfg.save();
         });
         n_right.append(&threshold_spin);
@@ -1146,12 +2691,213 @@ impl AppWindow {
         n_right.append(&notif_sw);
         notif_row.append(&n_lbl);
         notif_row.append(&n_right);
+
+        // Temperature unit
+        let unit_row = Box::new(Orientation::Horizontal, 12);
+        unit_row.add_css_class("settings-row");
+        let u_lbl = Box::new(Orientation::Vertical, 2); u_lbl.set_hexpand(true);
+        let ut = Label::new(Some("Temperature Unit"));
+        ut.set_halign(gtk4::Align::Start);
+        let us = Label::new(Some("Used for every reading and temperature entry; editors built earlier update after restart"));
+        us.add_css_class("subtitle"); us.set_halign(gtk4::Align::Start);
+        us.set_wrap(true);
+        u_lbl.append(&ut); u_lbl.append(&us);
+        let unit_names: Vec<&str> = TempUnit::ALL.iter().map(|u| u.name()).collect();
+        let unit_dd = DropDown::new(Some(StringList::new(&unit_names)), gtk4::Expression::NONE);
+        unit_dd.set_valign(gtk4::Align::Center);
+        unit_dd.set_selected(TempUnit::ALL.iter().position(|u| *u == unit).unwrap_or(0) as u32);
+        let cfg_unit = self.config_manager.clone();
+        let (spin_u, deg_u) = (threshold_spin.clone(), deg_lbl.clone());
+        unit_dd.connect_selected_notify(move |dd| {
+            let Some(&new_unit) = TempUnit::ALL.get(dd.selected() as usize) else { return };
+            let threshold = cfg_unit.lock().unwrap().get_config().thermal.max_temp_celsius;
+            {
+                let mut cfg = cfg_unit.lock().unwrap();
+                cfg.get_config_mut().general.temperature_unit = new_unit.config_name().to_string();
+                let _ = cfg.save();
+            }
+            // Switch the unit before touching the spinner: its handler converts back.
+            TempUnit::set_current(new_unit);
+            spin_u.set_digits(if new_unit == TempUnit::Kelvin { 1 } else { 0 });
+            spin_u.set_range(new_unit.convert(60.0) as f64, new_unit.convert(110.0) as f64);
+            spin_u.set_value(new_unit.convert(threshold) as f64);
+            deg_u.set_text(new_unit.symbol());
+        });
+        unit_row.append(&u_lbl);
+        unit_row.append(&unit_dd);
+
+        // Smoothing for displayed temperature and power
+        let smooth_row = Box::new(Orientation::Horizontal, 12);
+        smooth_row.add_css_class("settings-row");
+        let sm_lbl = Box::new(Orientation::Vertical, 2); sm_lbl.set_hexpand(true);
+        let smt = Label::new(Some("Reading Smoothing"));
+        smt.set_halign(gtk4::Align::Start);
+        let sms = Label::new(Some("Steadies the dashboard temperature and power; EMA or median over N samples"));
+        sms.add_css_class("subtitle"); sms.set_halign(gtk4::Align::Start);
+        sm_lbl.append(&smt); sm_lbl.append(&sms);
+        let sm_right = Box::new(Orientation::Horizontal, 8);
+        let method = SmoothingMethod::from_config(&cfg.monitoring.smoothing);
+        let smooth_dd = DropDown::new(Some(StringList::new(&["Off", "EMA", "Median"])), gtk4::Expression::NONE);
+        smooth_dd.set_valign(gtk4::Align::Center);
+        smooth_dd.set_selected(SmoothingMethod::ALL.iter().position(|m| *m == method).unwrap_or(0) as u32);
+        let window_spin = SpinButton::with_range(2.0, 30.0, 1.0);
+        window_spin.set_value(cfg.monitoring.smoothing_window as f64);
+        window_spin.set_valign(gtk4::Align::Center);
+        window_spin.set_sensitive(method != SmoothingMethod::None);
+        let cfg_sm = self.config_manager.clone();
+        let ws = window_spin.clone();
+        smooth_dd.connect_selected_notify(move |dd| {
+            let method = SmoothingMethod::ALL.get(dd.selected() as usize).copied().unwrap_or_default();
+            ws.set_sensitive(method != SmoothingMethod::None);
+            let mut cfg = cfg_sm.lock().unwrap();
+            cfg.get_config_mut().monitoring.smoothing = method.config_name().to_string();
+            let _ = cfg.save();
+        });
+        let cfg_sw = self.config_manager.clone();
+        window_spin.connect_value_changed(move |spin| {
+            let mut cfg = cfg_sw.lock().unwrap();
+            cfg.get_config_mut().monitoring.smoothing_window = spin.value() as usize;
+            let _ = cfg.save();
+        });
+        sm_right.append(&smooth_dd);
+        sm_right.append(&window_spin);
+        smooth_row.append(&sm_lbl);
+        smooth_row.append(&sm_right);
+
+        vbox.append(&unit_row);
+        vbox.append(&smooth_row);
         vbox.append(&notif_row);
 
         frame.set_child(Some(&vbox));
         frame
     }
 
+    // ── CPU temperature source ────────────────────────────────────────────────────
+
+    fn create_temp_source_section(&self) -> Frame {
+        let frame = Frame::new(Some("CPU Temperature Source"));
+        frame.add_css_class("card");
+        let vbox = Box::new(Orientation::Vertical, 10);
+        vbox.set_margin_top(12); vbox.set_margin_bottom(12);
+        vbox.set_margin_start(12); vbox.set_margin_end(12);
+
+        let (candidates, current, described) = {
+            let tm = self.thermal_manager.lock().unwrap();
+            (tm.temp_candidates(), tm.source().clone(), tm.describe_source())
+        };
+        let ids: Vec<String> = candidates.iter().map(|c| c.id.clone()).collect();
+
+        // Mode
+        let mode_row = Box::new(Orientation::Horizontal, 12);
+        mode_row.add_css_class("settings-row");
+        let m_lbl = Box::new(Orientation::Vertical, 2);
+        m_lbl.set_hexpand(true);
+        let mt = Label::new(Some("Source"));
+        mt.set_halign(gtk4::Align::Start);
+        let ms = Label::new(Some("Drives the dashboard, notifications and fan curves"));
+        ms.add_css_class("subtitle"); ms.set_halign(gtk4::Align::Start);
+        m_lbl.append(&mt); m_lbl.append(&ms);
+        let modes = ["Auto-detect", "Single sensor", "Maximum of selected", "Average of selected"];
+        let mode_dd = DropDown::new(Some(StringList::new(&modes)), None::<gtk4::Expression>);
+        mode_dd.set_valign(gtk4::Align::Center);
+        mode_dd.set_selected(match current {
+            TempSource::Auto => 0,
+            TempSource::Single { .. } => 1,
+            TempSource::Max { .. } => 2,
+            TempSource::Average { .. } => 3,
+        });
+        mode_row.append(&m_lbl);
+        mode_row.append(&mode_dd);
+        vbox.append(&mode_row);
+
+        // Ranked candidates: the check boxes pick the sensor(s) for non-auto modes
+        let selected: Vec<String> = match &current {
+            TempSource::Auto => Vec::new(),
+            TempSource::Single { sensor } => vec![sensor.clone()],
+            TempSource::Max { sensors } | TempSource::Average { sensors } => sensors.clone(),
+        };
+        let auto_id = candidates.iter().find(|c| c.score > 0 && c.temp_celsius.is_some()).map(|c| c.id.clone());
+        let list = Grid::new();
+        list.set_row_spacing(4); list.set_column_spacing(12);
+        let mut checks: Vec<gtk4::CheckButton> = Vec::new();
+        for (row, c) in candidates.iter().enumerate() {
+            let row = row as i32;
+            let check = gtk4::CheckButton::with_label(&c.label);
+            check.set_active(selected.contains(&c.id));
+            check.set_tooltip_text(Some(&c.id));
+            if let Some(first) = checks.first() {
+                if matches!(current, TempSource::Single { .. }) { check.set_group(Some(first)); }
+            }
+            let temp = Label::new(Some(&c.temp_celsius.map(|t| fmt_temp(t, 1)).unwrap_or_else(|| "--".into())));
+            let mut why = format!("{} · score {}", c.reason, c.score);
+            if auto_id.as_deref() == Some(c.id.as_str()) { why.push_str(" · auto choice"); }
+            let why = Label::new(Some(&why));
+            why.add_css_class("subtitle");
+            why.set_halign(gtk4::Align::Start);
+            why.set_hexpand(true);
+            list.attach(&check, 0, row, 1, 1);
+            list.attach(&temp, 1, row, 1, 1);
+            list.attach(&why, 2, row, 1, 1);
+            checks.push(check);
+        }
+        list.set_sensitive(!matches!(current, TempSource::Auto));
+        vbox.append(&list);
+
+        // Single-sensor mode behaves as radio buttons, set modes as check boxes.
+        {
+            let (list, checks) = (list.clone(), checks.clone());
+            mode_dd.connect_selected_notify(move |dd| {
+                list.set_sensitive(dd.selected() != 0);
+                let first = checks.first().cloned();
+                for c in checks.iter().skip(1) {
+                    c.set_group(if dd.selected() == 1 { first.as_ref() } else { None });
+                }
+            });
+        }
+
+        let status = Label::new(Some(&format!("Current: {}", described)));
+        status.add_css_class("subtitle");
+        status.set_halign(gtk4::Align::Start);
+        status.set_wrap(true);
+        vbox.append(&status);
+
+        let apply_btn = Button::with_label("Apply Temperature Source");
+        apply_btn.add_css_class("suggested-action");
+        apply_btn.set_halign(gtk4::Align::Center);
+        let tm_c = self.thermal_manager.clone();
+        let cfg_c = self.config_manager.clone();
+        apply_btn.connect_clicked(move |btn| {
+            let picked: Vec<String> = checks.iter().zip(&ids)
+                .filter(|(c, _)| c.is_active())
+                .map(|(_, id)| id.clone())
+                .collect();
+            let source = match (mode_dd.selected(), picked.first()) {
+                (0, _) | (_, None) => TempSource::Auto,
+                (1, Some(first)) => TempSource::Single { sensor: first.clone() },
+                (2, _) => TempSource::Max { sensors: picked },
+                _ => TempSource::Average { sensors: picked },
+            };
+            let described = {
+                let mut tm = tm_c.lock().unwrap();
+                tm.set_source(source.clone());
+                tm.describe_source()
+            };
+            status.set_text(&format!("Current: {}", described));
+            let mut c = cfg_c.lock().unwrap();
+            c.get_config_mut().thermal.cpu_temp_source = source;
+            match c.save() {
+                Ok(_) => btn.set_label("✓ Applied"),
+                Err(e) => { log::error!("Temperature source save: {}", e); btn.set_label("✗ Failed — see log"); }
+            }
+            let bc = btn.clone();
+            glib::timeout_add_seconds_local(2, move || { bc.set_label("Apply Temperature Source"); glib::ControlFlow::Break });
+        });
+        vbox.append(&apply_btn);
+
+        frame.set_child(Some(&vbox));
+        frame
+    }
+
     // ── Custom profiles ───────────────────────────────────────────────────────────
 
     fn create_custom_profiles_section(&self) -> Frame {
@@ -1184,6 +2930,17 @@ impl AppWindow {
         turbo_sw.set_valign(gtk4::Align::Center);
         let turbo_lbl = Label::new(Some("Turbo"));
 
+        // Optional RAPL limits — 0 leaves the firmware value untouched
+        let pl1_spin = SpinButton::with_range(0.0, 250.0, 1.0);
+        pl1_spin.set_tooltip_text(Some("PL1 package power limit in watts (0 = unchanged)"));
+        pl1_spin.set_valign(gtk4::Align::Center);
+        let pl2_spin = SpinButton::with_range(0.0, 250.0, 1.0);
+        pl2_spin.set_tooltip_text(Some("PL2 package power limit in watts (0 = unchanged)"));
+        pl2_spin.set_valign(gtk4::Align::Center);
+        let pl_available = !RaplZone::packages().is_empty();
+        pl1_spin.set_sensitive(pl_available);
+        pl2_spin.set_sensitive(pl_available);
+
         let save_btn = Button::with_label("Save Profile");
         save_btn.add_css_class("suggested-action");
 
@@ -1192,9 +2949,21 @@ impl AppWindow {
         let govs_c  = gov_names.clone();
         let gov_c   = gov_dd.clone();
         let turbo_c = turbo_sw.clone();
+        let pl1_c   = pl1_spin.clone();
+        let pl2_c   = pl2_spin.clone();
         save_btn.connect_clicked(move |btn| {
             let name = name_c.text().to_string().trim().to_string();
             if name.is_empty() { return; }
+            let pl1_watts = Some(pl1_c.value() as u32).filter(|&w| w > 0);
+            let pl2_watts = Some(pl2_c.value() as u32).filter(|&w| w > 0);
+            if let (Some(pl1), Some(pl2)) = (pl1_watts, pl2_watts) {
+                if pl1 > pl2 {
+                    btn.set_label("✗ PL1 must not exceed PL2");
+                    let bc = btn.clone();
+                    glib::timeout_add_seconds_local(3, move || { bc.set_label("Save Profile"); glib::ControlFlow::Break });
+                    return;
+                }
+            }
             let idx = gov_c.selected() as usize;
             let governor = govs_c.get(idx).cloned().unwrap_or_else(|| "powersave".into());
             let turbo_mode = if turbo_c.is_active() {
@@ -1211,6 +2980,8 @@ impl AppWindow {
                 max_freq_mhz: None,
                 epp: None,
                 epb: None,
+                pl1_watts,
+                pl2_watts,
             };
             let mut cfg = cfg_c.lock().unwrap();
             cfg.get_config_mut().custom_profiles.retain(|p| p.name != name);
@@ -1228,6 +2999,10 @@ impl AppWindow {
         form.append(&gov_dd);
         form.append(&turbo_lbl);
         form.append(&turbo_sw);
+        form.append(&Label::new(Some("PL1")));
+        form.append(&pl1_spin);
+        form.append(&Label::new(Some("PL2")));
+        form.append(&pl2_spin);
         form.append(&save_btn);
         vbox.append(&form);
 
@@ -1268,7 +3043,7 @@ impl AppWindow {
         vbox.set_margin_start(12); vbox.set_margin_end(12);
 
         let cpu = self.cpu_manager.lock().unwrap();
-        let items = vec![
+        let mut items = vec![
             ("CPU Model",  self.cpu_info.model.clone()),
             ("Vendor",     self.cpu_info.vendor.clone()),
             ("Cores",      self.cpu_info.core_count.to_string()),
@@ -1280,8 +3055,22 @@ impl AppWindow {
                 let tm = self.thermal_manager.lock().unwrap();
                 tm.get_zone_count().to_string()
             }),
-            ("RAPL Available", RaplTracker::is_available().to_string()),
+            ("RAPL Domains", {
+                let rapl = self.rapl_tracker.lock().unwrap();
+                if rapl.domains().is_empty() {
+                    "None".to_string()
+                } else {
+                    rapl.domains().iter().map(|d| d.name.as_str()).collect::<Vec<_>>().join(", ")
+                }
+            }),
         ];
+        if let Some(amd) = HwmonReader::get_amd_temps() {
+            let fmt = |t: Option<f32>| t.map(|t| fmt_temp(t, 1)).unwrap_or_else(|| "--".into());
+            items.push(("AMD Temp Sensor", format!(
+                "{} · Tctl {} · Tdie {} · {} CCD(s)",
+                amd.driver, fmt(amd.tctl), fmt(amd.tdie), amd.ccds.len()
+            )));
+        }
 
         for (label, value) in items {
             let row = Box::new(Orientation::Horizontal, 12);
@@ -1297,6 +3086,146 @@ impl AppWindow {
 
     // ── Update loop ───────────────────────────────────────────────────────────────
 
+    /// Pushes `overrides::presets()[i]`, or clears every override with
+    /// `None`. The rule timer (or the background service) applies the result
+    /// and restores the base state afterwards.
+    pub fn override_handler(&self) -> impl Fn(Option<usize>) + 'static {
+        let cpu_manager = self.cpu_manager.clone();
+        let profile_manager = self.profile_manager.clone();
+        move |preset| {
+            let (cpu, profiles) = (cpu_manager.lock().unwrap(), profile_manager.lock().unwrap());
+            let result = match preset {
+                Some(i) => {
+                    let (label, o) = overrides::presets().swap_remove(i);
+                    overrides::push(o, &profiles, &cpu, false).map(|id| log::info!("Override #{}: {}", id, label))
+                }
+                None => overrides::remove(None, &profiles, &cpu, false).map(|n| log::info!("Cleared {} override(s)", n)),
+            };
+            if let Err(e) = result {
+                log::warn!("Override failed: {:#}", e);
+            }
+        }
+    }
+
+    /// Rule engine: AC/battery switching, schedules, app and thermal rules,
+    /// with temporary overrides on top. Power-source changes arrive as events and are drained often, so a
+    /// switch lands almost immediately.
+    fn setup_rule_engine(&self) {
+        let debounce = self.config_manager.lock().unwrap().get_config().auto_tune.power_source_debounce_ms;
+        let events = PowerSourceWatcher::spawn(std::time::Duration::from_millis(debounce));
+        let cpu_manager = self.cpu_manager.clone();
+        let profile_manager = self.profile_manager.clone();
+        let config_manager = self.config_manager.clone();
+        let thermal_manager = self.thermal_manager.clone();
+        let cpu_usage_history = self.cpu_usage_history.clone();
+        let active_profile_label = self.active_profile_label.clone();
+        let last_auto_switch = self.last_auto_switch.clone();
+        let history_label = self.switch_history_label.clone();
+        let shared_on_ac = self.on_ac.clone();
+        let mut history = SwitchHistory::default();
+        let mut engine = RuleEngine::new();
+        let mut override_watcher = OverrideWatcher::default();
+        let mut resume = ResumeDetector::default();
+        let mut service_seen = None;
+        let mut on_ac = None;
+        let mut charge = None;
+        let mut temp = None;
+        let mut ticks: u32 = 0;
+
+        glib::timeout_add_local(std::time::Duration::from_millis(250), move || {
+            // Firmware may reset CPU settings across suspend, and schedule
+            // windows may have passed: re-apply after a resume.
+            let resumed = resume.check();
+            if resumed {
+                log::info!("Resumed from suspend, re-evaluating rules");
+                *last_auto_switch.lock().unwrap() = None;
+            }
+            let mut source_changed = resumed;
+            while let Ok(event) = events.try_recv() {
+                on_ac = Some(event.on_ac);
+                *shared_on_ac.lock().unwrap() = on_ac;
+                source_changed = true;
+            }
+            let rules = config_manager.lock().unwrap().get_config().effective_rules();
+            // Temperature once a second, charge (which moves slowly) every 10 s.
+            if ticks.is_multiple_of(4) {
+                temp = thermal_manager.lock().unwrap().get_cpu_temperature().ok();
+            }
+            if source_changed || ticks.is_multiple_of(40) {
+                let bat = on_ac.map_or_else(PowerSupplyReader::read, PowerSupplyReader::read_with_source);
+                charge = bat.present.then_some(bat.charge_percent);
+            }
+            ticks = ticks.wrapping_add(1);
+
+            let inputs = Inputs {
+                on_ac,
+                battery_percent: charge,
+                temp_celsius: temp,
+                load_percent: cpu_usage_history.lock().unwrap().last().copied(),
+                now: chrono::Local::now().naive_local(),
+            };
+            // The service applies the rules from its own config; show what it
+            // did and the notifications it can't deliver itself.
+            if crate::daemon::is_running() {
+                if let Some(status) = ServiceStatus::load() {
+                    for notice in status.new_notices(&mut service_seen) {
+                        notice.show();
+                    }
+                    if let Some(profile) = &status.profile {
+                        active_profile_label.set_text(profile);
+                    }
+                    let why = status.applied.as_ref().map(|_| format!("Why: {} (background service)", status.reason));
+                    active_profile_label.set_tooltip_text(why.as_deref());
+                }
+                // Re-apply our own rules if the service stops.
+                *last_auto_switch.lock().unwrap() = None;
+                return glib::ControlFlow::Continue;
+            }
+            let eval = engine.evaluate(&rules, &inputs);
+            for fired in &eval.fired {
+                fired.notify();
+                fired.run_command();
+            }
+
+            // Temporary overrides sit on top of the rules.
+            let stack = {
+                let cpu = cpu_manager.lock().unwrap();
+                let released = fixed_freq::recover_stale(&profile_manager.lock().unwrap(), &cpu);
+                let (stack, restored) = override_watcher.update(&cpu);
+                if restored || released {
+                    *last_auto_switch.lock().unwrap() = None;
+                }
+                stack
+            };
+            let target = stack.overlay(eval.target, chrono::Local::now().timestamp());
+            let baseline = config_manager.lock().unwrap().get_config().baseline_profile(on_ac);
+
+            let mut last = last_auto_switch.lock().unwrap();
+            let target = auto_tune::restore_baseline(target, last.as_ref(), baseline.as_deref());
+            let Some(selection) = target else {
+                *last = None;
+                return glib::ControlFlow::Continue;
+            };
+            if last.as_ref().is_some_and(|l| l.same_target(&selection)) {
+                return glib::ControlFlow::Continue;
+            }
+            active_profile_label.set_tooltip_text(Some(&format!("Why: {}", selection.reason)));
+            match selection.apply(&profile_manager.lock().unwrap(), &cpu_manager.lock().unwrap()) {
+                Ok(profile) => {
+                    if let Some(profile) = profile {
+                        active_profile_label.set_text(&profile.name);
+                    }
+                    log::info!("Rules applied {} ({})", selection.summary(), selection.reason);
+                    history.record(&selection);
+                    history_label.set_text(&history.lines().take(5).collect::<Vec<_>>().join("\n"));
+                }
+                Err(e) => log::warn!("Applying rules failed: {:#}", e),
+            }
+            *last = Some(selection);
+            glib::ControlFlow::Continue
+        });
+    }
+
     fn setup_updates(&self) {
         let freq_label    = self.freq_label.clone();
         let usage_label   = self.usage_label.clone();
@@ -1304,27 +3233,46 @@ impl AppWindow {
         let governor_label = self.governor_label.clone();
         let turbo_label   = self.turbo_label.clone();
         let power_label   = self.power_label.clone();
+        let power_detail_label = self.power_detail_label.clone();
         let battery_label = self.battery_label.clone();
         let fan_label     = self.fan_label.clone();
         let cpu_manager   = self.cpu_manager.clone();
         let thermal_manager = self.thermal_manager.clone();
-        let profile_manager = self.profile_manager.clone();
         let config_manager  = self.config_manager.clone();
         let cpu_usage_history = self.cpu_usage_history.clone();
         let cpu_usage_area    = self.cpu_usage_area.clone();
         let cpu_usage_tracker = self.cpu_usage_tracker.clone();
         let per_core_tracker  = self.per_core_tracker.clone();
         let rapl_tracker      = self.rapl_tracker.clone();
-        let active_profile_label = self.active_profile_label.clone();
-        let last_ac_status    = self.last_ac_status.clone();
+        let fan_controller    = self.fan_controller.clone();
+        let thermal_limiter   = self.thermal_limiter.clone();
+        let emergency_guard   = self.emergency_guard.clone();
+        let limiter_label     = self.limiter_label.clone();
+        let last_auto_switch  = self.last_auto_switch.clone();
+        let on_ac             = self.on_ac.clone();
 
         // Clone self fields needed for per-core rebuild
         let per_core_box  = self.per_core_box.clone();
+        let battery_box   = self.battery_box.clone();
+        // Sampled every BATTERY_REFRESH_TICKS, so this spans about a minute
+        let mut battery_estimator = BatteryEstimator::new(6);
+        let mut battery_source = None;
         let cpu_manager2  = self.cpu_manager.clone();
         let thermal_manager2 = self.thermal_manager.clone();
         let per_core_tracker2 = self.per_core_tracker.clone();
         let cpu_info_core_count = self.cpu_info.core_count;
 
+        let mut temp_smoother  = Smoother::default();
+        let throttle_label = self.throttle_label.clone();
+        let mut forecaster = ThrottleForecaster::new(
+            std::time::Duration::from_secs(
+                self.config_manager.lock().unwrap().get_config().thermal.forecast_window_seconds.max(5),
+            ),
+            Vec::new(),
+        );
+        let mut tick: u64 = 0;
+        let mut power_smoother = Smoother::default();
+
         glib::timeout_add_seconds_local(1, move || {
             let cpu_mgr = cpu_manager.lock().unwrap();
 
@@ -1346,25 +3294,103 @@ impl AppWindow {
             cpu_usage_area.queue_draw();
 
             // Temperature
-            if let Ok(temp) = thermal_manager.lock().unwrap().get_cpu_temperature() {
-                let css = if temp < 60.0 { "temp-normal" }
-                    else if temp < 75.0 { "temp-warm" }
-                    else if temp < 85.0 { "temp-hot" }
+            let (temp, temp_source) = {
+                let tm = thermal_manager.lock().unwrap();
+                (tm.get_cpu_temperature(), tm.describe_source())
+            };
+            temp_label.set_tooltip_text(Some(&temp_source));
+            let cfg = config_manager.lock().unwrap().get_config().clone();
+            let smoothing = SmoothingMethod::from_config(&cfg.monitoring.smoothing);
+            temp_smoother.configure(smoothing, cfg.monitoring.smoothing_window);
+            power_smoother.configure(smoothing, cfg.monitoring.smoothing_window);
+            // Time-to-throttle: trips can be moved at runtime, so re-read them now and then.
+            if tick.is_multiple_of(30) {
+                let mut thresholds = thermal_manager.lock().unwrap().throttle_thresholds();
+                thresholds.extend(cfg.thermal.app_thresholds());
+                forecaster.set_thresholds(thresholds);
+            }
+            tick += 1;
+            if let Ok(t) = temp { forecaster.push(t); }
+            match forecaster.forecast() {
+                Some(fc) => {
+                    throttle_label.set_text(&fc.summary());
+                    throttle_label.set_tooltip_text(Some(&format!(
+                        "Heating at {:+.2} {}/s over the last {} s; threshold {}",
+                        TempUnit::current().delta_from_celsius(fc.rate_celsius_per_sec),
+                        TempUnit::current().symbol(),
+                        cfg.thermal.forecast_window_seconds,
+                        fmt_temp(fc.threshold.temp_celsius, 0),
+                    )));
+                }
+                None => throttle_label.set_text(""),
+            }
+            if let Ok(temp) = temp {
+                // Display the smoothed value; alert on the raw one.
+                let shown = temp_smoother.push(temp);
+                let css = if shown < 60.0 { "temp-normal" }
+                    else if shown < 75.0 { "temp-warm" }
+                    else if shown < 85.0 { "temp-hot" }
                     else { "temp-critical" };
-                temp_label.set_text(&format!("{:.1}°C", temp));
+                temp_label.set_text(&fmt_temp(shown, 1));
                 for c in &["temp-normal","temp-warm","temp-hot","temp-critical"] { temp_label.remove_css_class(c); }
                 temp_label.add_css_class(css);
+            }
 
-                // Critical temp notification
+            // Fan curves, emergency clamp and thermal limiter — left to the background service when
+            // it runs; hand channels and limits back when disabled
+            {
                 let cfg = config_manager.lock().unwrap().get_config().clone();
-                if cfg.general.critical_temp_notify && temp >= cfg.thermal.max_temp_celsius {
-                    let _ = notify_rust::Notification::new()
-                        .summary("CPU Temperature Critical!")
-                        .body(&format!("CPU is at {:.1}°C — consider switching to Power Saver profile.", temp))
-                        .icon("dialog-warning")
-                        .timeout(notify_rust::Timeout::Milliseconds(5000))
-                        .show();
+                let service = crate::daemon::is_running();
+                let temp = thermal_manager.lock().unwrap().get_cpu_temperature().ok();
+                let mut fans = fan_controller.lock().unwrap();
+                if !service && cfg.thermal.fan_control_enabled && !cfg.thermal.fan_curves.is_empty() {
+                    if let Some(temp) = temp {
+                        fans.update(&cfg.thermal.fan_curves, temp);
+                    }
+                } else {
+                    fans.release();
+                }
+                let mut guard = emergency_guard.lock().unwrap();
+                let mut em_cfg = cfg.thermal.emergency.clone();
+                em_cfg.enabled &= !service;
+                // A fixed-frequency lock pauses the limiter, but a thermal
+                // emergency still clamps; say so, since the lock no longer holds.
+                let fixed = FixedLock::load();
+                if let Some(temp) = temp {
+                    if let Some(event) = guard.update(&em_cfg, cfg.thermal.emergency_temp_celsius, temp, &cpu_mgr) {
+                        if let (EmergencyEvent::Triggered { .. }, Some(lock)) = (event, &fixed) {
+                            log::warn!("Thermal emergency overrides the fixed-frequency lock ({})", lock.describe());
+                        }
+                        event.notify();
+                    }
                 }
+                let mut limiter = thermal_limiter.lock().unwrap();
+                let mut lim_cfg = cfg.thermal.limiter.clone();
+                lim_cfg.enabled &= !service;
+                // The emergency clamp wins; the limiter resumes once it is lifted.
+                let lead = lim_cfg.lead_seconds;
+                let lim_temp = temp
+                    .filter(|_| !guard.is_active() && fixed.is_none())
+                    .map(|t| forecaster.predict(lead).unwrap_or(t).max(t));
+                let limiting = limiter.is_some();
+                let out = ThermalLimiter::drive(&mut limiter, &lim_cfg, lim_temp, &cpu_mgr);
+                // Stopping put back the limits from when the limiter started;
+                // the rules and overrides re-apply what they select now.
+                if limiting && limiter.is_none() {
+                    *last_auto_switch.lock().unwrap() = None;
+                }
+                limiter_label.set_text(&match out {
+                    Some(o) => format!(
+                        "{} (target {}) → {:.0}% ({:.0} {})",
+                        fmt_temp(o.temp_celsius, 1), fmt_temp(o.target_celsius, 0),
+                        o.output * 100.0, o.value, o.unit
+                    ),
+                    None if service => "Handled by background service".to_string(),
+                    None if guard.is_active() => "Paused — thermal emergency".to_string(),
+                    None if fixed.is_some() && lim_cfg.enabled => "Paused — fixed-frequency lock".to_string(),
+                    None if cfg.thermal.limiter.enabled => "Unavailable — see log".to_string(),
+                    None => "Inactive".to_string(),
+                });
             }
 
             // Governor + Turbo
@@ -1375,56 +3401,55 @@ impl AppWindow {
                 turbo_label.add_css_class(if turbo { "status-ok" } else { "status-warning" });
             }
 
-            // RAPL power
-            if let Some(watts) = rapl_tracker.lock().unwrap().get_power_w() {
-                power_label.set_text(&format!("{:.1} W", watts));
-            } else if !RaplTracker::is_available() {
-                power_label.set_text("N/A");
+            // RAPL power — total of all packages plus per-domain breakdown
+            {
+                let mut rapl = rapl_tracker.lock().unwrap();
+                if rapl.domains().is_empty() {
+                    power_label.set_text("N/A");
+                } else if let Some(watts) = rapl.get_power_w() {
+                    power_label.set_text(&format!("{:.1} W", power_smoother.push(watts)));
+                    power_detail_label.set_text(&format_power_breakdown(rapl.last_breakdown()));
+                }
             }
 
-            // Battery
-            let bat = PowerSupplyReader::read();
-            if bat.present {
-                let charge_str = if let Some(w) = bat.power_now_w {
-                    format!("{:.0}% · {} · {:.1}W", bat.charge_percent, bat.status, w)
+            // Battery — right after a power-source event, otherwise every
+            // BATTERY_REFRESH_TICKS for the charge and power draw
+            let source = *on_ac.lock().unwrap();
+            if battery_source != Some(source) || tick.is_multiple_of(BATTERY_REFRESH_TICKS) {
+                battery_source = Some(source);
+                let bat = source.map_or_else(PowerSupplyReader::read, PowerSupplyReader::read_with_source);
+                if bat.present {
+                    let eta = battery_estimator.update(&bat);
+                    let mut charge_str = format!("{:.0}% · {}", bat.charge_percent, bat.status);
+                    if let Some(w) = bat.power_now_w {
+                        charge_str.push_str(&format!(" · {:.1}W", w));
+                    }
+                    if let Some((secs, charging)) = eta {
+                        charge_str.push_str(&format!(" · {} {}", format_duration(secs), if charging { "to full" } else { "left" }));
+                    }
+                    battery_label.set_text(&charge_str);
+                    if battery_box.is_mapped() {
+                        rebuild_battery_panel(&battery_box, &bat, eta);
+                    }
                 } else {
-                    format!("{:.0}% · {}", bat.charge_percent, bat.status)
-                };
-                battery_label.set_text(&charge_str);
-            } else {
-                battery_label.set_text(if bat.on_ac { "AC Power" } else { "No Battery" });
+                    battery_label.set_text(if bat.on_ac { "AC Power" } else { "No Battery" });
+                }
             }
 
-            // Fan
-            if let Some(rpm) = HwmonReader::get_fan_rpm() {
+            // Fan — first spinning fan on the card, every fan in the tooltip
+            let fans = HwmonReader::get_fans();
+            if let Some((_, _, rpm)) = fans.iter().find(|(_, _, rpm)| *rpm > 0) {
                 fan_label.set_text(&format!("{} RPM", rpm));
             } else {
                 fan_label.set_text("N/A");
             }
-
-            // AC/Battery auto-switch
-            {
-                let cfg = config_manager.lock().unwrap().get_config().clone();
-                if cfg.auto_tune.enabled {
-                    let on_ac = PowerSupplyReader::read().on_ac;
-                    let mut last = last_ac_status.lock().unwrap();
-                    if *last != Some(on_ac) {
-                        *last = Some(on_ac);
-                        let profile_name = if on_ac { &cfg.auto_tune.ac_profile } else { &cfg.auto_tune.battery_profile };
-                        let pm = profile_manager.lock().unwrap();
-                        if let Some(profile) = pm.get_profiles().iter().find(|p| p.name.to_lowercase() == profile_name.to_lowercase()).cloned() {
-                            drop(pm);
-                            match profile.apply(&cpu_mgr) {
-                                Ok(_) => {
-                                    active_profile_label.set_text(&profile.name);
-                                    log::info!("Auto-switched to {} profile (AC: {})", profile.name, on_ac);
-                                }
-                                Err(e) => log::warn!("Auto-switch failed: {}", e),
-                            }
-                        }
-                    }
-                }
-            }
+            let mut fan_tip: Vec<String> = fans.iter()
+                .map(|(dev, label, rpm)| format!("{} {}: {} RPM", dev, label, rpm))
+                .collect();
+            fan_tip.extend(fan_controller.lock().unwrap().duties().iter()
+                .map(|(key, duty)| format!("{}: {}% (curve)", key, duty)));
+            let fan_tip = fan_tip.join("\n");
+            fan_label.set_tooltip_text(if fan_tip.is_empty() { None } else { Some(&fan_tip) });
 
             drop(cpu_mgr);
 
@@ -1437,6 +3462,7 @@ impl AppWindow {
                     per_core_box.remove(&child);
                 }
                 let core_temps = HwmonReader::get_per_core_temps();
+                let ccd_temps = if core_temps.is_empty() { HwmonReader::get_ccd_temps_by_cpu() } else { Vec::new() };
                 let grid = Grid::new();
                 grid.set_row_spacing(4); grid.set_column_spacing(8);
 
@@ -1476,9 +3502,14 @@ impl AppWindow {
 
                         // Per-core temp
                         if let Some(&(_, temp)) = core_temps.iter().find(|(id, _)| *id == status.core_id) {
-                            let t = Label::new(Some(&format!("{:.0}°C", temp)));
+                            let t = Label::new(Some(&fmt_temp(temp, 0)));
                             t.add_css_class("subtitle"); t.set_width_chars(6);
                             row_box.append(&t);
+                        } else if let Some(&(_, ccd, temp)) = ccd_temps.iter().find(|(cpu, _, _)| *cpu == status.core_id) {
+                            let t = Label::new(Some(&format!("CCD{} {}", ccd, fmt_temp(temp, 0))));
+                            t.set_tooltip_text(Some(&format!("Tccd{} — shared by every core on this CCD", ccd)));
+                            t.add_css_class("subtitle"); t.set_width_chars(11);
+                            row_box.append(&t);
                         }
 
                         // Online toggle
@@ -1511,6 +3542,430 @@ impl AppWindow {
     pub fn window_handle(&self) -> gtk4::ApplicationWindow { self.window.clone() }
 }
 
+/// Pack totals, ETA and charge thresholds, then a line per battery.
+fn rebuild_battery_panel(container: &Box, bat: &BatteryInfo, eta: Option<(f32, bool)>) {
+    while let Some(child) = container.first_child() {
+        container.remove(&child);
+    }
+    let wh = |v: Option<f32>| v.map(|v| format!("{:.1} Wh", v)).unwrap_or_else(|| "--".into());
+    let pct = |v: Option<f32>| v.map(|v| format!("{:.0}%", v)).unwrap_or_else(|| "--".into());
+    let grid = Grid::new();
+    grid.set_row_spacing(4); grid.set_column_spacing(16);
+    let add = |row: i32, col: i32, key: &str, value: String| {
+        let k = Label::new(Some(key));
+        k.add_css_class("subtitle"); k.set_halign(gtk4::Align::Start);
+        let v = Label::new(Some(&value));
+        v.set_halign(gtk4::Align::Start);
+        grid.attach(&k, col * 2, row, 1, 1);
+        grid.attach(&v, col * 2 + 1, row, 1, 1);
+    };
+    add(0, 0, "Charge", format!("{:.0}% · {}", bat.charge_percent, bat.status));
+    add(0, 1, "Power", bat.power_now_w.map(|w| format!("{:.1} W", w)).unwrap_or_else(|| "--".into()));
+    add(1, 0, match eta { Some((_, true)) => "Time to full", _ => "Time to empty" },
+        eta.map(|(s, _)| format_duration(s)).unwrap_or_else(|| "--".into()));
+    add(1, 1, "Health", pct(bat.health_percent()));
+    add(2, 0, "Energy", format!("{} / {}", wh(bat.energy_now_wh), wh(bat.energy_full_wh)));
+    add(2, 1, "Design", wh(bat.energy_full_design_wh));
+    container.append(&grid);
+
+    for b in &bat.batteries {
+        let ident: Vec<&str> = [b.manufacturer.as_deref(), b.model.as_deref(), b.technology.as_deref()]
+            .into_iter().flatten().collect();
+        let mut parts = vec![format!("{} {}", b.name, ident.join(" "))];
+        if let Some(c) = b.capacity_percent { parts.push(format!("{:.0}%", c)); }
+        if let Some(h) = b.health_percent() { parts.push(format!("health {:.0}%", h)); }
+        if let Some(c) = b.cycle_count { parts.push(format!("{} cycles", c)); }
+        if let Some(v) = b.voltage_v { parts.push(format!("{:.2} V", v)); }
+        if let Some(end) = b.charge_end_threshold {
+            parts.push(format!("charges {}–{}%", b.charge_start_threshold.unwrap_or(0), end));
+        }
+        if let (Some(now), Some(full)) = (b.charge_now_ah, b.charge_full_ah) {
+            parts.push(format!("{:.2} / {:.2} Ah", now, full));
+        }
+        let l = Label::new(Some(&parts.join(" · ")));
+        l.add_css_class("subtitle");
+        l.set_halign(gtk4::Align::Start);
+        l.set_wrap(true);
+        container.append(&l);
+    }
+}
+
+/// Value and limits labels of one sensor channel, updated in place.
+type SensorLabels = (Label, Label);
+
+/// One card per hwmon device listing every channel with its thresholds.
+fn build_sensors_panel(container: &Box, devices: &[HwmonDevice]) -> Vec<Vec<SensorLabels>> {
+    if devices.is_empty() {
+        let na = Label::new(Some("No hwmon sensors found. Try loading drivers with `sudo sensors-detect`."));
+        na.add_css_class("subtitle");
+        container.append(&na);
+        return Vec::new();
+    }
+    let mut labels = Vec::with_capacity(devices.len());
+    for dev in devices {
+        let title = match &dev.model {
+            Some(model) => format!("{} — {} ({})", dev.name, model, dev.id),
+            None => format!("{} ({})", dev.name, dev.id),
+        };
+        let frame = Frame::new(Some(&title));
+        frame.add_css_class("card");
+        let grid = Grid::new();
+        grid.set_row_spacing(4); grid.set_column_spacing(16);
+        grid.set_margin_top(8); grid.set_margin_bottom(8);
+        grid.set_margin_start(12); grid.set_margin_end(12);
+
+        if dev.channels.is_empty() {
+            let l = Label::new(Some("No readable channels"));
+            l.add_css_class("subtitle"); l.set_halign(gtk4::Align::Start);
+            grid.attach(&l, 0, 0, 1, 1);
+        }
+        let mut rows = Vec::with_capacity(dev.channels.len());
+        for (row, ch) in dev.channels.iter().enumerate() {
+            let row = row as i32;
+            let name = Label::new(Some(&ch.label));
+            name.set_tooltip_text(Some(&dev.attr_path(ch, "input").display().to_string()));
+            name.set_halign(gtk4::Align::Start);
+            name.set_hexpand(true);
+            let kind = Label::new(Some(ch.kind.title()));
+            kind.add_css_class("subtitle");
+            kind.set_halign(gtk4::Align::Start);
+            let value = Label::new(None);
+            value.add_css_class("value");
+            value.set_halign(gtk4::Align::End);
+            let lim = Label::new(None);
+            lim.add_css_class("subtitle");
+            lim.set_halign(gtk4::Align::End);
+            grid.attach(&name, 0, row, 1, 1);
+            grid.attach(&kind, 1, row, 1, 1);
+            grid.attach(&value, 2, row, 1, 1);
+            grid.attach(&lim, 3, row, 1, 1);
+            rows.push((value, lim));
+        }
+        frame.set_child(Some(&grid));
+        container.append(&frame);
+        labels.push(rows);
+    }
+    update_sensor_labels(devices, &labels);
+    labels
+}
+
+fn update_sensor_labels(devices: &[HwmonDevice], labels: &[Vec<SensorLabels>]) {
+    for (dev, rows) in devices.iter().zip(labels) {
+        for (ch, (value, lim)) in dev.channels.iter().zip(rows) {
+            value.set_text(&ch.value.map(|v| format_sensor_value(ch.kind, v)).unwrap_or_else(|| "--".into()));
+            let over = ch.value.zip(ch.max.or(ch.crit)).is_some_and(|(v, lim)| lim > 0.0 && v >= lim);
+            if over { value.add_css_class("status-warning"); } else { value.remove_css_class("status-warning"); }
+            let limits: Vec<String> = [("min", ch.min), ("max", ch.max), ("crit", ch.crit)].iter()
+                .filter_map(|(n, v)| v.map(|v| format!("{} {}", n, format_sensor_value(ch.kind, v))))
+                .collect();
+            lim.set_text(&limits.join(" · "));
+        }
+    }
+}
+
+fn rebuild_benchmark_table(container: &Box, report: Option<&BenchmarkReport>) {
+    while let Some(child) = container.first_child() {
+        container.remove(&child);
+    }
+    let Some(report) = report else {
+        let na = Label::new(Some("No saved benchmark results yet."));
+        na.add_css_class("subtitle"); na.set_halign(gtk4::Align::Start);
+        container.append(&na);
+        return;
+    };
+    let machine = Label::new(Some(&format!("{} · cooldown {} s", report.machine, report.cooldown_seconds)));
+    machine.add_css_class("subtitle"); machine.set_halign(gtk4::Align::Start);
+    container.append(&machine);
+
+    let grid = Grid::new();
+    grid.set_row_spacing(4); grid.set_column_spacing(16);
+    let best = report.best().map(|r| r.profile.clone());
+    for (col, title) in report.columns().iter().enumerate() {
+        let h = Label::new(Some(title));
+        h.add_css_class("title");
+        h.set_halign(if col == 0 { gtk4::Align::Start } else { gtk4::Align::End });
+        grid.attach(&h, col as i32, 0, 1, 1);
+    }
+    for (row, (cells, result)) in report.rows().iter().zip(&report.results).enumerate() {
+        for (col, cell) in cells.iter().enumerate() {
+            let l = Label::new(Some(cell));
+            if col == 0 {
+                l.set_halign(gtk4::Align::Start);
+                l.set_hexpand(true);
+            } else {
+                l.set_halign(gtk4::Align::End);
+                l.add_css_class("value");
+            }
+            if best.as_deref() == Some(result.profile.as_str()) { l.add_css_class("status-ok"); }
+            grid.attach(&l, col as i32, row as i32 + 1, 1, 1);
+        }
+    }
+    container.append(&grid);
+
+    let mut summary = Vec::new();
+    if let Some(fastest) = report.fastest() {
+        summary.push(format!("Fastest: {}", fastest.profile));
+    }
+    summary.push(match &best {
+        Some(best) => format!("Best performance per watt: {}", best),
+        None => "No energy readings (benchmark as root to read RAPL)".to_string(),
+    });
+    let sum = Label::new(Some(&summary.join(" · ")));
+    sum.set_halign(gtk4::Align::Start);
+    container.append(&sum);
+    for r in report.results.iter().filter(|r| r.failed()) {
+        let err = Label::new(Some(&format!("{} failed: {}", r.profile, r.error.as_deref().unwrap_or_default())));
+        err.add_css_class("status-warning"); err.set_halign(gtk4::Align::Start); err.set_wrap(true);
+        container.append(&err);
+    }
+}
+
+fn set_trip_margin(label: &Label, margin: f32) {
+    label.set_text(&if margin > 0.0 { format!("{} left", TempUnit::current().format_delta(margin, 1)) } else { "tripped".to_string() });
+    label.remove_css_class("status-ok"); label.remove_css_class("status-warning");
+    label.add_css_class(if margin > 5.0 { "status-ok" } else { "status-warning" });
+}
+
+fn update_cooling_labels(dev: &CoolingDevice, zones: &[ThermalZone], state_lbl: &Label, bind_lbl: &Label) {
+    state_lbl.set_text(&format!("State {} / {} ({})", dev.cur_state, dev.max_state, dev.kind.state_unit()));
+    state_lbl.remove_css_class("status-warning");
+    if dev.cur_state > 0 { state_lbl.add_css_class("status-warning"); }
+
+    // Which zone trips drive this device; mark trips the zone has crossed.
+    let drivers: Vec<String> = zones.iter()
+        .flat_map(|z| z.bindings.iter()
+            .filter(|b| b.cooling_device == dev.id)
+            .map(move |b| match b.trip_point.and_then(|t| z.trip_points.get(t)) {
+                Some(trip) => format!(
+                    "{} trip {} ({} {}){}",
+                    z.type_name, trip.id, trip.trip_type, fmt_temp(trip.temp_celsius, 0),
+                    if z.temp_celsius >= trip.temp_celsius { " ▲ tripped" } else { "" },
+                ),
+                None => format!("{} (no trip)", z.type_name),
+            }))
+        .collect();
+    bind_lbl.set_text(&if drivers.is_empty() {
+        "Not bound to any thermal zone".to_string()
+    } else {
+        format!("Driven by: {}", drivers.join(", "))
+    });
+}
+
+fn rebuild_schedule_rules(rules_box: &Box, rules: &Rc<RefCell<Vec<ScheduleRule>>>, names: &Rc<[String]>) {
+    while let Some(child) = rules_box.first_child() {
+        rules_box.remove(&child);
+    }
+    let current = rules.borrow().clone();
+    for (i, rule) in current.iter().enumerate() {
+        let row = Box::new(Orientation::Horizontal, 8);
+        let text_entry = |text: &str, chars: i32, set: fn(&mut ScheduleRule, String)| {
+            let entry = Entry::new();
+            entry.set_text(text);
+            entry.set_width_chars(chars);
+            let r = rules.clone();
+            entry.connect_changed(move |e| {
+                if let Some(rule) = r.borrow_mut().get_mut(i) { set(rule, e.text().to_string()); }
+            });
+            entry
+        };
+        row.append(&text_entry(&rule.name, 10, |r, v| r.name = v));
+        row.append(&text_entry(&rule.days, 10, |r, v| r.days = v));
+        row.append(&text_entry(&rule.start, 5, |r, v| r.start = v));
+        row.append(&Label::new(Some("–")));
+        row.append(&text_entry(&rule.end, 5, |r, v| r.end = v));
+        row.append(&Label::new(Some("→")));
+
+        let sl = StringList::new(&names.iter().map(|n| n.as_str()).collect::<Vec<_>>());
+        let profile_dd = DropDown::new(Some(sl), None::<gtk4::Expression>);
+        if let Some(pos) = names.iter().position(|n| n.eq_ignore_ascii_case(&rule.profile)) {
+            profile_dd.set_selected(pos as u32);
+        }
+        {
+            let (r, names) = (rules.clone(), names.clone());
+            profile_dd.connect_selected_notify(move |d| {
+                if let (Some(rule), Some(name)) = (r.borrow_mut().get_mut(i), names.get(d.selected() as usize)) {
+                    rule.profile = name.clone();
+                }
+            });
+        }
+        row.append(&profile_dd);
+
+        row.append(&Label::new(Some("priority")));
+        let prio_spin = SpinButton::with_range(-100.0, 100.0, 1.0);
+        prio_spin.set_value(rule.priority as f64);
+        {
+            let r = rules.clone();
+            prio_spin.connect_value_changed(move |s| {
+                if let Some(rule) = r.borrow_mut().get_mut(i) { rule.priority = s.value() as i32; }
+            });
+        }
+        row.append(&prio_spin);
+
+        let remove_btn = Button::with_label("✕");
+        {
+            let (rb, r, names) = (rules_box.clone(), rules.clone(), names.clone());
+            remove_btn.connect_clicked(move |_| {
+                r.borrow_mut().remove(i);
+                rebuild_schedule_rules(&rb, &r, &names);
+            });
+        }
+        row.append(&remove_btn);
+        rules_box.append(&row);
+    }
+}
+
+fn rebuild_battery_rules(rules_box: &Box, rules: &Rc<RefCell<Vec<BatteryRule>>>, names: &Rc<[String]>) {
+    while let Some(child) = rules_box.first_child() {
+        rules_box.remove(&child);
+    }
+    const TURBO: [(&str, Option<bool>); 3] = [("Turbo unchanged", None), ("Turbo off", Some(false)), ("Turbo on", Some(true))];
+    let current = rules.borrow().clone();
+    for (i, rule) in current.iter().enumerate() {
+        let row = Box::new(Orientation::Horizontal, 8);
+        row.append(&Label::new(Some("Below")));
+        let below_spin = SpinButton::with_range(1.0, 100.0, 1.0);
+        below_spin.set_value(rule.below_percent as f64);
+        {
+            let r = rules.clone();
+            below_spin.connect_value_changed(move |s| {
+                if let Some(rule) = r.borrow_mut().get_mut(i) { rule.below_percent = s.value() as f32; }
+            });
+        }
+        row.append(&below_spin);
+        row.append(&Label::new(Some("% →")));
+
+        let sl = StringList::new(&names.iter().map(|n| n.as_str()).collect::<Vec<_>>());
+        let profile_dd = DropDown::new(Some(sl), None::<gtk4::Expression>);
+        if let Some(pos) = names.iter().position(|n| n.eq_ignore_ascii_case(&rule.profile)) {
+            profile_dd.set_selected(pos as u32);
+        }
+        {
+            let (r, names) = (rules.clone(), names.clone());
+            profile_dd.connect_selected_notify(move |d| {
+                if let (Some(rule), Some(name)) = (r.borrow_mut().get_mut(i), names.get(d.selected() as usize)) {
+                    rule.profile = name.clone();
+                }
+            });
+        }
+        row.append(&profile_dd);
+
+        let turbo_dd = DropDown::from_strings(&TURBO.map(|(label, _)| label));
+        turbo_dd.set_selected(TURBO.iter().position(|(_, t)| *t == rule.turbo).unwrap_or(0) as u32);
+        {
+            let r = rules.clone();
+            turbo_dd.connect_selected_notify(move |d| {
+                if let Some(rule) = r.borrow_mut().get_mut(i) { rule.turbo = TURBO[d.selected() as usize % TURBO.len()].1; }
+            });
+        }
+        row.append(&turbo_dd);
+
+        row.append(&Label::new(Some("release +")));
+        let hyst_spin = SpinButton::with_range(0.0, 20.0, 1.0);
+        hyst_spin.set_value(rule.hysteresis_percent as f64);
+        hyst_spin.set_tooltip_text(Some("Once active, the rule holds until the charge is this many points above its limit"));
+        {
+            let r = rules.clone();
+            hyst_spin.connect_value_changed(move |s| {
+                if let Some(rule) = r.borrow_mut().get_mut(i) { rule.hysteresis_percent = s.value() as f32; }
+            });
+        }
+        row.append(&hyst_spin);
+        row.append(&Label::new(Some("%")));
+
+        let up_btn = Button::with_label("↑");
+        up_btn.set_tooltip_text(Some("Check this rule earlier"));
+        up_btn.set_sensitive(i > 0);
+        {
+            let (rb, r, names) = (rules_box.clone(), rules.clone(), names.clone());
+            up_btn.connect_clicked(move |_| {
+                r.borrow_mut().swap(i - 1, i);
+                rebuild_battery_rules(&rb, &r, &names);
+            });
+        }
+        let remove_btn = Button::with_label("✕");
+        {
+            let (rb, r, names) = (rules_box.clone(), rules.clone(), names.clone());
+            remove_btn.connect_clicked(move |_| {
+                r.borrow_mut().remove(i);
+                rebuild_battery_rules(&rb, &r, &names);
+            });
+        }
+        row.append(&up_btn);
+        row.append(&remove_btn);
+        rules_box.append(&row);
+    }
+}
+
+fn rebuild_fan_points(points_box: &Box, curve: &Rc<RefCell<FanCurve>>, graph: &gtk4::DrawingArea) {
+    while let Some(child) = points_box.first_child() {
+        points_box.remove(&child);
+    }
+    let points = curve.borrow().points.clone();
+    for (i, p) in points.iter().enumerate() {
+        let row = Box::new(Orientation::Horizontal, 8);
+        let lbl = Label::new(Some(&format!("Point {}", i + 1)));
+        lbl.set_width_chars(8);
+        lbl.set_xalign(0.0);
+        let unit = TempUnit::current();
+        let temp_spin = SpinButton::with_range(unit.convert(0.0) as f64, unit.convert(110.0) as f64, 1.0);
+        temp_spin.set_value(unit.convert(p.temp_celsius) as f64);
+        let duty_spin = SpinButton::with_range(0.0, 100.0, 1.0);
+        duty_spin.set_value(p.duty_percent as f64);
+        {
+            let (c, g) = (curve.clone(), graph.clone());
+            temp_spin.connect_value_changed(move |s| {
+                if let Some(pt) = c.borrow_mut().points.get_mut(i) { pt.temp_celsius = unit.to_celsius(s.value() as f32); }
+                g.queue_draw();
+            });
+        }
+        {
+            let (c, g) = (curve.clone(), graph.clone());
+            duty_spin.connect_value_changed(move |s| {
+                if let Some(pt) = c.borrow_mut().points.get_mut(i) { pt.duty_percent = s.value() as u8; }
+                g.queue_draw();
+            });
+        }
+        let remove_btn = Button::with_label("✕");
+        remove_btn.set_sensitive(points.len() > 1);
+        {
+            let (pb, c, g) = (points_box.clone(), curve.clone(), graph.clone());
+            remove_btn.connect_clicked(move |_| {
+                c.borrow_mut().points.remove(i);
+                rebuild_fan_points(&pb, &c, &g);
+            });
+        }
+        row.append(&lbl);
+        row.append(&temp_spin);
+        row.append(&Label::new(Some(&format!("{} →", unit.symbol()))));
+        row.append(&duty_spin);
+        row.append(&Label::new(Some("%")));
+        row.append(&remove_btn);
+        points_box.append(&row);
+    }
+    graph.queue_draw();
+}
+
+fn format_sensor_value(kind: SensorKind, v: f64) -> String {
+    match kind {
+        SensorKind::Temp  => fmt_temp(v as f32, 1),
+        SensorKind::Fan   => format!("{:.0} {}", v, kind.unit()),
+        SensorKind::Power => format!("{:.2} {}", v, kind.unit()),
+        SensorKind::In | SensorKind::Curr => format!("{:.3} {}", v, kind.unit()),
+    }
+}
+
+/// Compact one-line RAPL breakdown, e.g. "package-0 12.1 · core 8.4 · dram 1.2 W".
+/// Subzones get a socket suffix on multi-socket systems so they stay distinguishable.
+fn format_power_breakdown(parts: &[DomainPower]) -> String {
+    let multi_socket = parts.iter().filter(|d| d.kind == EnergyDomainKind::Package).count() > 1;
+    let items: Vec<String> = parts.iter().map(|d| match d.socket {
+        Some(sock) if multi_socket && d.kind != EnergyDomainKind::Package =>
+            format!("{}{} {:.1}", d.name, sock, d.watts),
+        _ => format!("{} {:.1}", d.name, d.watts),
+    }).collect();
+    if items.is_empty() { String::new() } else { format!("{} W", items.join(" · ")) }
+}
+
 /// Creates a macOS-style circular traffic-light dot drawn with Cairo.
 /// Using DrawingArea instead of Button because GTK4 Button enforces internal
 /// padding and minimum dimensions that prevent true circles via CSS alone.
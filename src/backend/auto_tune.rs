@@ -1,10 +1,164 @@
 // Auto-tuning engine module
-use anyhow::Result;
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use crate::backend::cpu::CpuManager;
+use crate::backend::profile::{Profile, ProfileManager};
 
-pub struct AutoTuner;
+/// `[[auto_tune.battery_rules]]`: on battery with the charge below
+/// `below_percent`, switch to `profile`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BatteryRule {
+    pub below_percent: f32,
+    pub profile: String,
+    /// Forces turbo on or off on top of the profile; unset leaves it alone.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub turbo: Option<bool>,
+    /// Once active, the rule holds until the charge is this many points
+    /// above `below_percent`, so it doesn't flip at the boundary.
+    #[serde(default = "default_hysteresis")]
+    pub hysteresis_percent: f32,
+}
+
+fn default_hysteresis() -> f32 { 3.0 }
+
+impl BatteryRule {
+    pub fn new(below_percent: f32, profile: &str) -> Self {
+        Self { below_percent, profile: profile.to_string(), turbo: None, hysteresis_percent: default_hysteresis() }
+    }
+}
+
+/// What the rules want applied right now, and why. Unset fields are left
+/// as they are.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Selection {
+    pub profile: Option<String>,
+    pub turbo: Option<bool>,
+    pub min_freq_mhz: Option<u32>,
+    pub max_freq_mhz: Option<u32>,
+    pub reason: String,
+}
+
+impl Selection {
+    /// Same settings, whatever the reason.
+    pub fn same_target(&self, other: &Selection) -> bool {
+        let profile_eq = match (&self.profile, &other.profile) {
+            (Some(a), Some(b)) => a.eq_ignore_ascii_case(b),
+            (a, b) => a == b,
+        };
+        profile_eq
+            && self.turbo == other.turbo
+            && self.min_freq_mhz == other.min_freq_mhz
+            && self.max_freq_mhz == other.max_freq_mhz
+    }
+
+    /// `Performance, turbo off, ≤ 2400 MHz`
+    pub fn summary(&self) -> String {
+        let mut parts = vec![self.profile.clone().unwrap_or_else(|| "Current profile".into())];
+        if let Some(turbo) = self.turbo {
+            parts.push(format!("turbo {}", if turbo { "on" } else { "off" }));
+        }
+        if let Some(min) = self.min_freq_mhz {
+            parts.push(format!("≥ {} MHz", min));
+        }
+        if let Some(max) = self.max_freq_mhz {
+            parts.push(format!("≤ {} MHz", max));
+        }
+        parts.join(", ")
+    }
+
+    /// Applies the profile (matched case-insensitively), then the turbo and
+    /// frequency overrides. Returns the profile if one was applied.
+    pub fn apply(&self, profiles: &ProfileManager, cpu: &CpuManager) -> Result<Option<Profile>> {
+        let profile = match &self.profile {
+            Some(name) => {
+                let profile = profiles.get_profiles().iter()
+                    .find(|p| p.name.eq_ignore_ascii_case(name))
+                    .cloned()
+                    .with_context(|| format!("No profile named {:?}", name))?;
+                profile.apply(cpu)?;
+                Some(profile)
+            }
+            None => None,
+        };
+        if let Some(turbo) = self.turbo {
+            cpu.set_turbo(turbo).context("Failed to set turbo")?;
+        }
+        for core in 0..cpu.core_count() {
+            // Offline cores (e.g. SMT siblings) have no cpufreq files.
+            if !cpu.is_core_online(core).unwrap_or(true) {
+                continue;
+            }
+            if let Some(max) = self.max_freq_mhz {
+                cpu.set_scaling_max_freq(core, max).context("Failed to set max frequency")?;
+            }
+            if let Some(min) = self.min_freq_mhz {
+                cpu.set_scaling_min_freq(core, min).context("Failed to set min frequency")?;
+            }
+        }
+        Ok(profile)
+    }
+}
+
+/// Turbo and frequency limits stay where a rule put them until something
+/// sets them again. When `last` set one that `target` leaves unset and
+/// `target` brings no profile of its own, the `baseline` profile is added
+/// so applying it puts them back.
+pub fn restore_baseline(target: Option<Selection>, last: Option<&Selection>, baseline: Option<&str>) -> Option<Selection> {
+    let (Some(last), Some(baseline)) = (last, baseline) else { return target };
+    if target.as_ref().is_some_and(|t| t.profile.is_some()) {
+        return target;
+    }
+    let dropped = |set: fn(&Selection) -> bool| set(last) && !target.as_ref().is_some_and(set);
+    if !(dropped(|s| s.turbo.is_some()) || dropped(|s| s.min_freq_mhz.is_some()) || dropped(|s| s.max_freq_mhz.is_some())) {
+        return target;
+    }
+    let mut selection = target.unwrap_or_else(|| Selection { reason: "rules ended".into(), ..Default::default() });
+    selection.reason = format!("{}; back to {} for turbo/limits", selection.reason, baseline);
+    selection.profile = Some(baseline.to_string());
+    Some(selection)
+}
+
+/// The most recent auto-switches with their reasons, newest first.
+#[derive(Debug, Default)]
+pub struct SwitchHistory {
+    entries: VecDeque<(chrono::DateTime<chrono::Local>, Selection)>,
+}
+
+impl SwitchHistory {
+    const CAPACITY: usize = 20;
+
+    pub fn record(&mut self, selection: &Selection) {
+        self.entries.push_front((chrono::Local::now(), selection.clone()));
+        self.entries.truncate(Self::CAPACITY);
+    }
+
+    /// `14:02:31  Performance — cargo (pid 4242) running (rule "Builds")`
+    pub fn lines(&self) -> impl Iterator<Item = String> + '_ {
+        self.entries.iter().map(|(at, s)| format!("{}  {} — {}", at.format("%H:%M:%S"), s.summary(), s.reason))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_restore_baseline() {
+        let hot = Selection { turbo: Some(false), max_freq_mhz: Some(2000), reason: "hot".into(), ..Default::default() };
+        let balanced = Selection { profile: Some("Balanced".into()), reason: "AC".into(), ..Default::default() };
 
-impl AutoTuner {
-    pub fn new() -> Result<Self> {
-        Ok(Self)
+        // No rule left: the baseline profile resets turbo and limits.
+        let back = restore_baseline(None, Some(&hot), Some("Balanced")).unwrap();
+        assert_eq!(back.profile.as_deref(), Some("Balanced"));
+        assert_eq!((back.turbo, back.max_freq_mhz), (None, None));
+        // A remaining rule's profile already does.
+        assert_eq!(restore_baseline(Some(balanced.clone()), Some(&hot), Some("Silent")), Some(balanced.clone()));
+        // Nothing lingering from the last selection.
+        assert_eq!(restore_baseline(None, Some(&balanced), Some("Balanced")), None);
+        // Still held by the next selection.
+        let still = Selection { max_freq_mhz: Some(1800), turbo: Some(false), ..Default::default() };
+        assert_eq!(restore_baseline(Some(still.clone()), Some(&hot), Some("Balanced")), Some(still));
+        assert_eq!(restore_baseline(None, Some(&hot), None), None);
     }
 }
@@ -0,0 +1,366 @@
+use anyhow::{bail, Context, Result};
+use chrono::{Local, TimeZone};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+use crate::backend::cpu::CpuManager;
+use crate::backend::launch;
+use crate::backend::measure::{self, Measurement, Sampler};
+use crate::backend::control;
+use crate::backend::overrides::{Expiry, Override};
+use crate::backend::profile::ProfileManager;
+use crate::backend::thermal::{TempSource, ThermalManager};
+
+/// Results directory name; see `measure::save_results`.
+const RESULTS: &str = "benchmarks";
+
+/// Iterations per deadline check in the built-in workload.
+const CHUNK: u64 = 100_000;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Workload {
+    /// Integer and floating-point busy loop on `threads` threads for a fixed
+    /// time; scored in iterations per second.
+    Builtin { seconds: u64, threads: usize },
+    /// A user command run to completion; scored in runs per hour.
+    Command { argv: Vec<String> },
+}
+
+impl Workload {
+    pub fn describe(&self) -> String {
+        match self {
+            Workload::Builtin { seconds, threads } => format!("built-in, {} threads × {} s", threads, seconds),
+            Workload::Command { argv } => argv.join(" "),
+        }
+    }
+
+    /// Unit of `ProfileResult::score`; higher is better either way.
+    pub fn score_unit(&self) -> &'static str {
+        match self {
+            Workload::Builtin { .. } => "Mops/s",
+            Workload::Command { .. } => "runs/h",
+        }
+    }
+
+    fn run(&self, interval: Duration, source: TempSource) -> Result<(Measurement, f64)> {
+        match self {
+            Workload::Builtin { seconds, threads } => {
+                let before = measure::rusage(libc::RUSAGE_SELF);
+                let sampler = Sampler::start(interval, source);
+                let ops = spin(Duration::from_secs(*seconds), *threads);
+                let mut m = sampler.finish();
+                let after = measure::rusage(libc::RUSAGE_SELF);
+                m.cpu_user_seconds = after.0 - before.0;
+                m.cpu_system_seconds = after.1 - before.1;
+                let score = ops as f64 / 1e6 / m.wall_seconds;
+                Ok((m, score))
+            }
+            Workload::Command { argv } => {
+                // A non-zero exit code fails the run in `run`.
+                let m = measure::measure(argv, interval, source)?;
+                let score = 3600.0 / m.wall_seconds;
+                Ok((m, score))
+            }
+        }
+    }
+}
+
+/// Busy loop on `threads` threads until `duration` has passed, or until
+/// `launch::interrupted`. Returns the total number of iterations.
+pub(crate) fn spin(duration: Duration, threads: usize) -> u64 {
+    let deadline = Instant::now() + duration;
+    std::thread::scope(|s| {
+        let workers: Vec<_> = (0..threads.max(1) as u64)
+            .map(|i| s.spawn(move || {
+                let mut x = 0x9e37_79b9_7f4a_7c15u64 ^ i;
+                let mut f = 1.0f64;
+                let mut ops = 0;
+                while Instant::now() < deadline && !launch::interrupted() {
+                    for _ in 0..CHUNK {
+                        x ^= x << 13;
+                        x ^= x >> 7;
+                        x ^= x << 17;
+                        f = f.mul_add(0.999_999_9, (x & 0xff) as f64 * 1e-9);
+                    }
+                    ops += CHUNK;
+                }
+                std::hint::black_box((x, f));
+                ops
+            }))
+            .collect();
+        workers.into_iter().map(|w| w.join().unwrap_or(0)).sum()
+    })
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ProfileResult {
+    pub profile: String,
+    pub score: f64,
+    pub measurement: Measurement,
+    /// Why this profile's run failed; its score and measurement don't count.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl ProfileResult {
+    pub fn failed(&self) -> bool { self.error.is_some() }
+
+    /// Score per average watt; `None` without an energy reading.
+    pub fn perf_per_watt(&self) -> Option<f64> {
+        if self.failed() { return None; }
+        self.measurement.avg_watts.filter(|&w| w > 0.0).map(|w| self.score / w)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BenchmarkReport {
+    /// Unix time the run started.
+    pub timestamp: i64,
+    /// `hostname (CPU model)`
+    pub machine: String,
+    pub workload: Workload,
+    pub cooldown_seconds: u64,
+    pub results: Vec<ProfileResult>,
+}
+
+impl BenchmarkReport {
+    /// `2026-10-18 14:03 — built-in, 8 threads × 30 s`
+    pub fn title(&self) -> String {
+        let when = Local.timestamp_opt(self.timestamp, 0).single()
+            .map(|t| t.format("%Y-%m-%d %H:%M").to_string())
+            .unwrap_or_default();
+        format!("{} — {}", when, self.workload.describe())
+    }
+
+    pub fn columns(&self) -> Vec<String> {
+        let unit = self.workload.score_unit();
+        ["Profile", &format!("Score ({})", unit), "Avg W", &format!("{}/W", unit), "Perf/W",
+            "Energy J", "Time s", "Peak temp", "Throttled s"]
+            .iter().map(|c| c.to_string()).collect()
+    }
+
+    /// One row per profile, matching `columns`. Perf/W is relative to the
+    /// most efficient profile; failed runs only show "failed".
+    pub fn rows(&self) -> Vec<Vec<String>> {
+        let best = self.best().and_then(|b| b.perf_per_watt());
+        let opt = |v: Option<f64>, digits: usize| v.map(|v| format!("{:.*}", digits, v)).unwrap_or_else(|| "n/a".into());
+        self.results.iter().map(|r| {
+            if r.failed() {
+                let mut row = vec![r.profile.clone(), "failed".to_string()];
+                row.resize(self.columns().len(), "n/a".to_string());
+                return row;
+            }
+            let m = &r.measurement;
+            vec![
+                r.profile.clone(),
+                format!("{:.1}", r.score),
+                opt(m.avg_watts, 1),
+                opt(r.perf_per_watt(), 2),
+                r.perf_per_watt().zip(best).map(|(p, b)| format!("{:.0}%", p / b * 100.0)).unwrap_or_else(|| "n/a".into()),
+                opt(m.energy_joules, 0),
+                format!("{:.1}", m.wall_seconds),
+                m.peak_temp_celsius.map(|t| crate::utils::units::fmt_temp(t, 0)).unwrap_or_else(|| "n/a".into()),
+                opt(m.throttled_seconds, 1),
+            ]
+        }).collect()
+    }
+
+    /// The profile with the highest perf/W.
+    pub fn best(&self) -> Option<&ProfileResult> {
+        self.results.iter()
+            .filter(|r| r.perf_per_watt().is_some())
+            .max_by(|a, b| a.perf_per_watt().partial_cmp(&b.perf_per_watt()).unwrap_or(std::cmp::Ordering::Equal))
+    }
+
+    /// The profile with the highest score.
+    pub fn fastest(&self) -> Option<&ProfileResult> {
+        self.results.iter().filter(|r| !r.failed()).max_by(|a, b| a.score.partial_cmp(&b.score).unwrap_or(std::cmp::Ordering::Equal))
+    }
+
+    pub fn print(&self) {
+        let mut table = vec![self.columns()];
+        table.extend(self.rows());
+        let widths: Vec<usize> = (0..table[0].len())
+            .map(|c| table.iter().map(|row| row[c].chars().count()).max().unwrap_or(0))
+            .collect();
+        println!("{}", self.title());
+        println!("{}\n", self.machine);
+        for row in &table {
+            let cells: Vec<String> = row.iter().zip(&widths).enumerate()
+                .map(|(c, (cell, &w))| if c == 0 { format!("{:<w$}", cell) } else { format!("{:>w$}", cell) })
+                .collect();
+            println!("{}", cells.join("  "));
+        }
+        println!();
+        if let Some(fastest) = self.fastest() {
+            println!("Fastest:        {}", fastest.profile);
+        }
+        match self.best() {
+            Some(best) => println!("Best perf/W:    {}", best.profile),
+            None => println!("Best perf/W:    n/a (no energy readings; run as root)"),
+        }
+        for r in self.results.iter().filter(|r| r.failed()) {
+            println!("Failed:         {}: {}", r.profile, r.error.as_deref().unwrap_or_default());
+        }
+    }
+
+    /// Writes the report with `measure::save_results`.
+    pub fn save(&self) -> Result<PathBuf> {
+        let name = Local.timestamp_opt(self.timestamp, 0).single()
+            .map(|t| t.format("%Y%m%d-%H%M%S").to_string())
+            .unwrap_or_else(|| self.timestamp.to_string());
+        measure::save_results(RESULTS, &format!("benchmark-{}.json", name), self)
+    }
+
+    /// Every saved report this user can read, newest first.
+    pub fn load_all() -> Vec<BenchmarkReport> {
+        let mut reports: Vec<BenchmarkReport> = measure::results_dirs(RESULTS)
+            .into_iter()
+            .filter_map(|dir| fs::read_dir(dir).ok())
+            .flatten()
+            .filter_map(|e| e.ok())
+            .filter(|e| e.path().extension().is_some_and(|x| x == "json"))
+            .filter_map(|e| serde_json::from_str(&fs::read_to_string(e.path()).ok()?).ok())
+            .collect();
+        reports.sort_by_key(|r| std::cmp::Reverse(r.timestamp));
+        reports
+    }
+}
+
+pub struct BenchmarkOptions {
+    /// Profile names; empty means every profile.
+    pub profiles: Vec<String>,
+    pub workload: Workload,
+    pub cooldown: Duration,
+    pub interval: Duration,
+    pub source: TempSource,
+}
+
+/// Runs the workload once per profile. Each profile is applied as a
+/// temporary override tied to this process, so the previous state comes
+/// back afterwards even if the run is killed. Between runs the CPU rests
+/// for `cooldown`, and up to as long again until it is within 3 °C of where
+/// it started. A profile that can't be applied or whose run fails is
+/// recorded as failed and the rest still run. Once `launch::interrupted`,
+/// the current profile is removed and this fails with "Interrupted".
+pub fn run(
+    opts: &BenchmarkOptions,
+    profiles: &ProfileManager,
+    cpu: &CpuManager,
+    mut progress: impl FnMut(&str),
+) -> Result<BenchmarkReport> {
+    let names: Vec<String> = if opts.profiles.is_empty() {
+        profiles.get_profiles().iter().map(|p| p.name.clone()).collect()
+    } else {
+        opts.profiles.iter()
+            .map(|name| profiles.get_profiles().iter()
+                .find(|p| p.name.eq_ignore_ascii_case(name))
+                .map(|p| p.name.clone())
+                .with_context(|| format!("No profile named {:?}", name)))
+            .collect::<Result<_>>()?
+    };
+    if names.is_empty() {
+        bail!("No profiles to benchmark");
+    }
+
+    let mut thermal = ThermalManager::new().ok();
+    if let Some(t) = thermal.as_mut() {
+        t.set_source(opts.source.clone());
+    }
+    let read_temp = || thermal.as_ref().and_then(|t| t.get_cpu_temperature().ok());
+    let baseline = read_temp();
+    let machine = measure::machine_label(cpu);
+    let timestamp = Local::now().timestamp();
+    let mut results = Vec::new();
+
+    for (i, name) in names.iter().enumerate() {
+        if i > 0 {
+            progress(&format!("Cooling down for {} s…", opts.cooldown.as_secs()));
+            launch::sleep(opts.cooldown);
+            let deadline = Instant::now() + opts.cooldown;
+            while let (Some(now), Some(start)) = (read_temp(), baseline) {
+                if now <= start + 3.0 || Instant::now() >= deadline || launch::interrupted() {
+                    break;
+                }
+                launch::sleep(Duration::from_secs(1));
+            }
+        }
+        if launch::interrupted() {
+            bail!("Interrupted");
+        }
+        progress(&format!("[{}/{}] {}: running {}…", i + 1, names.len(), name, opts.workload.describe()));
+        let o = Override { profile: Some(name.clone()), ..Override::new(Expiry::process(std::process::id() as i32)?) };
+        let outcome = control::push_override(o, profiles, cpu).and_then(|id| {
+            let outcome = opts.workload.run(opts.interval, opts.source.clone());
+            if let Err(e) = control::remove_override(id, profiles, cpu) {
+                log::warn!("Failed to restore the previous state: {:#}", e);
+            }
+            outcome
+        });
+        // The run was cut short; its numbers mean nothing.
+        if launch::interrupted() {
+            bail!("Interrupted");
+        }
+        let result = match outcome {
+            Ok((measurement, score)) if measurement.exit_code == 0 => {
+                ProfileResult { profile: name.clone(), score, measurement, error: None }
+            }
+            Ok((measurement, _)) => {
+                let error = format!("exited with code {}", measurement.exit_code);
+                ProfileResult { profile: name.clone(), score: 0.0, measurement, error: Some(error) }
+            }
+            Err(e) => {
+                ProfileResult { profile: name.clone(), score: 0.0, measurement: Measurement::default(), error: Some(format!("{:#}", e)) }
+            }
+        };
+        match &result.error {
+            Some(e) => progress(&format!("[{}/{}] {}: failed: {}", i + 1, names.len(), name, e)),
+            None => progress(&format!(
+                "[{}/{}] {}: {:.1} {} in {:.1} s",
+                i + 1, names.len(), name, result.score, opts.workload.score_unit(), result.measurement.wall_seconds
+            )),
+        }
+        results.push(result);
+    }
+
+    Ok(BenchmarkReport { timestamp, machine, workload: opts.workload.clone(), cooldown_seconds: opts.cooldown.as_secs(), results })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_report_ranking() {
+        let result = |profile: &str, score: f64, watts: Option<f64>| ProfileResult {
+            profile: profile.into(),
+            score,
+            measurement: Measurement { avg_watts: watts, wall_seconds: 10.0, ..Default::default() },
+            error: None,
+        };
+        let report = BenchmarkReport {
+            timestamp: 0,
+            machine: String::new(),
+            workload: Workload::Builtin { seconds: 10, threads: 4 },
+            cooldown_seconds: 30,
+            results: vec![
+                result("Performance", 400.0, Some(40.0)),
+                result("Balanced", 300.0, Some(20.0)),
+                result("Power Saver", 100.0, None),
+                ProfileResult { error: Some("exited with code 2".into()), ..result("Turbo", 900.0, Some(10.0)) },
+            ],
+        };
+        assert_eq!(report.fastest().unwrap().profile, "Performance");
+        assert_eq!(report.best().unwrap().profile, "Balanced");
+        let rows = report.rows();
+        assert_eq!(rows[0][4], "67%");
+        assert_eq!(rows[1][4], "100%");
+        assert_eq!(rows[2][3], "n/a");
+        assert_eq!(rows[2][4], "n/a");
+        assert_eq!(rows[3][1], "failed");
+        assert!(rows.iter().all(|r| r.len() == report.columns().len()));
+        assert!(spin(Duration::from_millis(20), 2) >= 2 * CHUNK);
+    }
+}
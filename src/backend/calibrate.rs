@@ -0,0 +1,276 @@
+use anyhow::{bail, Result};
+use chrono::{Local, TimeZone};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+use std::time::Duration;
+use crate::backend::benchmark::spin;
+use crate::backend::cpu::CpuManager;
+use crate::backend::launch;
+use crate::backend::measure::{self, Sampler};
+use crate::backend::control;
+use crate::backend::overrides::{Expiry, Override};
+use crate::backend::profile::ProfileManager;
+use crate::backend::thermal::TempSource;
+
+/// Results directory name; see `measure::save_results`.
+const RESULTS: &str = "calibration";
+
+/// Balanced gets the highest step still this close to the best efficiency.
+const BALANCED_EFFICIENCY: f64 = 0.9;
+
+/// `/etc/machine-id`, or the hostname without one. Curves are filed under
+/// it so a shared home directory keeps one per machine.
+pub fn machine_id() -> String {
+    ["/etc/machine-id", "/proc/sys/kernel/hostname"].iter()
+        .filter_map(|p| fs::read_to_string(p).ok())
+        .map(|s| s.trim().to_string())
+        .find(|s| !s.is_empty())
+        .unwrap_or_else(|| "unknown".into())
+}
+
+/// One step of the sweep, measured under the fixed load.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SweepPoint {
+    pub max_freq_mhz: u32,
+    pub avg_freq_mhz: Option<f64>,
+    pub package_watts: Option<f64>,
+    pub peak_temp_celsius: Option<f32>,
+    /// Built-in workload iterations, in millions per second.
+    pub throughput: f64,
+}
+
+impl SweepPoint {
+    /// Millions of iterations per joule; `None` without a power reading.
+    pub fn efficiency(&self) -> Option<f64> {
+        self.package_watts.filter(|&w| w > 0.0).map(|w| self.throughput / w)
+    }
+}
+
+/// Suggested `max_freq_mhz` for the low-power profiles.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SweetSpots {
+    /// Highest step within 10 % of the best efficiency.
+    pub balanced_mhz: u32,
+    /// The most efficient step.
+    pub silent_mhz: u32,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EfficiencyCurve {
+    pub timestamp: i64,
+    pub machine_id: String,
+    /// `hostname (CPU model)`
+    pub machine: String,
+    pub threads: usize,
+    pub step_seconds: u64,
+    /// Turbo state during the sweep; the top steps mean little without it.
+    pub turbo: Option<bool>,
+    /// Ascending by `max_freq_mhz`.
+    pub points: Vec<SweepPoint>,
+}
+
+impl EfficiencyCurve {
+    pub fn most_efficient(&self) -> Option<&SweepPoint> {
+        self.points.iter()
+            .filter(|p| p.efficiency().is_some())
+            .max_by(|a, b| a.efficiency().partial_cmp(&b.efficiency()).unwrap_or(std::cmp::Ordering::Equal))
+    }
+
+    pub fn sweet_spots(&self) -> Option<SweetSpots> {
+        let best = self.most_efficient()?;
+        let peak = best.efficiency()?;
+        let balanced = self.points.iter()
+            .filter(|p| p.efficiency().is_some_and(|e| e >= peak * BALANCED_EFFICIENCY))
+            .map(|p| p.max_freq_mhz)
+            .max()?;
+        Some(SweetSpots { balanced_mhz: balanced, silent_mhz: best.max_freq_mhz })
+    }
+
+    /// `2026-10-18 14:03`
+    pub fn date(&self) -> String {
+        Local.timestamp_opt(self.timestamp, 0).single()
+            .map(|t| t.format("%Y-%m-%d %H:%M").to_string())
+            .unwrap_or_default()
+    }
+
+    pub fn print(&self) {
+        println!("{} — {}", self.date(), self.machine);
+        println!(
+            "{} threads, {} s per step, turbo {}\n",
+            self.threads, self.step_seconds,
+            self.turbo.map_or("unknown", |t| if t { "on" } else { "off" })
+        );
+        let opt = |v: Option<f64>, digits: usize| v.map(|v| format!("{:.*}", digits, v)).unwrap_or_else(|| "n/a".into());
+        println!("{:>9}  {:>9}  {:>9}  {:>9}  {:>10}  {:>9}", "Max MHz", "Avg MHz", "Package W", "Peak temp", "Mops/s", "Mops/J");
+        for p in &self.points {
+            println!(
+                "{:>9}  {:>9}  {:>9}  {:>9}  {:>10.1}  {:>9}",
+                p.max_freq_mhz,
+                opt(p.avg_freq_mhz, 0),
+                opt(p.package_watts, 1),
+                p.peak_temp_celsius.map(|t| crate::utils::units::fmt_temp(t, 0)).unwrap_or_else(|| "n/a".into()),
+                p.throughput,
+                opt(p.efficiency(), 2),
+            );
+        }
+        println!();
+        match self.sweet_spots() {
+            Some(s) => {
+                println!("Suggested max frequency for Balanced: {} MHz", s.balanced_mhz);
+                println!("Suggested max frequency for Silent:   {} MHz", s.silent_mhz);
+            }
+            None => println!("No power readings, so no suggestions (calibrate as root to read RAPL)"),
+        }
+    }
+
+    /// Writes the curve with `measure::save_results`, replacing this
+    /// machine's previous one.
+    pub fn save(&self) -> Result<PathBuf> {
+        measure::save_results(RESULTS, &format!("{}.json", self.machine_id), self)
+    }
+
+    /// This machine's newest saved curve.
+    pub fn load() -> Option<Self> {
+        let id = machine_id();
+        measure::results_dirs(RESULTS)
+            .into_iter()
+            .filter_map(|dir| fs::read_to_string(dir.join(format!("{}.json", id))).ok())
+            .filter_map(|s| serde_json::from_str::<Self>(&s).ok())
+            .max_by_key(|c| c.timestamp)
+    }
+}
+
+/// The max frequencies to try, ascending: the driver's available
+/// frequencies where it lists them, otherwise `count` points from the
+/// hardware minimum to maximum, rounded to 100 MHz.
+pub fn steps(cpu: &CpuManager, count: usize) -> Result<Vec<u32>> {
+    let mut available = cpu.get_available_frequencies(0).unwrap_or_default();
+    available.sort_unstable();
+    available.dedup();
+    if available.len() >= 2 {
+        return Ok(available);
+    }
+    let min = cpu.get_hardware_min_freq(0)?;
+    let max = cpu.get_hardware_max_freq(0)?;
+    Ok(evenly_spaced(min, max, count))
+}
+
+fn evenly_spaced(min: u32, max: u32, count: usize) -> Vec<u32> {
+    let count = count.max(2);
+    let mut points: Vec<u32> = (0..count)
+        .map(|i| min + ((max - min) as f64 * i as f64 / (count - 1) as f64) as u32)
+        .map(|f| ((f + 50) / 100 * 100).clamp(min, max))
+        .collect();
+    points.dedup();
+    points
+}
+
+pub struct SweepOptions {
+    /// Points between the hardware limits when the driver lists none.
+    pub steps: usize,
+    pub step_seconds: u64,
+    /// Load before each measurement, so clocks and temperature settle.
+    pub settle_seconds: u64,
+    pub threads: usize,
+    pub interval: Duration,
+    pub source: TempSource,
+}
+
+/// Runs the built-in workload at each max frequency step. Each step is
+/// applied as a temporary override tied to this process and removed once
+/// measured, or as soon as `launch::interrupted` (failing with
+/// "Interrupted"); if the process is killed outright, the background
+/// service or GUI removes it when either runs.
+pub fn sweep(
+    opts: &SweepOptions,
+    profiles: &ProfileManager,
+    cpu: &CpuManager,
+    mut progress: impl FnMut(&str),
+) -> Result<EfficiencyCurve> {
+    let steps = steps(cpu, opts.steps)?;
+    if steps.is_empty() {
+        bail!("No frequency steps to try");
+    }
+    let hw_min = cpu.get_hardware_min_freq(0)?;
+    let machine = measure::machine_label(cpu);
+    let timestamp = Local::now().timestamp();
+    let turbo = cpu.is_turbo_enabled().ok();
+    let mut points = Vec::new();
+
+    for (i, &mhz) in steps.iter().enumerate() {
+        progress(&format!("[{}/{}] max {} MHz…", i + 1, steps.len(), mhz));
+        let o = Override {
+            min_freq_mhz: Some(hw_min),
+            max_freq_mhz: Some(mhz),
+            ..Override::new(Expiry::process(std::process::id() as i32)?)
+        };
+        let id = control::push_override(o, profiles, cpu)?;
+        spin(Duration::from_secs(opts.settle_seconds), opts.threads);
+        let sampler = Sampler::start(opts.interval, opts.source.clone());
+        let ops = spin(Duration::from_secs(opts.step_seconds), opts.threads);
+        let m = sampler.finish();
+        if let Err(e) = control::remove_override(id, profiles, cpu) {
+            log::warn!("Failed to restore the previous state: {:#}", e);
+        }
+        if launch::interrupted() {
+            bail!("Interrupted");
+        }
+        let point = SweepPoint {
+            max_freq_mhz: mhz,
+            avg_freq_mhz: m.avg_freq_mhz,
+            package_watts: m.avg_watts,
+            peak_temp_celsius: m.peak_temp_celsius,
+            throughput: ops as f64 / 1e6 / m.wall_seconds,
+        };
+        progress(&format!(
+            "[{}/{}] max {} MHz: {:.1} Mops/s at {}",
+            i + 1, steps.len(), mhz, point.throughput,
+            point.package_watts.map(|w| format!("{:.1} W", w)).unwrap_or_else(|| "n/a W".into())
+        ));
+        points.push(point);
+    }
+
+    Ok(EfficiencyCurve {
+        timestamp,
+        machine_id: machine_id(),
+        machine,
+        threads: opts.threads,
+        step_seconds: opts.step_seconds,
+        turbo,
+        points,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sweet_spots() {
+        assert_eq!(evenly_spaced(400, 4700, 5), vec![400, 1500, 2600, 3600, 4700]);
+        assert_eq!(evenly_spaced(800, 800, 4), vec![800]);
+
+        let point = |mhz: u32, throughput: f64, watts: Option<f64>| SweepPoint {
+            max_freq_mhz: mhz, avg_freq_mhz: None, package_watts: watts, peak_temp_celsius: None, throughput,
+        };
+        let mut curve = EfficiencyCurve {
+            timestamp: 0,
+            machine_id: "test".into(),
+            machine: String::new(),
+            threads: 4,
+            step_seconds: 10,
+            turbo: Some(true),
+            points: vec![
+                point(800, 80.0, Some(8.0)),    // 10 Mops/J
+                point(1600, 160.0, Some(12.0)), // 13.3
+                point(2400, 240.0, Some(19.0)), // 12.6
+                point(3200, 300.0, Some(30.0)), // 10
+                point(4000, 330.0, Some(45.0)), // 7.3
+            ],
+        };
+        assert_eq!(curve.sweet_spots(), Some(SweetSpots { balanced_mhz: 2400, silent_mhz: 1600 }));
+        curve.points.iter_mut().for_each(|p| p.package_watts = None);
+        assert_eq!(curve.sweet_spots(), None);
+    }
+}
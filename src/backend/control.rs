@@ -0,0 +1,302 @@
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io::{Read, Write};
+use std::os::unix::fs::PermissionsExt;
+use std::os::unix::io::AsRawFd;
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::Path;
+use std::time::{Duration, Instant};
+use crate::backend::cpu::CpuManager;
+use crate::backend::overrides::{self, Expiry, Override, OverrideStack};
+use crate::backend::profile::ProfileManager;
+use crate::config::{ConfigManager, ServiceSections};
+
+/// The background service listens here for override requests, so commands
+/// like `run` work without root while it is running.
+pub const SOCKET: &str = "/run/cpu-power-manager/control.sock";
+
+/// Members of this group may use `SOCKET`; without it, only root can.
+pub const GROUP: &str = "cpu-power-manager";
+
+/// Requests and replies are one small JSON document each.
+const MAX_MESSAGE: u64 = 64 * 1024;
+
+/// How long a client may take to send its request. The service loop waits
+/// meanwhile, so fan and limiter updates stall for at most this long.
+const REQUEST_DEADLINE: Duration = Duration::from_millis(200);
+
+/// Connections served per `poll`; any more wait for the next tick.
+const MAX_PER_POLL: usize = 8;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "snake_case")]
+pub enum Request {
+    Push { over: Override },
+    Remove { id: u32 },
+    /// Replaces the sections of the service's config that the GUI edits.
+    SyncConfig { sections: Box<ServiceSections> },
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct Reply {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    id: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+/// Who sent a request, from `SO_PEERCRED`.
+#[derive(Debug, Clone, Copy)]
+pub struct Peer {
+    pub pid: i32,
+    pub uid: u32,
+}
+
+fn peer(stream: &UnixStream) -> Result<Peer> {
+    let mut cred = libc::ucred { pid: 0, uid: 0, gid: 0 };
+    let mut len = std::mem::size_of::<libc::ucred>() as libc::socklen_t;
+    // SAFETY: `cred` and `len` describe a buffer of the size the kernel expects.
+    let rc = unsafe {
+        libc::getsockopt(
+            stream.as_raw_fd(), libc::SOL_SOCKET, libc::SO_PEERCRED,
+            &mut cred as *mut libc::ucred as *mut libc::c_void, &mut len,
+        )
+    };
+    if rc != 0 {
+        return Err(std::io::Error::last_os_error()).context("Failed to read the peer's credentials");
+    }
+    Ok(Peer { pid: cred.pid, uid: cred.uid })
+}
+
+/// Sends `req` to the service. `Ok(None)` when no service is listening.
+fn send(req: &Request) -> Result<Option<u32>> {
+    let mut stream = match UnixStream::connect(SOCKET) {
+        Ok(stream) => stream,
+        Err(e) if e.kind() == std::io::ErrorKind::PermissionDenied => {
+            bail!("Not allowed to use the background service; run with sudo or join the '{}' group", GROUP)
+        }
+        Err(_) => return Ok(None),
+    };
+    stream.set_read_timeout(Some(Duration::from_secs(10)))?;
+    stream.write_all(&serde_json::to_vec(req)?)?;
+    stream.shutdown(std::net::Shutdown::Write)?;
+    let mut text = String::new();
+    stream.take(MAX_MESSAGE).read_to_string(&mut text).context("No reply from the background service")?;
+    let reply: Reply = serde_json::from_str(&text).context("Invalid reply from the background service")?;
+    match (reply.id, reply.error) {
+        (_, Some(e)) => bail!("Background service: {}", e),
+        (Some(id), None) => Ok(Some(id)),
+        (None, None) => bail!("Invalid reply from the background service"),
+    }
+}
+
+/// Pushes `o` through the background service when it runs, which needs no
+/// root; otherwise pushes and applies it here.
+pub fn push_override(o: Override, profiles: &ProfileManager, cpu: &CpuManager) -> Result<u32> {
+    match send(&Request::Push { over: o.clone() })? {
+        Some(id) => Ok(id),
+        None => overrides::push(o, profiles, cpu, true),
+    }
+}
+
+/// Removes override `id`, through the service when it runs.
+pub fn remove_override(id: u32, profiles: &ProfileManager, cpu: &CpuManager) -> Result<()> {
+    if send(&Request::Remove { id })?.is_none() {
+        overrides::remove(Some(id), profiles, cpu, true)?;
+    }
+    Ok(())
+}
+
+/// Hands `sections` to the service, which writes them to its config and
+/// picks them up on its next tick. `Ok(false)` when no service is listening.
+pub fn sync_config(sections: ServiceSections) -> Result<bool> {
+    Ok(send(&Request::SyncConfig { sections: Box::new(sections) })?.is_some())
+}
+
+/// Without root, a peer may only push overrides that end with its own
+/// process, and only remove those.
+fn owned_by(expiry: &Expiry, peer: Peer) -> bool {
+    peer.uid == 0 || Expiry::process(peer.pid).is_ok_and(|own| *expiry == own)
+}
+
+/// Frequency limits must lie within the hardware's `(min, max)` range,
+/// the minimum no higher than the maximum.
+fn check_freq_range(o: &Override, (hw_min, hw_max): (u32, u32)) -> Result<()> {
+    for (what, mhz) in [("Minimum", o.min_freq_mhz), ("Maximum", o.max_freq_mhz)] {
+        if let Some(mhz) = mhz.filter(|f| !(hw_min..=hw_max).contains(f)) {
+            bail!("{} frequency {} MHz is outside the hardware range {}–{} MHz", what, mhz, hw_min, hw_max);
+        }
+    }
+    if let (Some(min), Some(max)) = (o.min_freq_mhz, o.max_freq_mhz) {
+        if min > max {
+            bail!("Minimum frequency {} MHz is above the maximum {} MHz", min, max);
+        }
+    }
+    Ok(())
+}
+
+/// Carries out one request for the service.
+pub fn handle(req: Request, peer: Peer, profiles: &ProfileManager, cpu: &CpuManager) -> Result<u32> {
+    match req {
+        Request::Push { over } => {
+            if !owned_by(&over.expiry, peer) {
+                bail!("Without root, overrides must end with the requesting process");
+            }
+            if over.min_freq_mhz.is_some() || over.max_freq_mhz.is_some() {
+                check_freq_range(&over, (cpu.get_hardware_min_freq(0)?, cpu.get_hardware_max_freq(0)?))?;
+            }
+            if let Some(name) = &over.profile {
+                if !profiles.get_profiles().iter().any(|p| p.name.eq_ignore_ascii_case(name)) {
+                    bail!("No profile named {:?}", name);
+                }
+            }
+            overrides::push(over, profiles, cpu, true)
+        }
+        Request::Remove { id } => {
+            let stack = OverrideStack::load();
+            let o = stack.overrides.iter().find(|o| o.id == id).with_context(|| format!("No override #{}", id))?;
+            if !owned_by(&o.expiry, peer) {
+                bail!("Override #{} belongs to another process", id);
+            }
+            overrides::remove(Some(id), profiles, cpu, true)?;
+            Ok(id)
+        }
+        Request::SyncConfig { sections } => {
+            let mut service = ConfigManager::service()?;
+            (*sections).apply_to(service.get_config_mut());
+            service.save()?;
+            Ok(0)
+        }
+    }
+}
+
+/// The service's end of `SOCKET`.
+pub struct ControlServer {
+    listener: UnixListener,
+}
+
+impl ControlServer {
+    /// Binds `SOCKET`, open to root and `GROUP`; `handle` decides what each may do.
+    pub fn bind() -> Result<Self> {
+        let path = Path::new(SOCKET);
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).with_context(|| format!("Failed to create {}", dir.display()))?;
+        }
+        // Left behind by a previous run.
+        let _ = fs::remove_file(path);
+        let listener = UnixListener::bind(path).with_context(|| format!("Failed to listen on {}", path.display()))?;
+        match nix::unistd::Group::from_name(GROUP) {
+            Ok(Some(group)) => nix::unistd::chown(path, None, Some(group.gid))
+                .with_context(|| format!("Failed to give {} to group {}", path.display(), GROUP))?,
+            _ => log::warn!("No '{}' group; only root can use {}", GROUP, path.display()),
+        }
+        fs::set_permissions(path, fs::Permissions::from_mode(0o660))?;
+        listener.set_nonblocking(true)?;
+        Ok(Self { listener })
+    }
+
+    /// Serves the requests already waiting, without blocking for more. A
+    /// client gets `REQUEST_DEADLINE` to send its request.
+    pub fn poll(&self, mut serve: impl FnMut(Request, Peer) -> Result<u32>) {
+        for _ in 0..MAX_PER_POLL {
+            let Ok((mut stream, _)) = self.listener.accept() else { break };
+            let reply = (|| {
+                stream.set_nonblocking(false)?;
+                stream.set_write_timeout(Some(REQUEST_DEADLINE))?;
+                let peer = peer(&stream)?;
+                let text = read_request(&mut stream, Instant::now() + REQUEST_DEADLINE)?;
+                let req: Request = serde_json::from_str(&text).context("Invalid request")?;
+                log::info!("Request from pid {} (uid {}): {:?}", peer.pid, peer.uid, req);
+                serve(req, peer)
+            })();
+            let reply = match reply {
+                Ok(id) => Reply { id: Some(id), error: None },
+                Err(e) => {
+                    log::warn!("Control request failed: {:#}", e);
+                    Reply { id: None, error: Some(format!("{:#}", e)) }
+                }
+            };
+            if let Ok(bytes) = serde_json::to_vec(&reply) {
+                let _ = stream.write_all(&bytes);
+            }
+        }
+    }
+}
+
+/// Reads until the client shuts down its end, giving up at `deadline` so a
+/// client that never writes can't hold the service up.
+fn read_request(stream: &mut UnixStream, deadline: Instant) -> Result<String> {
+    let mut buf = Vec::new();
+    let mut chunk = [0u8; 4096];
+    loop {
+        let left = deadline.saturating_duration_since(Instant::now());
+        if left.is_zero() {
+            bail!("Timed out waiting for the request");
+        }
+        stream.set_read_timeout(Some(left))?;
+        match stream.read(&mut chunk) {
+            Ok(0) => break,
+            Ok(n) => {
+                buf.extend_from_slice(&chunk[..n]);
+                if buf.len() as u64 > MAX_MESSAGE {
+                    bail!("Request too large");
+                }
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::Interrupted => {}
+            Err(e) if matches!(e.kind(), std::io::ErrorKind::WouldBlock | std::io::ErrorKind::TimedOut) => {
+                bail!("Timed out waiting for the request");
+            }
+            Err(e) => return Err(e).context("Failed to read the request"),
+        }
+    }
+    String::from_utf8(buf).context("Invalid request")
+}
+
+impl Drop for ControlServer {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(SOCKET);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ownership() {
+        let me = Peer { pid: std::process::id() as i32, uid: 1000 };
+        let own = Expiry::process(me.pid).unwrap();
+        assert!(owned_by(&own, me));
+        assert!(!owned_by(&Expiry::Manual, me));
+        assert!(!owned_by(&Expiry::Process { pid: me.pid, start_time: 1 }, me));
+        assert!(owned_by(&Expiry::Manual, Peer { uid: 0, ..me }));
+
+        let req = Request::Remove { id: 3 };
+        let json = serde_json::to_string(&req).unwrap();
+        assert!(matches!(serde_json::from_str::<Request>(&json).unwrap(), Request::Remove { id: 3 }));
+    }
+
+    #[test]
+    fn test_freq_range() {
+        let hw = (800, 4000);
+        let o = |min, max| Override { min_freq_mhz: min, max_freq_mhz: max, ..Override::new(Expiry::Manual) };
+        assert!(check_freq_range(&o(Some(800), Some(4000)), hw).is_ok());
+        assert!(check_freq_range(&o(None, Some(2000)), hw).is_ok());
+        assert!(check_freq_range(&o(Some(3000), Some(2000)), hw).is_err());
+        assert!(check_freq_range(&o(Some(400), None), hw).is_err());
+        assert!(check_freq_range(&o(None, Some(5000)), hw).is_err());
+    }
+
+    #[test]
+    fn test_request_deadline() {
+        let (mut server, mut client) = UnixStream::pair().unwrap();
+        let start = Instant::now();
+        assert!(read_request(&mut server, start + Duration::from_millis(50)).is_err());
+        assert!(start.elapsed() < Duration::from_secs(1));
+
+        client.write_all(b"{}").unwrap();
+        client.shutdown(std::net::Shutdown::Write).unwrap();
+        assert_eq!(read_request(&mut server, Instant::now() + REQUEST_DEADLINE).unwrap(), "{}");
+    }
+}
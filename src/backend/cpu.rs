@@ -93,6 +93,41 @@ const AMD_PSTATE_PATH: &str = "/sys/devices/system/cpu/amd_pstate";
 // FIX: AMD boost path is cpufreq/boost, not under amd_pstate
 const AMD_BOOST_PATH: &str = "/sys/devices/system/cpu/cpufreq/boost";
 
+/// Logical CPUs grouped by shared L3 cache (one group per CCX on AMD Zen),
+/// ordered by their lowest CPU number. Offline CPUs have no cache info and
+/// are left out.
+pub fn l3_cache_groups() -> Vec<Vec<usize>> {
+    let mut groups: Vec<((u32, u32), Vec<usize>)> = Vec::new();
+    let entries = match fs::read_dir(CPUFREQ_BASE) {
+        Ok(e) => e,
+        Err(_) => return Vec::new(),
+    };
+    for entry in entries.filter_map(|e| e.ok()) {
+        let name = entry.file_name().to_string_lossy().to_string();
+        let cpu: usize = match name.strip_prefix("cpu").and_then(|n| n.parse().ok()) {
+            Some(c) => c,
+            None => continue,
+        };
+        let read = |rel: &str| -> Option<u32> {
+            fs::read_to_string(entry.path().join(rel)).ok()?.trim().parse().ok()
+        };
+        let l3 = match read("cache/index3/id") {
+            Some(id) => id,
+            None => continue,
+        };
+        let key = (read("topology/physical_package_id").unwrap_or(0), l3);
+        match groups.iter_mut().find(|(k, _)| *k == key) {
+            Some((_, cpus)) => cpus.push(cpu),
+            None => groups.push((key, vec![cpu])),
+        }
+    }
+    let mut groups: Vec<Vec<usize>> = groups.into_iter()
+        .map(|(_, mut cpus)| { cpus.sort_unstable(); cpus })
+        .collect();
+    groups.sort_by_key(|cpus| cpus[0]);
+    groups
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CpuInfo {
     pub model: String,
@@ -358,6 +393,28 @@ impl CpuManager {
         Ok(())
     }
 
+    /// Caps every online core at `fraction` (0–1) of its own hardware range,
+    /// so hybrid P/E-core parts scale together. Logs once, not per core.
+    /// Returns the cap written for core 0, in MHz.
+    pub fn set_max_freq_fraction(&self, fraction: f32) -> Result<u32> {
+        self.check_write_permission()?;
+        let fraction = fraction.clamp(0.0, 1.0);
+        let mut first = None;
+        for core in 0..self.core_count {
+            let (Ok(min), Ok(max)) = (self.get_hardware_min_freq(core), self.get_hardware_max_freq(core)) else {
+                continue; // offline
+            };
+            let mhz = min + ((max - min) as f32 * fraction).round() as u32;
+            let path = format!("{}/cpu{}/cpufreq/scaling_max_freq", CPUFREQ_BASE, core);
+            if fs::write(&path, (mhz * 1000).to_string()).is_ok() && first.is_none() {
+                first = Some(mhz);
+            }
+        }
+        let mhz = first.context("Failed to set max frequency on any core. Run with sudo or enable PolicyKit.")?;
+        log::debug!("Capped all cores at {:.0}% of hardware range ({} MHz on core 0)", fraction * 100.0, mhz);
+        Ok(mhz)
+    }
+
     pub fn set_scaling_limits_all(&self, min_mhz: u32, max_mhz: u32) -> Result<()> {
         for core in 0..self.core_count {
             self.set_scaling_min_freq(core, min_mhz)?;
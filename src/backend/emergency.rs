@@ -0,0 +1,277 @@
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::time::{Duration, Instant};
+use crate::backend::cpu::CpuManager;
+
+/// `[thermal.emergency]`: what happens once `emergency_temp_celsius` is
+/// exceeded for `dwell_seconds`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EmergencyConfig {
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    /// How long the temperature must stay at or above the emergency
+    /// threshold before acting, so single-sample spikes are ignored.
+    #[serde(default = "default_dwell")]
+    pub dwell_seconds: u64,
+    /// Previous state is restored once the temperature drops below this.
+    #[serde(default = "default_recovery")]
+    pub recovery_celsius: f32,
+    /// Also take every core except cpu0 offline.
+    #[serde(default)]
+    pub offline_cores: bool,
+    /// Shell command run on entering the emergency state (empty = none).
+    /// `CPM_TEMP` holds the temperature in °C.
+    #[serde(default)]
+    pub command: String,
+}
+
+fn default_true() -> bool { true }
+fn default_dwell() -> u64 { 5 }
+fn default_recovery() -> f32 { 85.0 }
+
+impl Default for EmergencyConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            dwell_seconds: default_dwell(),
+            recovery_celsius: default_recovery(),
+            offline_cores: false,
+            command: String::new(),
+        }
+    }
+}
+
+/// Result of one `EmergencyGuard::update`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum EmergencyEvent {
+    Triggered { temp_celsius: f32 },
+    /// Triggered, but none of the clamp writes succeeded.
+    Failed { temp_celsius: f32 },
+    Recovered { temp_celsius: f32 },
+}
+
+impl EmergencyEvent {
+    /// Notification summary and body.
+    pub fn message(&self) -> (&'static str, String) {
+        use crate::utils::units::fmt_temp;
+        match *self {
+            EmergencyEvent::Triggered { temp_celsius } => (
+                "CPU Thermal Emergency",
+                format!("CPU at {} — clamped to minimum frequency with turbo off.", fmt_temp(temp_celsius, 1)),
+            ),
+            EmergencyEvent::Failed { temp_celsius } => (
+                "CPU Thermal Emergency",
+                format!("CPU at {} — failed to clamp frequency or turbo; see the log.", fmt_temp(temp_celsius, 1)),
+            ),
+            EmergencyEvent::Recovered { temp_celsius } => (
+                "CPU Temperature Recovered",
+                format!("CPU at {} — previous limits restored.", fmt_temp(temp_celsius, 1)),
+            ),
+        }
+    }
+
+    /// Desktop notification; silently does nothing without a session bus.
+    /// The background service queues `message()` in its status file instead.
+    pub fn notify(&self) {
+        let (summary, body) = self.message();
+        let icon = match self {
+            EmergencyEvent::Recovered { .. } => "dialog-information",
+            _ => "dialog-error",
+        };
+        let _ = notify_rust::Notification::new()
+            .summary(summary)
+            .body(&body)
+            .icon(icon)
+            .timeout(notify_rust::Timeout::Milliseconds(8000))
+            .show();
+    }
+}
+
+/// What the emergency path changed, so it can be put back.
+#[derive(Debug, Default)]
+struct SavedState {
+    /// (core, scaling_min MHz, scaling_max MHz)
+    limits: Vec<(usize, u32, u32)>,
+    turbo: Option<bool>,
+    offlined: Vec<usize>,
+}
+
+#[derive(Debug)]
+enum State {
+    Normal,
+    Hot { since: Instant },
+    Active(SavedState),
+    /// Engaging failed; waits for the temperature to drop before retrying.
+    Failed,
+}
+
+/// Watches the CPU temperature and applies / lifts the emergency clamp.
+#[derive(Debug)]
+pub struct EmergencyGuard {
+    state: State,
+}
+
+impl Default for EmergencyGuard {
+    fn default() -> Self { Self::new() }
+}
+
+impl EmergencyGuard {
+    pub fn new() -> Self { Self { state: State::Normal } }
+
+    pub fn is_active(&self) -> bool { matches!(self.state, State::Active(_)) }
+
+    /// Feed one temperature sample. `threshold` is `emergency_temp_celsius`.
+    pub fn update(
+        &mut self,
+        cfg: &EmergencyConfig,
+        threshold: f32,
+        temp: f32,
+        cpu: &CpuManager,
+    ) -> Option<EmergencyEvent> {
+        // Disabling (or handing over to the service) lifts the clamp quietly.
+        if !cfg.enabled {
+            if self.release(cpu) {
+                log::info!("Thermal emergency clamp released");
+            }
+            return None;
+        }
+        match self.advance(cfg, threshold, temp, Instant::now()) {
+            Some(EmergencyEvent::Triggered { .. }) => {
+                log::warn!("Thermal emergency: {:.1}°C ≥ {:.1}°C for {} s", temp, threshold, cfg.dwell_seconds);
+                let (saved, applied) = engage(cfg, temp, cpu);
+                if applied {
+                    self.state = State::Active(saved);
+                    Some(EmergencyEvent::Triggered { temp_celsius: temp })
+                } else {
+                    log::error!("Thermal emergency: no clamp could be applied");
+                    self.state = State::Failed;
+                    Some(EmergencyEvent::Failed { temp_celsius: temp })
+                }
+            }
+            Some(EmergencyEvent::Recovered { .. }) => {
+                log::info!("Thermal emergency over: {:.1}°C < {:.1}°C", temp, cfg.recovery_celsius);
+                self.release(cpu);
+                Some(EmergencyEvent::Recovered { temp_celsius: temp })
+            }
+            Some(EmergencyEvent::Failed { .. }) | None => None,
+        }
+    }
+
+    /// Dwell / recovery state machine, without touching the hardware.
+    fn advance(&mut self, cfg: &EmergencyConfig, threshold: f32, temp: f32, now: Instant) -> Option<EmergencyEvent> {
+        match &self.state {
+            State::Normal | State::Hot { .. } | State::Failed if temp < threshold => {
+                self.state = State::Normal;
+                None
+            }
+            State::Failed => None,
+            State::Normal => {
+                self.state = State::Hot { since: now };
+                (cfg.dwell_seconds == 0).then_some(EmergencyEvent::Triggered { temp_celsius: temp })
+            }
+            State::Hot { since } => (now.duration_since(*since) >= Duration::from_secs(cfg.dwell_seconds))
+                .then_some(EmergencyEvent::Triggered { temp_celsius: temp }),
+            State::Active(_) => (temp < cfg.recovery_celsius.min(threshold))
+                .then_some(EmergencyEvent::Recovered { temp_celsius: temp }),
+        }
+    }
+
+    /// Restores whatever the emergency path changed. Returns true if it was active.
+    pub fn release(&mut self, cpu: &CpuManager) -> bool {
+        let State::Active(saved) = std::mem::replace(&mut self.state, State::Normal) else {
+            return false;
+        };
+        for &core in &saved.offlined {
+            if let Err(e) = cpu.set_core_online(core, true) {
+                log::warn!("Failed to bring core {} back online: {}", core, e);
+            }
+        }
+        for &(core, min, max) in &saved.limits {
+            // Max first: it is still at the hardware minimum.
+            let _ = cpu.set_scaling_max_freq(core, max);
+            let _ = cpu.set_scaling_min_freq(core, min);
+        }
+        if let Some(turbo) = saved.turbo {
+            if let Err(e) = cpu.set_turbo(turbo) {
+                log::warn!("Failed to restore turbo: {}", e);
+            }
+        }
+        true
+    }
+}
+
+/// Applies the clamp. The flag is false when no frequency, turbo or core
+/// write succeeded, so there is nothing to restore.
+fn engage(cfg: &EmergencyConfig, temp: f32, cpu: &CpuManager) -> (SavedState, bool) {
+    let mut saved = SavedState::default();
+    let mut applied = false;
+    let mut try_step = |what: &str, r: Result<()>| match r {
+        Ok(()) => applied = true,
+        Err(e) => log::error!("Thermal emergency: {} failed: {}", what, e),
+    };
+
+    for core in 0..cpu.core_count() {
+        let (Ok(min), Ok(max), Ok(hw_min)) = (
+            cpu.get_scaling_min_freq(core), cpu.get_scaling_max_freq(core), cpu.get_hardware_min_freq(core),
+        ) else { continue };
+        saved.limits.push((core, min, max));
+        try_step("min clamp", cpu.set_scaling_min_freq(core, hw_min));
+        try_step("max clamp", cpu.set_scaling_max_freq(core, hw_min));
+    }
+
+    if let Ok(turbo) = cpu.is_turbo_enabled() {
+        saved.turbo = Some(turbo);
+        try_step("turbo off", cpu.set_turbo(false));
+    }
+
+    if cfg.offline_cores {
+        for core in 1..cpu.core_count() {
+            if cpu.is_core_online(core).unwrap_or(false) && cpu.set_core_online(core, false).is_ok() {
+                saved.offlined.push(core);
+                applied = true;
+            }
+        }
+    }
+
+    if !cfg.command.trim().is_empty() {
+        let spawned = std::process::Command::new("sh")
+            .arg("-c").arg(&cfg.command)
+            .env("CPM_TEMP", format!("{:.1}", temp))
+            .spawn();
+        if let Err(e) = spawned {
+            log::error!("Thermal emergency: emergency command failed: {}", e);
+        }
+    }
+    (saved, applied)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_dwell_and_recovery() {
+        let cfg = EmergencyConfig { dwell_seconds: 5, recovery_celsius: 85.0, ..Default::default() };
+        let mut g = EmergencyGuard::new();
+        let t0 = Instant::now();
+        let at = |s| t0 + Duration::from_secs(s);
+
+        assert_eq!(g.advance(&cfg, 95.0, 96.0, at(0)), None);
+        // A dip resets the dwell timer.
+        assert_eq!(g.advance(&cfg, 95.0, 94.0, at(2)), None);
+        assert_eq!(g.advance(&cfg, 95.0, 96.0, at(3)), None);
+        assert_eq!(g.advance(&cfg, 95.0, 97.0, at(7)), None);
+        assert!(matches!(g.advance(&cfg, 95.0, 97.0, at(8)), Some(EmergencyEvent::Triggered { .. })));
+
+        g.state = State::Active(SavedState::default());
+        // Between recovery and emergency thresholds: stays clamped.
+        assert_eq!(g.advance(&cfg, 95.0, 90.0, at(9)), None);
+        assert!(matches!(g.advance(&cfg, 95.0, 84.0, at(10)), Some(EmergencyEvent::Recovered { .. })));
+
+        // After a failed engage, no retry until the temperature has dropped.
+        g.state = State::Failed;
+        assert_eq!(g.advance(&cfg, 95.0, 99.0, at(20)), None);
+        assert_eq!(g.advance(&cfg, 95.0, 90.0, at(21)), None);
+        assert!(matches!(g.state, State::Normal));
+    }
+}
@@ -0,0 +1,364 @@
+use anyhow::{Context, Result};
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+use crate::backend::hwmon::HwmonReader;
+
+/// `pwmN_enable` value that hands the channel to userspace.
+const PWM_ENABLE_MANUAL: &str = "1";
+
+/// A controllable `pwmN` output on a hwmon device.
+#[derive(Debug, Clone)]
+pub struct PwmChannel {
+    /// hwmon driver name, e.g. `nct6798` or `thinkpad`.
+    pub device_name: String,
+    /// hwmon device directory; the `hwmonN` number is not stable across boots.
+    pub path: PathBuf,
+    pub index: u32,
+}
+
+impl PwmChannel {
+    /// Every `pwmN` file that has a matching `pwmN_enable`.
+    pub fn discover() -> Vec<PwmChannel> {
+        let mut channels = Vec::new();
+        for dev in HwmonReader::devices() {
+            let mut indices: Vec<u32> = fs::read_dir(&dev.path)
+                .map(|entries| entries
+                    .filter_map(|e| e.ok())
+                    .filter_map(|e| e.file_name().to_string_lossy().strip_prefix("pwm")?.parse().ok())
+                    .collect())
+                .unwrap_or_default();
+            indices.sort_unstable();
+            for index in indices {
+                if dev.path.join(format!("pwm{}_enable", index)).exists() {
+                    channels.push(PwmChannel { device_name: dev.name.clone(), path: dev.path.clone(), index });
+                }
+            }
+        }
+        channels
+    }
+
+    /// Identifier stored in config, e.g. `nct6798/pwm2`.
+    pub fn key(&self) -> String { format!("{}/pwm{}", self.device_name, self.index) }
+
+    fn pwm_path(&self) -> PathBuf { self.path.join(format!("pwm{}", self.index)) }
+    fn enable_path(&self) -> PathBuf { self.path.join(format!("pwm{}_enable", self.index)) }
+
+    /// RPM of the fan with the same index, when the chip pairs them.
+    pub fn fan_rpm(&self) -> Option<u32> {
+        fs::read_to_string(self.path.join(format!("fan{}_input", self.index))).ok()?.trim().parse().ok()
+    }
+
+    /// Switches the channel to manual mode, remembering the original
+    /// `pwmN_enable`/`pwmN` values the first time so they can be restored.
+    pub fn take_control(&self) -> Result<()> {
+        let enable = fs::read_to_string(self.enable_path())
+            .with_context(|| format!("Failed to read {}", self.enable_path().display()))?
+            .trim()
+            .to_string();
+        {
+            let mut saved = SAVED_STATE.lock();
+            if !saved.iter().any(|s| s.path == self.path && s.index == self.index) {
+                saved.push(SavedPwm {
+                    path: self.path.clone(),
+                    index: self.index,
+                    enable: enable.clone(),
+                    pwm: fs::read_to_string(self.pwm_path()).ok().map(|p| p.trim().to_string()),
+                });
+            }
+        }
+        if enable != PWM_ENABLE_MANUAL {
+            fs::write(self.enable_path(), PWM_ENABLE_MANUAL).with_context(|| {
+                format!("Failed to set {} to manual mode. Run with sudo or enable PolicyKit.", self.key())
+            })?;
+            log::info!("Fan {} switched to manual control (was mode {})", self.key(), enable);
+        }
+        Ok(())
+    }
+
+    pub fn write_duty(&self, percent: u8) -> Result<()> {
+        let raw = (percent.min(100) as u32 * 255 + 50) / 100;
+        fs::write(self.pwm_path(), raw.to_string())
+            .with_context(|| format!("Failed to set {} duty", self.key()))
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub struct CurvePoint {
+    pub temp_celsius: f32,
+    pub duty_percent: u8,
+}
+
+/// Temperature-to-duty curve for one PWM channel, stored in `[[thermal.fan_curves]]`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FanCurve {
+    /// `PwmChannel::key()` of the channel this curve drives.
+    pub channel: String,
+    pub points: Vec<CurvePoint>,
+    /// Duty only drops once the temperature has fallen this far below the
+    /// temperature that set the current duty.
+    #[serde(default = "default_hysteresis")]
+    pub hysteresis_celsius: f32,
+    /// Lowest non-zero duty; anything between 0 and this is raised to it so
+    /// the fan never sits at a stall speed.
+    #[serde(default = "default_min_duty")]
+    pub min_duty_percent: u8,
+    /// Duty used briefly when starting a stopped fan.
+    #[serde(default = "default_spinup_duty")]
+    pub spinup_duty_percent: u8,
+    #[serde(default = "default_spinup_ms")]
+    pub spinup_ms: u64,
+}
+
+fn default_hysteresis() -> f32 { 3.0 }
+fn default_min_duty() -> u8 { 20 }
+fn default_spinup_duty() -> u8 { 50 }
+fn default_spinup_ms() -> u64 { 1000 }
+
+impl FanCurve {
+    pub fn new(channel: &str) -> Self {
+        Self {
+            channel: channel.to_string(),
+            points: vec![
+                CurvePoint { temp_celsius: 40.0, duty_percent: 20 },
+                CurvePoint { temp_celsius: 60.0, duty_percent: 40 },
+                CurvePoint { temp_celsius: 75.0, duty_percent: 70 },
+                CurvePoint { temp_celsius: 85.0, duty_percent: 100 },
+            ],
+            hysteresis_celsius: default_hysteresis(),
+            min_duty_percent: default_min_duty(),
+            spinup_duty_percent: default_spinup_duty(),
+            spinup_ms: default_spinup_ms(),
+        }
+    }
+
+    /// Linear interpolation between points, flat beyond either end,
+    /// with the minimum-duty floor applied.
+    pub fn duty_at(&self, temp: f32) -> u8 {
+        let mut pts = self.points.clone();
+        pts.sort_by(|a, b| a.temp_celsius.total_cmp(&b.temp_celsius));
+        let raw = match (pts.first(), pts.last()) {
+            (None, _) | (_, None) => 100,
+            (Some(first), _) if temp <= first.temp_celsius => first.duty_percent,
+            (_, Some(last)) if temp >= last.temp_celsius => last.duty_percent,
+            _ => {
+                let i = pts.iter().position(|p| p.temp_celsius > temp).unwrap_or(pts.len() - 1);
+                let (a, b) = (pts[i - 1], pts[i]);
+                let t = (temp - a.temp_celsius) / (b.temp_celsius - a.temp_celsius);
+                (a.duty_percent as f32 + t * (b.duty_percent as f32 - a.duty_percent as f32)).round() as u8
+            }
+        };
+        match raw.min(100) {
+            0 => 0,
+            d => d.max(self.min_duty_percent.min(100)),
+        }
+    }
+}
+
+#[derive(Debug)]
+struct SavedPwm {
+    path: PathBuf,
+    index: u32,
+    enable: String,
+    pwm: Option<String>,
+}
+
+/// Original state of every channel taken over, so automatic mode can be
+/// restored from `Drop`, app shutdown, signal handlers and the panic hook.
+static SAVED_STATE: Lazy<Mutex<Vec<SavedPwm>>> = Lazy::new(|| Mutex::new(Vec::new()));
+
+/// Hands every channel we switched to manual back to its original mode.
+/// Safe to call repeatedly.
+pub fn restore_automatic() {
+    restore_where(|_| true);
+}
+
+fn restore_where(pred: impl Fn(&SavedPwm) -> bool) {
+    let saved: Vec<SavedPwm> = {
+        let mut all = SAVED_STATE.lock();
+        let (restore, keep) = std::mem::take(&mut *all).into_iter().partition(|s| pred(s));
+        *all = keep;
+        restore
+    };
+    restore_saved(saved);
+}
+
+/// `restore_automatic` without waiting for the lock; false if it is held.
+fn try_restore_automatic() -> bool {
+    let Some(mut all) = SAVED_STATE.try_lock() else { return false };
+    let saved = std::mem::take(&mut *all);
+    drop(all);
+    restore_saved(saved);
+    true
+}
+
+fn restore_saved(saved: Vec<SavedPwm>) {
+    for s in saved {
+        let base = s.path.join(format!("pwm{}", s.index));
+        if s.enable == PWM_ENABLE_MANUAL {
+            // Was already manual before us: put the old duty back.
+            if let Some(pwm) = &s.pwm { let _ = fs::write(&base, pwm); }
+        }
+        match fs::write(s.path.join(format!("pwm{}_enable", s.index)), &s.enable) {
+            Ok(_) => log::info!("Restored {} to mode {}", base.display(), s.enable),
+            Err(e) => {
+                // Last resort: full speed is always safe.
+                log::error!("Failed to restore {}: {}", base.display(), e);
+                let _ = fs::write(&base, "255");
+            }
+        }
+    }
+}
+
+/// Restores automatic fan control before the default panic output runs.
+pub fn install_panic_hook() {
+    let previous = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        // A panic while the saved state is locked (in `FanController::update`,
+        // say) still holds the lock here; waiting for it would hang.
+        if !try_restore_automatic() {
+            log::error!("Fan state locked during panic; fan modes not restored");
+        }
+        previous(info);
+    }));
+}
+
+#[derive(Debug, Default)]
+struct ChannelState {
+    /// Temperature that produced the current duty, for hysteresis.
+    tracked_temp: Option<f32>,
+    duty: Option<u8>,
+    spinup_until: Option<Instant>,
+}
+
+/// Drives PWM channels from their curves. Call `update()` once per tick.
+pub struct FanController {
+    channels: Vec<PwmChannel>,
+    state: Vec<(String, ChannelState)>,
+}
+
+impl FanController {
+    pub fn new() -> Self {
+        Self { channels: PwmChannel::discover(), state: Vec::new() }
+    }
+
+    pub fn channels(&self) -> &[PwmChannel] { &self.channels }
+
+    pub fn is_active(&self) -> bool { !self.state.is_empty() }
+
+    /// Current duty per channel key, as last written.
+    pub fn duties(&self) -> Vec<(String, u8)> {
+        self.state.iter().filter_map(|(k, s)| Some((k.clone(), s.duty?))).collect()
+    }
+
+    pub fn update(&mut self, curves: &[FanCurve], temp: f32) {
+        // Channels whose curve was removed go back to firmware control.
+        let channels = &self.channels;
+        self.state.retain(|(key, _)| {
+            if curves.iter().any(|c| c.channel == *key) { return true; }
+            if let Some(ch) = channels.iter().find(|c| c.key() == *key) {
+                restore_where(|s| s.path == ch.path && s.index == ch.index);
+            }
+            false
+        });
+
+        for curve in curves {
+            let Some(channel) = self.channels.iter().find(|c| c.key() == curve.channel) else {
+                log::debug!("Fan curve for unknown channel {}", curve.channel);
+                continue;
+            };
+            if !self.state.iter().any(|(k, _)| *k == curve.channel) {
+                if let Err(e) = channel.take_control() {
+                    log::warn!("{}", e);
+                    continue;
+                }
+                self.state.push((curve.channel.clone(), ChannelState::default()));
+            }
+            let state = &mut self.state.iter_mut().find(|(k, _)| *k == curve.channel).unwrap().1;
+
+            let effective = match state.tracked_temp {
+                Some(t) if temp <= t && temp > t - curve.hysteresis_celsius => t,
+                _ => temp,
+            };
+            state.tracked_temp = Some(effective);
+            let target = curve.duty_at(effective);
+
+            let now = Instant::now();
+            let stopped = match state.duty {
+                Some(d) => d == 0,
+                None => channel.fan_rpm() == Some(0),
+            };
+            let duty = if target > 0 && target < curve.spinup_duty_percent && stopped && state.spinup_until.is_none() {
+                state.spinup_until = Some(now + Duration::from_millis(curve.spinup_ms));
+                curve.spinup_duty_percent
+            } else if state.spinup_until.is_some_and(|until| now < until) {
+                curve.spinup_duty_percent.max(target)
+            } else {
+                state.spinup_until = None;
+                target
+            };
+
+            if state.duty != Some(duty) {
+                match channel.write_duty(duty) {
+                    Ok(_) => {
+                        log::debug!("Fan {} → {}% at {:.1}°C", curve.channel, duty, temp);
+                        state.duty = Some(duty);
+                    }
+                    Err(e) => log::warn!("{}", e),
+                }
+            }
+        }
+    }
+
+    /// Stops driving all channels and restores their automatic mode.
+    pub fn release(&mut self) {
+        if self.is_active() {
+            self.state.clear();
+            restore_automatic();
+        }
+    }
+}
+
+impl Drop for FanController {
+    fn drop(&mut self) {
+        self.release();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_duty_interpolation() {
+        let curve = FanCurve::new("test/pwm1");
+        assert_eq!(curve.duty_at(20.0), 20);
+        assert_eq!(curve.duty_at(50.0), 30);
+        assert_eq!(curve.duty_at(80.0), 85);
+        assert_eq!(curve.duty_at(95.0), 100);
+    }
+
+    #[test]
+    fn test_restore_with_lock_held() {
+        let held = SAVED_STATE.lock();
+        assert!(!try_restore_automatic());
+        drop(held);
+        assert!(try_restore_automatic());
+    }
+
+    #[test]
+    fn test_min_duty_floor() {
+        let mut curve = FanCurve::new("test/pwm1");
+        curve.points = vec![
+            CurvePoint { temp_celsius: 40.0, duty_percent: 0 },
+            CurvePoint { temp_celsius: 80.0, duty_percent: 100 },
+        ];
+        curve.min_duty_percent = 25;
+        assert_eq!(curve.duty_at(30.0), 0);
+        assert_eq!(curve.duty_at(44.0), 25);
+        assert_eq!(curve.duty_at(70.0), 75);
+    }
+}
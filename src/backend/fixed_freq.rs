@@ -0,0 +1,347 @@
+use anyhow::{bail, Context, Result};
+use chrono::Local;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::fs;
+use std::path::{Path, PathBuf};
+use crate::backend::cpu::CpuManager;
+use crate::backend::overrides::{self, Expiry, Override};
+use crate::backend::profile::ProfileManager;
+
+/// The active lock, for whoever has to undo it. Under /run like the
+/// override stack, so it never outlasts a reboot.
+pub const LOCK_FILE: &str = "/run/cpu-power-manager/fixed-frequency.toml";
+
+const CPU_BASE: &str = "/sys/devices/system/cpu";
+const SMT_CONTROL: &str = "/sys/devices/system/cpu/smt/control";
+
+/// Reported frequencies this far off target (as a share) fail verification.
+const TOLERANCE: f64 = 0.05;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct LockOptions {
+    pub freq_mhz: u32,
+    /// Disable every idle state past C1 (`state2` and up).
+    pub disable_deep_cstates: bool,
+    pub disable_smt: bool,
+}
+
+/// What a lock changed. Frequency and turbo are held by an override, so
+/// the stack's base snapshot brings them back; idle states and SMT are
+/// recorded here.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FixedLock {
+    pub options: LockOptions,
+    pub override_id: u32,
+    /// The lock ends with its owner.
+    pub expiry: Expiry,
+    /// (cpu, idle state) pairs this lock disabled.
+    #[serde(default)]
+    pub cstates: Vec<(usize, usize)>,
+    /// SMT control before the lock, if the lock changed it.
+    #[serde(default)]
+    pub smt: Option<String>,
+}
+
+impl FixedLock {
+    pub fn load() -> Option<Self> {
+        toml::from_str(&fs::read_to_string(LOCK_FILE).ok()?).ok()
+    }
+
+    fn save(&self) -> Result<()> {
+        if let Some(dir) = Path::new(LOCK_FILE).parent() {
+            fs::create_dir_all(dir).ok();
+        }
+        fs::write(LOCK_FILE, toml::to_string(self)?).with_context(|| format!("Failed to write {}", LOCK_FILE))
+    }
+
+    /// `2000 MHz, turbo off, deep C-states off, SMT off`
+    pub fn describe(&self) -> String {
+        let mut parts = vec![format!("{} MHz", self.options.freq_mhz), "turbo off".to_string()];
+        if !self.cstates.is_empty() {
+            parts.push("deep C-states off".into());
+        }
+        if self.smt.is_some() {
+            parts.push("SMT off".into());
+        }
+        parts.join(", ")
+    }
+}
+
+/// The lockable frequency nearest `mhz`: one the driver lists where it
+/// lists any, within the hardware limits either way.
+pub fn snap(cpu: &CpuManager, mhz: u32) -> Result<u32> {
+    let (min, max) = (cpu.get_hardware_min_freq(0)?, cpu.get_hardware_max_freq(0)?);
+    if !(min..=max).contains(&mhz) {
+        bail!("{} MHz is outside the hardware range {}–{} MHz", mhz, min, max);
+    }
+    let available = cpu.get_available_frequencies(0).unwrap_or_default();
+    Ok(available.into_iter().min_by_key(|f| f.abs_diff(mhz)).unwrap_or(mhz))
+}
+
+/// Pins every policy to `opts.freq_mhz` with turbo off, then disables deep
+/// idle states and SMT if asked. Undone by `unlock`, or by `recover_stale`
+/// once `owner` has expired. A lock left by an owner that died is released
+/// first. While locked, the thermal limiter stands down.
+pub fn lock(opts: LockOptions, owner: Expiry, profiles: &ProfileManager, cpu: &CpuManager) -> Result<FixedLock> {
+    recover_stale(profiles, cpu);
+    if let Some(l) = FixedLock::load() {
+        bail!("Already locked at {}; unlock first", l.describe());
+    }
+    let freq = snap(cpu, opts.freq_mhz)?;
+    let o = Override {
+        turbo: Some(false),
+        min_freq_mhz: Some(freq),
+        max_freq_mhz: Some(freq),
+        ..Override::new(owner.clone())
+    };
+    let override_id = overrides::push(o, profiles, cpu, true)?;
+    let mut lock = FixedLock {
+        options: LockOptions { freq_mhz: freq, ..opts },
+        override_id,
+        expiry: owner,
+        cstates: Vec::new(),
+        smt: None,
+    };
+    let result = (|| -> Result<()> {
+        lock.save()?;
+        if opts.disable_deep_cstates {
+            let disabled = disable_deep_cstates(&mut lock.cstates);
+            lock.save()?;
+            disabled?;
+        }
+        if opts.disable_smt {
+            lock.smt = disable_smt()?;
+            lock.save()?;
+        }
+        Ok(())
+    })();
+    if let Err(e) = result {
+        let _ = unlock(profiles, cpu);
+        return Err(e);
+    }
+    log::info!("Locked CPU at {}", lock.describe());
+    Ok(lock)
+}
+
+/// Undoes the lock in reverse: SMT, idle states, then frequency and turbo.
+/// Returns the lock that was released, if any.
+pub fn unlock(profiles: &ProfileManager, cpu: &CpuManager) -> Result<Option<FixedLock>> {
+    let Some(lock) = FixedLock::load() else {
+        return Ok(None);
+    };
+    if let Some(control) = &lock.smt {
+        if let Err(e) = fs::write(SMT_CONTROL, control) {
+            log::warn!("Failed to restore SMT: {}", e);
+        }
+    }
+    for &(cpu, state) in &lock.cstates {
+        if let Err(e) = fs::write(idle_state(cpu, state).join("disable"), "0") {
+            log::warn!("Failed to re-enable cpu{} idle state {}: {}", cpu, state, e);
+        }
+    }
+    overrides::remove(Some(lock.override_id), profiles, cpu, true)?;
+    fs::remove_file(LOCK_FILE).with_context(|| format!("Failed to remove {}", LOCK_FILE))?;
+    log::info!("Released fixed-frequency lock ({})", lock.describe());
+    Ok(Some(lock))
+}
+
+/// Releases a lock whose owner has gone; for the service and GUI loops.
+pub fn recover_stale(profiles: &ProfileManager, cpu: &CpuManager) -> bool {
+    match FixedLock::load() {
+        Some(lock) if lock.expiry.expired(Local::now().timestamp()) => {
+            log::info!("Fixed-frequency lock owner is gone, restoring");
+            if let Err(e) = unlock(profiles, cpu) {
+                log::warn!("Failed to release the fixed-frequency lock: {:#}", e);
+            }
+            true
+        }
+        _ => false,
+    }
+}
+
+/// Releases the lock if this process holds it; for shutdown.
+pub fn release_owned(profiles: &ProfileManager, cpu: &CpuManager) {
+    let own = std::process::id() as i32;
+    if FixedLock::load().is_some_and(|l| matches!(l.expiry, Expiry::Process { pid, .. } if pid == own)) {
+        if let Err(e) = unlock(profiles, cpu) {
+            log::warn!("Failed to release the fixed-frequency lock: {:#}", e);
+        }
+    }
+}
+
+fn idle_state(cpu: usize, state: usize) -> PathBuf {
+    PathBuf::from(format!("{}/cpu{}/cpuidle/state{}", CPU_BASE, cpu, state))
+}
+
+/// Records each state in `disabled` as it goes, so a failure part way
+/// through can still be undone.
+fn disable_deep_cstates(disabled: &mut Vec<(usize, usize)>) -> Result<()> {
+    let cpus = fs::read_dir(CPU_BASE).context("Failed to read CPU directory")?
+        .filter_map(|e| e.ok())
+        .filter_map(|e| e.file_name().to_str()?.strip_prefix("cpu")?.parse::<usize>().ok());
+    for cpu in cpus {
+        // state0 is polling and state1 is C1; everything past that is deep.
+        for state in 2.. {
+            let dir = idle_state(cpu, state);
+            let Ok(current) = fs::read_to_string(dir.join("disable")) else { break };
+            if current.trim() == "0" {
+                fs::write(dir.join("disable"), "1")
+                    .with_context(|| format!("Failed to disable cpu{} idle state {}", cpu, state))?;
+                disabled.push((cpu, state));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Turns SMT off. Returns the previous control value if it changed.
+fn disable_smt() -> Result<Option<String>> {
+    let control = fs::read_to_string(SMT_CONTROL).context("SMT control is not available")?;
+    match control.trim() {
+        "on" => {
+            fs::write(SMT_CONTROL, "off").context("Failed to disable SMT")?;
+            Ok(Some("on".into()))
+        }
+        "off" | "forceoff" => Ok(None),
+        other => bail!("SMT can't be switched off here ({})", other),
+    }
+}
+
+/// Tracks readings until they stop moving: stable once the last `window`
+/// samples span at most `tolerance` degrees.
+#[derive(Debug)]
+pub struct Stabiliser {
+    window: usize,
+    tolerance: f32,
+    temps: VecDeque<f32>,
+}
+
+impl Stabiliser {
+    pub fn new(window: usize, tolerance: f32) -> Self {
+        Self { window: window.max(2), tolerance, temps: VecDeque::new() }
+    }
+
+    /// Adds a reading; true once stable.
+    pub fn push(&mut self, temp: f32) -> bool {
+        self.temps.push_back(temp);
+        if self.temps.len() > self.window {
+            self.temps.pop_front();
+        }
+        self.is_stable()
+    }
+
+    pub fn is_stable(&self) -> bool {
+        let lo = self.temps.iter().copied().fold(f32::MAX, f32::min);
+        let hi = self.temps.iter().copied().fold(f32::MIN, f32::max);
+        self.temps.len() >= self.window && hi - lo <= self.tolerance
+    }
+
+    pub fn last(&self) -> Option<f32> {
+        self.temps.back().copied()
+    }
+}
+
+/// The lock as read back from sysfs.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Verification {
+    pub freq_mhz: u32,
+    /// Online cores whose limits aren't min = max = target: (core, min, max).
+    pub bad_limits: Vec<(usize, u32, u32)>,
+    pub turbo: Option<bool>,
+    /// Online cores reporting a frequency off target: (core, MHz).
+    pub off_target: Vec<(usize, u32)>,
+    /// Lowest and highest reported frequency.
+    pub range: Option<(u32, u32)>,
+    pub cores: usize,
+}
+
+impl Verification {
+    pub fn held(&self) -> bool {
+        self.bad_limits.is_empty() && self.turbo != Some(true) && self.off_target.is_empty()
+    }
+
+    /// `Lock held: 8 cores at 1995–2003 MHz, turbo off`
+    pub fn summary(&self) -> String {
+        let range = match self.range {
+            Some((lo, hi)) if lo == hi => format!("{} MHz", lo),
+            Some((lo, hi)) => format!("{}–{} MHz", lo, hi),
+            None => "unknown frequency".into(),
+        };
+        let turbo = match self.turbo {
+            Some(true) => "turbo ON",
+            Some(false) => "turbo off",
+            None => "turbo unknown",
+        };
+        if self.held() {
+            return format!("Lock held: {} cores at {}, {}", self.cores, range, turbo);
+        }
+        let mut problems = Vec::new();
+        if !self.bad_limits.is_empty() {
+            let cores: Vec<String> = self.bad_limits.iter().map(|(c, min, max)| format!("cpu{} {}–{}", c, min, max)).collect();
+            problems.push(format!("limits changed on {}", cores.join(", ")));
+        }
+        if self.turbo == Some(true) {
+            problems.push("turbo is on".into());
+        }
+        if !self.off_target.is_empty() {
+            let cores: Vec<String> = self.off_target.iter().map(|(c, f)| format!("cpu{} {} MHz", c, f)).collect();
+            problems.push(format!("off target: {}", cores.join(", ")));
+        }
+        format!("Lock NOT held at {} MHz ({}): {}", self.freq_mhz, range, problems.join("; "))
+    }
+}
+
+pub fn verify(cpu: &CpuManager, freq_mhz: u32) -> Verification {
+    let mut v = Verification {
+        freq_mhz,
+        bad_limits: Vec::new(),
+        turbo: cpu.is_turbo_enabled().ok(),
+        off_target: Vec::new(),
+        range: None,
+        cores: 0,
+    };
+    for core in 0..cpu.core_count() {
+        if !cpu.is_core_online(core).unwrap_or(true) {
+            continue;
+        }
+        let (Ok(min), Ok(max)) = (cpu.get_scaling_min_freq(core), cpu.get_scaling_max_freq(core)) else { continue };
+        v.cores += 1;
+        if min != freq_mhz || max != freq_mhz {
+            v.bad_limits.push((core, min, max));
+        }
+        if let Ok(cur) = cpu.get_frequency(core) {
+            v.range = Some(v.range.map_or((cur, cur), |(lo, hi)| (lo.min(cur), hi.max(cur))));
+            if off_target(cur, freq_mhz) {
+                v.off_target.push((core, cur));
+            }
+        }
+    }
+    v
+}
+
+fn off_target(cur: u32, target: u32) -> bool {
+    cur.abs_diff(target) as f64 > target as f64 * TOLERANCE
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stabiliser_and_tolerance() {
+        let mut s = Stabiliser::new(4, 1.0);
+        assert!(!s.push(60.0));
+        for t in [55.0, 52.0, 51.0, 50.5, 50.8] {
+            assert!(!s.push(t));
+        }
+        assert!(s.push(50.6));
+        assert!(s.is_stable());
+        assert_eq!(s.last(), Some(50.6));
+        assert!(!s.push(53.0));
+
+        assert!(!off_target(2050, 2000));
+        assert!(off_target(2200, 2000));
+        assert!(off_target(1800, 2000));
+    }
+}
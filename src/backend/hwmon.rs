@@ -1,56 +1,387 @@
 use std::fs;
-use std::path::Path;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use crate::backend::cpu::l3_cache_groups;
+
+const HWMON_BASE: &str = "/sys/class/hwmon";
+
+/// How long a directory scan is trusted before hwmon is enumerated again.
+const LAYOUT_TTL: Duration = Duration::from_secs(30);
+
+/// Devices, names, labels and channel numbers from the last scan; values
+/// are re-read on every `devices()` call.
+static LAYOUT: Mutex<Option<(Instant, Vec<HwmonDevice>)>> = Mutex::new(None);
+
+/// Channel types exposed by the hwmon sysfs ABI.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SensorKind {
+    Temp,
+    Fan,
+    In,
+    Power,
+    Curr,
+}
+
+impl SensorKind {
+    pub const ALL: [SensorKind; 5] = [Self::Temp, Self::Fan, Self::In, Self::Power, Self::Curr];
+
+    fn prefix(self) -> &'static str {
+        match self {
+            Self::Temp  => "temp",
+            Self::Fan   => "fan",
+            Self::In    => "in",
+            Self::Power => "power",
+            Self::Curr  => "curr",
+        }
+    }
+
+    /// Divisor from the raw sysfs integer to the display unit
+    /// (m°C → °C, mV → V, µW → W, mA → A).
+    fn scale(self) -> f64 {
+        match self {
+            Self::Temp | Self::In | Self::Curr => 1000.0,
+            Self::Fan   => 1.0,
+            Self::Power => 1_000_000.0,
+        }
+    }
+
+    pub fn unit(self) -> &'static str {
+        match self {
+            Self::Temp  => "°C",
+            Self::Fan   => "RPM",
+            Self::In    => "V",
+            Self::Power => "W",
+            Self::Curr  => "A",
+        }
+    }
+
+    pub fn title(self) -> &'static str {
+        match self {
+            Self::Temp  => "Temperature",
+            Self::Fan   => "Fan",
+            Self::In    => "Voltage",
+            Self::Power => "Power",
+            Self::Curr  => "Current",
+        }
+    }
+}
+
+/// One `<kind><N>_*` channel, with values already scaled to `kind.unit()`.
+#[derive(Debug, Clone)]
+pub struct SensorChannel {
+    pub kind: SensorKind,
+    pub index: u32,
+    /// `<kind><N>_label` if present, otherwise e.g. `temp1`.
+    pub label: String,
+    pub value: Option<f64>,
+    pub min: Option<f64>,
+    pub max: Option<f64>,
+    pub crit: Option<f64>,
+}
+
+impl SensorChannel {
+    fn refresh(&mut self, dev: &Path) {
+        let base = format!("{}{}", self.kind.prefix(), self.index);
+        let attr = |suffix: &str| read_scaled(&dev.join(format!("{}_{}", base, suffix)), self.kind);
+        self.value = attr("input").or_else(|| attr("average"));
+        self.min = attr("min");
+        self.max = attr("max");
+        self.crit = attr("crit");
+    }
+}
+
+/// A `/sys/class/hwmon/hwmonN` device and all of its channels.
+#[derive(Debug, Clone)]
+pub struct HwmonDevice {
+    pub path: PathBuf,
+    /// Directory name, e.g. `hwmon3`.
+    pub id: String,
+    /// Driver-reported `name`, e.g. `coretemp`, `nvme`, `nct6798`.
+    pub name: String,
+    /// Hardware model where the parent device reports one (NVMe drives do).
+    pub model: Option<String>,
+    /// The parent device, e.g. `0000:00:18.3` or `coretemp.0`. Unlike
+    /// `hwmonN` it stays the same across reboots; virtual devices have none.
+    pub device: Option<String>,
+    pub channels: Vec<SensorChannel>,
+}
+
+impl HwmonDevice {
+    fn read(path: &Path) -> Option<HwmonDevice> {
+        let id = path.file_name()?.to_string_lossy().to_string();
+        let name = fs::read_to_string(path.join("name")).ok()?.trim().to_string();
+        let model = fs::read_to_string(path.join("device/model"))
+            .ok()
+            .map(|m| m.trim().to_string())
+            .filter(|m| !m.is_empty());
+        let device = fs::canonicalize(path.join("device")).ok()
+            .and_then(|d| Some(d.file_name()?.to_string_lossy().to_string()));
+
+        // Channel numbering is sparse on some chips, so collect indices from
+        // the directory listing instead of probing 1..N.
+        let mut keys: Vec<(SensorKind, u32)> = fs::read_dir(path).ok()?
+            .filter_map(|e| e.ok())
+            .filter_map(|e| parse_channel_file(&e.file_name().to_string_lossy()))
+            .collect();
+        keys.sort_by_key(|&(kind, idx)| (kind as u8, idx));
+        keys.dedup();
+
+        let channels = keys.into_iter().map(|(kind, index)| {
+            let base = format!("{}{}", kind.prefix(), index);
+            let mut channel = SensorChannel {
+                kind,
+                index,
+                label: fs::read_to_string(path.join(format!("{}_label", base)))
+                    .map(|l| l.trim().to_string())
+                    .unwrap_or_else(|_| base.clone()),
+                value: None,
+                min: None,
+                max: None,
+                crit: None,
+            };
+            channel.refresh(path);
+            channel
+        }).collect();
+
+        Some(HwmonDevice { path: path.to_path_buf(), id, name, model, device, channels })
+    }
+
+    /// Re-reads every channel's value and thresholds, keeping the layout.
+    pub fn refresh(&mut self) {
+        for channel in &mut self.channels {
+            channel.refresh(&self.path);
+        }
+    }
+
+    /// Sysfs file for one attribute of a channel, e.g. `.../hwmon3/temp1_input`.
+    pub fn attr_path(&self, channel: &SensorChannel, suffix: &str) -> PathBuf {
+        self.path.join(format!("{}{}_{}", channel.kind.prefix(), channel.index, suffix))
+    }
+
+    pub fn channels_of(&self, kind: SensorKind) -> impl Iterator<Item = &SensorChannel> {
+        self.channels.iter().filter(move |c| c.kind == kind)
+    }
+}
+
+/// Recognises `temp3_input`, `fan1_input`, `power1_average`, … and returns (kind, N).
+fn parse_channel_file(file: &str) -> Option<(SensorKind, u32)> {
+    let (stem, suffix) = file.split_once('_')?;
+    if suffix != "input" && suffix != "average" {
+        return None;
+    }
+    SensorKind::ALL.into_iter().find_map(|kind| {
+        let idx = stem.strip_prefix(kind.prefix())?;
+        if idx.is_empty() || !idx.chars().all(|c| c.is_ascii_digit()) {
+            return None;
+        }
+        Some((kind, idx.parse().ok()?))
+    })
+}
+
+fn read_scaled(path: &Path, kind: SensorKind) -> Option<f64> {
+    let raw: f64 = fs::read_to_string(path).ok()?.trim().parse().ok()?;
+    Some(raw / kind.scale())
+}
+
+/// Die temperatures from the AMD `k10temp` or `zenpower` driver.
+#[derive(Debug, Clone)]
+pub struct AmdCpuTemps {
+    /// `k10temp` or `zenpower`.
+    pub driver: String,
+    /// Control temperature; on some parts this includes a fan-control offset.
+    pub tctl: Option<f32>,
+    /// Real die temperature: the driver's `Tdie`, or `Tctl` minus the known offset.
+    pub tdie: Option<f32>,
+    /// Per-CCD temperatures as (CCD number from the `TccdN` label, °C).
+    pub ccds: Vec<(usize, f32)>,
+}
+
+impl AmdCpuTemps {
+    pub fn die_temp(&self) -> Option<f32> { self.tdie.or(self.tctl) }
+}
+
+/// Tctl offsets the k10temp driver applies for parts whose Tctl runs hot
+/// on purpose, matched against the `model name` prefix.
+const TCTL_OFFSETS: &[(&str, f32)] = &[
+    ("AMD Ryzen 5 1600X", 20.0),
+    ("AMD Ryzen 7 1700X", 20.0),
+    ("AMD Ryzen 7 1800X", 20.0),
+    ("AMD Ryzen 7 2700X", 10.0),
+    ("AMD Ryzen Threadripper 19", 27.0),
+    ("AMD Ryzen Threadripper 29", 27.0),
+];
+
+fn tctl_offset(model: &str) -> f32 {
+    TCTL_OFFSETS.iter()
+        .find(|(prefix, _)| model.starts_with(prefix))
+        .map(|&(_, off)| off)
+        .unwrap_or(0.0)
+}
+
+fn cpu_model_name() -> String {
+    fs::read_to_string("/proc/cpuinfo").unwrap_or_default()
+        .lines()
+        .find(|l| l.starts_with("model name"))
+        .and_then(|l| l.split(':').nth(1))
+        .map(|m| m.trim().to_string())
+        .unwrap_or_default()
+}
+
+/// Assigns logical CPUs to CCDs. Each present CCD (in `TccdN` order) takes an
+/// equal share of the L3 groups, which covers both one-CCX-per-CCD (Zen 3+)
+/// and two-CCX-per-CCD (Zen 2) layouts. Returns empty if the counts don't divide.
+fn map_ccds_to_cpus(ccds: &[(usize, f32)], l3_groups: &[Vec<usize>]) -> Vec<(usize, usize, f32)> {
+    if ccds.is_empty() || l3_groups.is_empty() || !l3_groups.len().is_multiple_of(ccds.len()) {
+        return Vec::new();
+    }
+    let per_ccd = l3_groups.len() / ccds.len();
+    ccds.iter().zip(l3_groups.chunks(per_ccd))
+        .flat_map(|(&(ccd, temp), groups)| {
+            groups.iter().flatten().map(move |&cpu| (cpu, ccd, temp))
+        })
+        .collect()
+}
 
 pub struct HwmonReader;
 
 impl HwmonReader {
-    /// First non-zero fan RPM found across all hwmon devices.
-    pub fn get_fan_rpm() -> Option<u32> {
-        for i in 0..16 {
-            let base = format!("/sys/class/hwmon/hwmon{}", i);
-            if !Path::new(&base).exists() { break; }
-            for fan in 1..=8 {
-                let path = format!("{}/fan{}_input", base, fan);
-                if let Ok(s) = fs::read_to_string(&path) {
-                    if let Ok(rpm) = s.trim().parse::<u32>() {
-                        if rpm > 0 { return Some(rpm); }
-                    }
-                }
+    /// Every hwmon device with all of its channels, ordered by hwmon index.
+    /// The directory layout is cached for `LAYOUT_TTL`; readings are fresh.
+    pub fn devices() -> Vec<HwmonDevice> {
+        let mut layout = LAYOUT.lock().unwrap_or_else(|e| e.into_inner());
+        match layout.as_ref() {
+            Some((scanned, devices)) if scanned.elapsed() < LAYOUT_TTL => {
+                let mut devices = devices.clone();
+                devices.iter_mut().for_each(HwmonDevice::refresh);
+                devices
+            }
+            _ => {
+                let devices = Self::scan();
+                *layout = Some((Instant::now(), devices.clone()));
+                devices
             }
         }
-        None
+    }
+
+    /// Enumerates hwmon now, bypassing the cached layout.
+    pub fn scan() -> Vec<HwmonDevice> {
+        let mut dirs: Vec<PathBuf> = match fs::read_dir(HWMON_BASE) {
+            Ok(entries) => entries.filter_map(|e| e.ok()).map(|e| e.path()).collect(),
+            Err(_) => return Vec::new(),
+        };
+        dirs.sort_by_key(|d| hwmon_index(d));
+        dirs.iter().filter_map(|d| HwmonDevice::read(d)).collect()
+    }
+
+    /// Path of the first hwmon device whose `name` matches, e.g. `amd_energy`.
+    pub fn find_device(name: &str) -> Option<PathBuf> {
+        let mut dirs: Vec<PathBuf> = fs::read_dir(HWMON_BASE).ok()?
+            .filter_map(|e| e.ok())
+            .map(|e| e.path())
+            .collect();
+        dirs.sort_by_key(|d| hwmon_index(d));
+        dirs.into_iter().find(|d| {
+            fs::read_to_string(d.join("name")).map(|n| n.trim() == name).unwrap_or(false)
+        })
+    }
+
+    /// Every fan with a reading, as (device name, channel label, RPM).
+    pub fn get_fans() -> Vec<(String, String, u32)> {
+        Self::devices().iter()
+            .flat_map(|d| d.channels_of(SensorKind::Fan)
+                .filter_map(|c| Some((d.name.clone(), c.label.clone(), c.value? as u32)))
+                .collect::<Vec<_>>())
+            .collect()
     }
 
     /// Per-core temps from the `coretemp` kernel driver.
     /// Returns vec of (physical_core_id, temp_celsius).
     pub fn get_per_core_temps() -> Vec<(usize, f32)> {
-        for i in 0..16 {
-            let base = format!("/sys/class/hwmon/hwmon{}", i);
-            let name_path = format!("{}/name", base);
-            if fs::read_to_string(&name_path).map(|n| n.trim().to_string())
-                .unwrap_or_default() != "coretemp" { continue; }
-
-            let mut result = Vec::new();
-            for j in 1..=32 {
-                let label = match fs::read_to_string(format!("{}/temp{}_label", base, j)) {
-                    Ok(l) => l,
-                    Err(_) => continue,
-                };
-                let val = match fs::read_to_string(format!("{}/temp{}_input", base, j)) {
-                    Ok(v) => v,
-                    Err(_) => continue,
-                };
-                if label.trim().starts_with("Core") {
-                    if let (Some(id), Ok(mdeg)) = (
-                        label.trim().split_whitespace().nth(1).and_then(|s| s.parse::<usize>().ok()),
-                        val.trim().parse::<u32>(),
-                    ) {
-                        result.push((id, mdeg as f32 / 1000.0));
-                    }
-                }
-            }
+        for dev in Self::devices().iter().filter(|d| d.name == "coretemp") {
+            let result: Vec<(usize, f32)> = dev.channels_of(SensorKind::Temp)
+                .filter_map(|c| {
+                    let id = c.label.strip_prefix("Core ")?.trim().parse::<usize>().ok()?;
+                    Some((id, c.value? as f32))
+                })
+                .collect();
             if !result.is_empty() { return result; }
         }
         Vec::new()
     }
+
+    /// Tctl/Tdie/Tccd readings from `k10temp` or `zenpower`, if either is loaded.
+    pub fn get_amd_temps() -> Option<AmdCpuTemps> {
+        let dev = Self::devices().into_iter()
+            .find(|d| d.name == "k10temp" || d.name == "zenpower")?;
+        let find = |label: &str| dev.channels_of(SensorKind::Temp)
+            .find(|c| c.label == label)
+            .and_then(|c| c.value)
+            .map(|v| v as f32);
+        let tctl = find("Tctl");
+        let tdie = find("Tdie").or_else(|| tctl.map(|t| t - tctl_offset(&cpu_model_name())));
+        let mut ccds: Vec<(usize, f32)> = dev.channels_of(SensorKind::Temp)
+            .filter_map(|c| {
+                let n = c.label.strip_prefix("Tccd")?.parse().ok()?;
+                Some((n, c.value? as f32))
+            })
+            .collect();
+        ccds.sort_by_key(|&(n, _)| n);
+        if tctl.is_none() && tdie.is_none() && ccds.is_empty() {
+            return None;
+        }
+        Some(AmdCpuTemps { driver: dev.name.clone(), tctl, tdie, ccds })
+    }
+
+    /// Per-CCD temperatures spread over the logical CPUs of each CCD.
+    /// Returns vec of (logical_cpu, ccd_number, temp_celsius).
+    pub fn get_ccd_temps_by_cpu() -> Vec<(usize, usize, f32)> {
+        match Self::get_amd_temps() {
+            Some(amd) => map_ccds_to_cpus(&amd.ccds, &l3_cache_groups()),
+            None => Vec::new(),
+        }
+    }
+}
+
+/// Numeric suffix of `hwmonN`, so hwmon10 sorts after hwmon9.
+fn hwmon_index(path: &Path) -> u32 {
+    path.file_name()
+        .and_then(|n| n.to_string_lossy().strip_prefix("hwmon").and_then(|i| i.parse().ok()))
+        .unwrap_or(u32::MAX)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_channel_file() {
+        assert_eq!(parse_channel_file("temp3_input"), Some((SensorKind::Temp, 3)));
+        assert_eq!(parse_channel_file("fan12_input"), Some((SensorKind::Fan, 12)));
+        assert_eq!(parse_channel_file("in0_input"), Some((SensorKind::In, 0)));
+        assert_eq!(parse_channel_file("power1_average"), Some((SensorKind::Power, 1)));
+        assert_eq!(parse_channel_file("curr2_input"), Some((SensorKind::Curr, 2)));
+        assert_eq!(parse_channel_file("temp3_max"), None);
+        assert_eq!(parse_channel_file("intrusion0_alarm"), None);
+        assert_eq!(parse_channel_file("pwm1"), None);
+    }
+
+    #[test]
+    fn test_tctl_offset() {
+        assert_eq!(tctl_offset("AMD Ryzen 7 1800X Eight-Core Processor"), 20.0);
+        assert_eq!(tctl_offset("AMD Ryzen Threadripper 2950X 16-Core Processor"), 27.0);
+        assert_eq!(tctl_offset("AMD Ryzen 9 7950X 16-Core Processor"), 0.0);
+    }
+
+    #[test]
+    fn test_map_ccds_to_cpus() {
+        // Zen 2: two CCX per CCD, with SMT siblings in the upper half
+        let groups = vec![vec![0, 1, 8, 9], vec![2, 3, 10, 11], vec![4, 5, 12, 13], vec![6, 7, 14, 15]];
+        let map = map_ccds_to_cpus(&[(1, 60.0), (2, 70.0)], &groups);
+        assert_eq!(map.len(), 16);
+        assert!(map.contains(&(10, 1, 60.0)));
+        assert!(map.contains(&(12, 2, 70.0)));
+        // Mismatched counts give up rather than guess
+        assert!(map_ccds_to_cpus(&[(1, 60.0), (2, 70.0)], &groups[..3]).is_empty());
+    }
 }
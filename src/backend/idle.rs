@@ -0,0 +1,52 @@
+use std::time::{Duration, Instant};
+use zbus::blocking::{Connection, Proxy, ProxyBuilder};
+use crate::backend::schedule::clock;
+
+/// Idle time changes slowly; logind is asked at most this often.
+const REFRESH: Duration = Duration::from_secs(5);
+
+/// How long the user has been idle, from logind's `IdleHint` /
+/// `IdleSinceHintMonotonic`. Desktop environments set the hint after their
+/// own idle timeout (usually a few minutes). logind aggregates every
+/// session, so this works from the GUI and the root service alike.
+#[derive(Default)]
+pub struct IdleMonitor {
+    proxy: Option<Proxy<'static>>,
+    cached: Option<Duration>,
+    checked: Option<Instant>,
+}
+
+impl IdleMonitor {
+    /// `None` when logind can't be reached.
+    pub fn idle_for(&mut self) -> Option<Duration> {
+        if self.checked.is_some_and(|t| t.elapsed() < REFRESH) {
+            return self.cached;
+        }
+        self.checked = Some(Instant::now());
+        self.cached = self.query()
+            .map_err(|e| {
+                log::debug!("Idle state unavailable: {}", e);
+                self.proxy = None;
+            })
+            .ok();
+        self.cached
+    }
+
+    fn query(&mut self) -> zbus::Result<Duration> {
+        if self.proxy.is_none() {
+            let conn = Connection::system()?;
+            self.proxy = Some(ProxyBuilder::new(&conn)
+                .destination("org.freedesktop.login1")?
+                .path("/org/freedesktop/login1")?
+                .interface("org.freedesktop.login1.Manager")?
+                .cache_properties(zbus::CacheProperties::No)
+                .build()?);
+        }
+        let proxy = self.proxy.as_ref().expect("proxy set above");
+        if !proxy.get_property::<bool>("IdleHint")? {
+            return Ok(Duration::ZERO);
+        }
+        let since = Duration::from_micros(proxy.get_property::<u64>("IdleSinceHintMonotonic")?);
+        Ok(clock(libc::CLOCK_MONOTONIC).saturating_sub(since))
+    }
+}
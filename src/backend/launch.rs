@@ -0,0 +1,214 @@
+use anyhow::{Context, Result};
+use std::os::unix::process::{CommandExt, ExitStatusExt};
+use std::process::{Child, Command, ExitStatus};
+use std::sync::atomic::{AtomicBool, AtomicI32, Ordering};
+
+/// Pid of the command being waited for, for the signal handler.
+static CHILD: AtomicI32 = AtomicI32::new(0);
+
+extern "C" fn forward(sig: libc::c_int) {
+    let pid = CHILD.load(Ordering::SeqCst);
+    if pid > 0 {
+        // SAFETY: kill is async-signal-safe.
+        unsafe { libc::kill(pid, sig) };
+    }
+}
+
+/// Set by `catch_interrupts`' handler.
+static INTERRUPTED: AtomicBool = AtomicBool::new(false);
+
+extern "C" fn note_interrupt(_sig: libc::c_int) {
+    INTERRUPTED.store(true, Ordering::SeqCst);
+}
+
+/// From now on SIGINT, SIGTERM and SIGHUP only set a flag (see
+/// `interrupted`), so a long-running CLI command can clean up before it
+/// exits. `wait` swaps its own handling in while a child runs.
+pub fn catch_interrupts() -> Result<()> {
+    use nix::sys::signal::{sigaction, SaFlags, SigAction, SigHandler, SigSet, Signal};
+    let action = SigAction::new(SigHandler::Handler(note_interrupt), SaFlags::SA_RESTART, SigSet::empty());
+    for sig in [Signal::SIGINT, Signal::SIGTERM, Signal::SIGHUP] {
+        // SAFETY: the handler only stores to an atomic.
+        unsafe { sigaction(sig, &action)? };
+    }
+    Ok(())
+}
+
+pub fn interrupted() -> bool {
+    INTERRUPTED.load(Ordering::SeqCst)
+}
+
+/// Sleeps for `d`, waking early once `interrupted`.
+pub fn sleep(d: std::time::Duration) {
+    let deadline = std::time::Instant::now() + d;
+    while !interrupted() {
+        let left = deadline.saturating_duration_since(std::time::Instant::now());
+        if left.is_zero() {
+            break;
+        }
+        std::thread::sleep(left.min(std::time::Duration::from_millis(200)));
+    }
+}
+
+/// Starts `argv`. Under sudo the command runs as the invoking user, so
+/// only the CPU settings need root.
+pub fn spawn(argv: &[String]) -> Result<Child> {
+    let (program, args) = argv.split_first().context("No command given")?;
+    let mut cmd = Command::new(program);
+    cmd.args(args);
+    if nix::unistd::geteuid().is_root() {
+        let id = |var: &str| std::env::var(var).ok().and_then(|v| v.parse::<u32>().ok());
+        if let (Some(uid), Some(gid)) = (id("SUDO_UID"), id("SUDO_GID")) {
+            drop_to(&mut cmd, uid, gid)?;
+        }
+    }
+    cmd.spawn().with_context(|| format!("Failed to run {:?}", program))
+}
+
+/// Makes `cmd` run as `uid`, with the user's own environment and groups
+/// rather than root's. `Command::uid` would clear the supplementary groups,
+/// so the switch happens in `pre_exec` instead.
+fn drop_to(cmd: &mut Command, uid: u32, gid: u32) -> Result<()> {
+    use nix::unistd::{getgrouplist, Gid, Uid, User};
+    let user = User::from_uid(Uid::from_raw(uid))?.with_context(|| format!("No user with uid {}", uid))?;
+    cmd.env("HOME", &user.dir)
+        .env("USER", &user.name)
+        .env("LOGNAME", &user.name)
+        .env("SHELL", &user.shell)
+        // sudo's secure_path isn't meant for the user's programs.
+        .env("PATH", user_path(&user));
+    let name = std::ffi::CString::new(user.name.as_str())?;
+    let groups: Vec<libc::gid_t> = getgrouplist(&name, Gid::from_raw(gid))
+        .map(|g| g.into_iter().map(Gid::as_raw).collect())
+        .unwrap_or_else(|_| vec![gid]);
+    // SAFETY: setgroups, setgid and setuid are async-signal-safe and
+    // `groups` is only read.
+    unsafe {
+        cmd.pre_exec(move || {
+            if libc::setgroups(groups.len() as _, groups.as_ptr()) != 0
+                || libc::setgid(gid) != 0
+                || libc::setuid(uid) != 0
+            {
+                return Err(std::io::Error::last_os_error());
+            }
+            Ok(())
+        });
+    }
+    Ok(())
+}
+
+/// The `PATH` sudo was started with, so the user's own directories
+/// (`~/.cargo/bin` and the like) stay on it; failing that, the usual user
+/// and system directories.
+fn user_path(user: &nix::unistd::User) -> String {
+    ancestor_env(user.uid.as_raw(), "PATH")
+        .filter(|p| !p.is_empty())
+        .unwrap_or_else(|| {
+            let home = user.dir.display();
+            format!("{home}/.local/bin:{home}/.cargo/bin:/usr/local/bin:/usr/bin:/bin")
+        })
+}
+
+/// `var` from the starting environment of the nearest ancestor whose real
+/// uid is `uid`: sudo itself (setuid, so its real uid is the caller's), or
+/// the caller's shell. Walks at most a few levels, past sudo's pty monitor.
+fn ancestor_env(uid: u32, var: &str) -> Option<String> {
+    env_from(nix::unistd::getppid().as_raw(), uid, var)
+}
+
+/// `ancestor_env` starting at `pid` rather than our parent.
+fn env_from(mut pid: i32, uid: u32, var: &str) -> Option<String> {
+    for _ in 0..4 {
+        if pid <= 1 {
+            return None;
+        }
+        let status = std::fs::read_to_string(format!("/proc/{}/status", pid)).ok()?;
+        let field = |name: &str| status.lines().find_map(|l| l.strip_prefix(name)).map(str::trim);
+        let real_uid = field("Uid:")?.split_whitespace().next()?.parse::<u32>().ok()?;
+        if real_uid == uid {
+            let environ = std::fs::read(format!("/proc/{}/environ", pid)).ok()?;
+            let prefix = format!("{}=", var);
+            return environ.split(|&b| b == 0)
+                .find_map(|kv| kv.strip_prefix(prefix.as_bytes()))
+                .map(|v| String::from_utf8_lossy(v).into_owned());
+        }
+        pid = field("PPid:")?.parse().ok()?;
+    }
+    None
+}
+
+/// Waits for `child`. Ctrl-C reaches it through the terminal, so this
+/// process ignores SIGINT/SIGQUIT meanwhile; SIGTERM and SIGHUP are passed
+/// on. Either way the caller gets to clean up once the child is gone.
+pub fn wait(child: &mut Child) -> Result<ExitStatus> {
+    use nix::sys::signal::{sigaction, SaFlags, SigAction, SigHandler, SigSet, Signal};
+    CHILD.store(child.id() as i32, Ordering::SeqCst);
+    let ignore = SigAction::new(SigHandler::SigIgn, SaFlags::empty(), SigSet::empty());
+    let pass_on = SigAction::new(SigHandler::Handler(forward), SaFlags::SA_RESTART, SigSet::empty());
+    let mut saved = Vec::new();
+    // SAFETY: the handler only reads an atomic and calls kill.
+    unsafe {
+        for (sig, action) in [
+            (Signal::SIGINT, &ignore), (Signal::SIGQUIT, &ignore),
+            (Signal::SIGTERM, &pass_on), (Signal::SIGHUP, &pass_on),
+        ] {
+            saved.push((sig, sigaction(sig, action)?));
+        }
+    }
+    let status = child.wait();
+    CHILD.store(0, Ordering::SeqCst);
+    // SAFETY: restores the dispositions saved above.
+    unsafe {
+        for (sig, old) in saved {
+            let _ = sigaction(sig, &old);
+        }
+    }
+    status.context("Failed to wait for the command")
+}
+
+/// Shell convention: the exit code, or 128 + signal number.
+pub fn exit_code(status: ExitStatus) -> i32 {
+    status.code().or_else(|| status.signal().map(|s| 128 + s)).unwrap_or(1)
+}
+
+/// Spawns `argv` and waits for it, as `spawn` and `wait`.
+pub fn run(argv: &[String]) -> Result<ExitStatus> {
+    let mut child = spawn(argv)?;
+    wait(&mut child)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_exit_codes() {
+        let sh = |script: &str| exit_code(run(&["sh".into(), "-c".into(), script.into()]).unwrap());
+        assert_eq!(sh("exit 3"), 3);
+        assert_eq!(sh("kill -TERM $$"), 128 + libc::SIGTERM);
+        assert!(run(&["/nonexistent/cpm-test".into()]).is_err());
+        assert!(run(&[]).is_err());
+    }
+
+    #[test]
+    fn test_ancestor_env() {
+        // A child started with a known variable stands in for sudo.
+        let mut child = Command::new("sleep").arg("10").env("CPM_TEST_VARIABLE", "marker").spawn().unwrap();
+        let pid = child.id() as i32;
+        let uid = nix::unistd::getuid().as_raw();
+        // spawn can return before exec has laid out the new environment.
+        for _ in 0..100 {
+            if std::fs::read(format!("/proc/{}/environ", pid)).is_ok_and(|e| !e.is_empty()) {
+                break;
+            }
+            std::thread::sleep(std::time::Duration::from_millis(10));
+        }
+        let found = (env_from(pid, uid, "CPM_TEST_VARIABLE"), env_from(pid, uid, "CPM_TEST_UNSET_VARIABLE"));
+        // Another uid: the walk passes the child and its ancestors by.
+        let other = env_from(pid, uid.wrapping_add(4242), "CPM_TEST_VARIABLE");
+        let _ = child.kill();
+        let _ = child.wait();
+        assert_eq!(found, (Some("marker".into()), None));
+        assert_eq!(other, None);
+    }
+}
@@ -0,0 +1,262 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use crate::backend::cpu::CpuManager;
+use crate::backend::launch;
+use crate::backend::power_supply::EnergyMeter;
+use crate::backend::thermal::{TempSource, ThermalManager};
+
+/// What running one command cost. Hardware readings are `None` where the
+/// machine doesn't expose them (RAPL usually needs root).
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Measurement {
+    pub exit_code: i32,
+    pub wall_seconds: f64,
+    pub cpu_user_seconds: f64,
+    pub cpu_system_seconds: f64,
+    /// Package energy (all sockets), or platform (psys) without a package counter.
+    pub energy_joules: Option<f64>,
+    pub avg_watts: Option<f64>,
+    pub peak_watts: Option<f64>,
+    /// Mean of `scaling_cur_freq` over all cores and samples.
+    pub avg_freq_mhz: Option<f64>,
+    pub peak_temp_celsius: Option<f32>,
+    /// From the kernel's `thermal_throttle` counters (Intel).
+    pub throttled_seconds: Option<f64>,
+    pub samples: usize,
+}
+
+impl Measurement {
+    pub fn cpu_seconds(&self) -> f64 { self.cpu_user_seconds + self.cpu_system_seconds }
+
+    pub fn print(&self) {
+        let opt = |v: Option<f64>, unit: &str, digits: usize| {
+            v.map(|v| format!("{:.*} {}", digits, v, unit)).unwrap_or_else(|| "n/a".into())
+        };
+        println!("Exit code:       {}", self.exit_code);
+        println!("Wall time:       {:.2} s", self.wall_seconds);
+        println!("CPU time:        {:.2} s (user {:.2} s, system {:.2} s)", self.cpu_seconds(), self.cpu_user_seconds, self.cpu_system_seconds);
+        println!("Package energy:  {}", opt(self.energy_joules, "J", 1));
+        println!("Power:           avg {}, peak {}", opt(self.avg_watts, "W", 2), opt(self.peak_watts, "W", 2));
+        println!("Avg frequency:   {}", opt(self.avg_freq_mhz, "MHz", 0));
+        println!(
+            "Peak temp:       {}",
+            self.peak_temp_celsius.map(|t| crate::utils::units::fmt_temp(t, 1)).unwrap_or_else(|| "n/a".into())
+        );
+        println!("Time throttled:  {}", opt(self.throttled_seconds, "s", 2));
+    }
+}
+
+/// Shorter samples don't count towards peak power.
+const MIN_PEAK_SECONDS: f64 = 0.1;
+
+/// Running totals, fed once per sample interval.
+#[derive(Debug, Default)]
+struct Totals {
+    energy_j: Option<f64>,
+    peak_w: Option<f64>,
+    freq_sum: f64,
+    freq_n: usize,
+    peak_temp: Option<f32>,
+    samples: usize,
+}
+
+impl Totals {
+    /// `joules` is the energy used over the `dt` seconds since the last call.
+    fn add(&mut self, joules: Option<f64>, dt: f64, freqs: &[u32], temp: Option<f32>) {
+        if let Some(j) = joules {
+            *self.energy_j.get_or_insert(0.0) += j;
+            // The last sample can be cut short; too noisy for a peak.
+            if dt >= MIN_PEAK_SECONDS {
+                let w = j / dt;
+                self.peak_w = Some(self.peak_w.map_or(w, |p| p.max(w)));
+            }
+        }
+        let online: Vec<u32> = freqs.iter().copied().filter(|&f| f > 0).collect();
+        if !online.is_empty() {
+            self.freq_sum += online.iter().map(|&f| f as f64).sum::<f64>() / online.len() as f64;
+            self.freq_n += 1;
+        }
+        if let Some(t) = temp {
+            self.peak_temp = Some(self.peak_temp.map_or(t, |p| p.max(t)));
+        }
+        self.samples += 1;
+    }
+}
+
+/// Samples energy, frequency and temperature on a background thread until
+/// stopped, with a last sample when stopped.
+pub struct Sampler {
+    stop: Arc<AtomicBool>,
+    handle: std::thread::JoinHandle<Totals>,
+    throttle_start: Option<u64>,
+    started: Instant,
+}
+
+impl Sampler {
+    pub fn start(interval: Duration, source: TempSource) -> Self {
+        let stop = Arc::new(AtomicBool::new(false));
+        let flag = stop.clone();
+        let throttle_start = throttle_ms();
+        // Starting counters are read here, so the thread's startup is counted.
+        let mut meter = EnergyMeter::new();
+        let mut energy = meter.read_j();
+        let handle = std::thread::spawn(move || {
+            let mut totals = Totals::default();
+            let cpu = CpuManager::new().ok();
+            let mut thermal = ThermalManager::new().ok();
+            if let Some(t) = thermal.as_mut() {
+                t.set_source(source);
+            }
+            let mut last = Instant::now();
+            loop {
+                // Sleep in short steps so stopping doesn't wait a full interval.
+                let until = last + interval;
+                while Instant::now() < until && !flag.load(Ordering::SeqCst) {
+                    std::thread::sleep(Duration::from_millis(20).min(until - Instant::now()));
+                }
+                let dt = last.elapsed().as_secs_f64();
+                last = Instant::now();
+                let now = meter.read_j();
+                let joules = now.zip(energy).map(|(now, before)| now - before);
+                energy = now.or(energy);
+                let freqs = cpu.as_ref().and_then(|c| c.get_all_frequencies().ok()).unwrap_or_default();
+                let temp = thermal.as_ref().and_then(|t| t.get_cpu_temperature().ok());
+                totals.add(joules, dt, &freqs, temp);
+                if flag.load(Ordering::SeqCst) {
+                    break totals;
+                }
+            }
+        });
+        Self { stop, handle, throttle_start, started: Instant::now() }
+    }
+
+    /// Stops sampling; fills in everything but the exit code and CPU time.
+    pub fn finish(self) -> Measurement {
+        let wall = self.started.elapsed().as_secs_f64();
+        self.stop.store(true, Ordering::SeqCst);
+        let totals = self.handle.join().unwrap_or_default();
+        let throttled = self.throttle_start.zip(throttle_ms())
+            .map(|(start, end)| end.saturating_sub(start) as f64 / 1000.0);
+        Measurement {
+            wall_seconds: wall,
+            energy_joules: totals.energy_j,
+            avg_watts: totals.energy_j.filter(|_| wall > 0.0).map(|e| e / wall),
+            peak_watts: totals.peak_w,
+            avg_freq_mhz: (totals.freq_n > 0).then(|| totals.freq_sum / totals.freq_n as f64),
+            peak_temp_celsius: totals.peak_temp,
+            throttled_seconds: throttled,
+            samples: totals.samples,
+            ..Default::default()
+        }
+    }
+}
+
+/// Runs `argv` (as `launch::run`) and measures it.
+pub fn measure(argv: &[String], interval: Duration, source: TempSource) -> Result<Measurement> {
+    let before = rusage(libc::RUSAGE_CHILDREN);
+    let sampler = Sampler::start(interval, source);
+    let mut child = match launch::spawn(argv) {
+        Ok(child) => child,
+        Err(e) => {
+            sampler.finish();
+            return Err(e);
+        }
+    };
+    let status = launch::wait(&mut child);
+    // Stopping takes the final sample, right after the command exits.
+    let mut m = sampler.finish();
+    let after = rusage(libc::RUSAGE_CHILDREN);
+    m.exit_code = launch::exit_code(status?);
+    m.cpu_user_seconds = after.0 - before.0;
+    m.cpu_system_seconds = after.1 - before.1;
+    Ok(m)
+}
+
+/// Where runs as root save benchmark and calibration results, one
+/// subdirectory per kind, so every user's GUI can read them.
+pub const SYSTEM_RESULTS_DIR: &str = "/var/lib/cpu-power-manager";
+
+fn user_results_dir(kind: &str) -> Option<PathBuf> {
+    std::env::var("HOME").ok()
+        .map(|home| PathBuf::from(home).join(".local/share/cpu-power-manager").join(kind))
+}
+
+/// The directories saved results of `kind` (`benchmarks`, `calibration`)
+/// are read from, the system-wide one first.
+pub fn results_dirs(kind: &str) -> Vec<PathBuf> {
+    [Some(PathBuf::from(SYSTEM_RESULTS_DIR).join(kind)), user_results_dir(kind)]
+        .into_iter()
+        .flatten()
+        .collect()
+}
+
+/// Writes `value` as JSON to `file` in the `kind` results directory: under
+/// `SYSTEM_RESULTS_DIR` as root, otherwise under
+/// `~/.local/share/cpu-power-manager`.
+pub fn save_results<T: Serialize>(kind: &str, file: &str, value: &T) -> Result<PathBuf> {
+    let dir = if nix::unistd::geteuid().is_root() {
+        PathBuf::from(SYSTEM_RESULTS_DIR).join(kind)
+    } else {
+        user_results_dir(kind).context("HOME is not set")?
+    };
+    fs::create_dir_all(&dir).with_context(|| format!("Failed to create {}", dir.display()))?;
+    let path = dir.join(file);
+    fs::write(&path, serde_json::to_string_pretty(value)?)
+        .with_context(|| format!("Failed to write {}", path.display()))?;
+    Ok(path)
+}
+
+/// `hostname (CPU model)`, recorded with saved results.
+pub fn machine_label(cpu: &CpuManager) -> String {
+    let host = fs::read_to_string("/proc/sys/kernel/hostname").unwrap_or_default();
+    let model = cpu.get_cpu_info().map(|i| i.model).unwrap_or_default();
+    format!("{} ({})", host.trim(), model.trim())
+}
+
+/// (user, system) CPU seconds so far for `who` (`RUSAGE_SELF` or
+/// `RUSAGE_CHILDREN`, the latter counting only waited-for children).
+pub(crate) fn rusage(who: libc::c_int) -> (f64, f64) {
+    // SAFETY: getrusage fills the zeroed struct.
+    let mut ru: libc::rusage = unsafe { std::mem::zeroed() };
+    unsafe { libc::getrusage(who, &mut ru) };
+    let secs = |tv: libc::timeval| tv.tv_sec as f64 + tv.tv_usec as f64 / 1e6;
+    (secs(ru.ru_utime), secs(ru.ru_stime))
+}
+
+/// Total package throttle time in ms, the largest across CPUs (each reports
+/// its package's counter); core counters where there is no package one.
+fn throttle_ms() -> Option<u64> {
+    let read = |file: &str| -> Option<u64> {
+        fs::read_dir("/sys/devices/system/cpu").ok()?
+            .filter_map(|e| e.ok())
+            .filter_map(|e| fs::read_to_string(e.path().join("thermal_throttle").join(file)).ok())
+            .filter_map(|s| s.trim().parse().ok())
+            .max()
+    };
+    read("package_throttle_total_time_ms").or_else(|| read("core_throttle_total_time_ms"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_totals() {
+        let mut t = Totals::default();
+        t.add(Some(5.0), 0.5, &[1000, 3000, 0], Some(60.0));
+        t.add(Some(10.0), 0.5, &[2000, 2000], Some(70.0));
+        t.add(None, 0.5, &[], None);
+        // A cut-short final sample counts for energy, not peak.
+        t.add(Some(1.0), 0.02, &[], None);
+        assert_eq!(t.energy_j, Some(16.0));
+        assert_eq!(t.peak_w, Some(20.0));
+        assert_eq!(t.freq_sum / t.freq_n as f64, 2000.0);
+        assert_eq!(t.peak_temp, Some(70.0));
+        assert_eq!(t.samples, 4);
+    }
+}
@@ -1,21 +1,45 @@
+pub mod control;
 pub mod cpu;
+pub mod emergency;
+pub mod fan;
+pub mod fixed_freq;
 pub mod frequency;
 pub mod governor;
 pub mod hwmon;
+pub mod idle;
+pub mod launch;
+pub mod measure;
+pub mod overrides;
 pub mod power;
+pub mod power_source;
 pub mod power_supply;
+pub mod process_watch;
 pub mod profile;
+pub mod rules;
+pub mod schedule;
+pub mod service_status;
+pub mod snapshot;
 pub mod thermal;
+pub mod thermal_forecast;
+pub mod thermal_limiter;
 pub mod turbo;
 pub mod auto_tune;
+pub mod benchmark;
+pub mod calibrate;
 
 pub use cpu::CpuManager;
+pub use emergency::EmergencyGuard;
+pub use fan::{FanController, FanCurve};
 #[allow(unused_imports)] pub use frequency::FrequencyManager;
 #[allow(unused_imports)] pub use governor::GovernorManager;
 pub use hwmon::HwmonReader;
 #[allow(unused_imports)] pub use power::PowerManager;
-pub use power_supply::{BatteryInfo, PowerSupplyReader, RaplTracker};
+pub use power_source::PowerSourceWatcher;
+#[allow(unused_imports)] pub use power_source::PowerSource;
+pub use power_supply::{BatteryEstimator, BatteryInfo, ChargeThresholds, PowerSupplyReader, RaplTracker, RaplZone};
 #[allow(unused_imports)] pub use profile::ProfileManager;
 #[allow(unused_imports)] pub use thermal::ThermalManager;
+pub use thermal_forecast::ThrottleForecaster;
+pub use thermal_limiter::ThermalLimiter;
 #[allow(unused_imports)] pub use turbo::TurboManager;
-#[allow(unused_imports)] pub use auto_tune::AutoTuner;
+pub use rules::RuleEngine;
@@ -0,0 +1,405 @@
+use anyhow::{bail, Context, Result};
+use chrono::{Local, NaiveTime, TimeZone};
+use serde::{Deserialize, Serialize};
+use nix::fcntl::{Flock, FlockArg};
+use std::fs;
+use std::path::Path;
+use std::time::Duration;
+use crate::backend::auto_tune::Selection;
+use crate::backend::cpu::CpuManager;
+use crate::backend::profile::ProfileManager;
+use crate::backend::snapshot::CpuSnapshot;
+
+/// Shared by the GUI, the CLI and the background service. Lives under /run
+/// so overrides never outlast a reboot.
+pub const OVERRIDES_FILE: &str = "/run/cpu-power-manager/overrides.toml";
+
+/// Held across every load-modify-save of `OVERRIDES_FILE`.
+const LOCK_FILE: &str = "/run/cpu-power-manager/overrides.lock";
+
+/// Exclusive `flock` on `LOCK_FILE`, released on drop. Readers that only
+/// load the stack don't need it; saves are atomic renames.
+struct StackLock {
+    _flock: Flock<fs::File>,
+}
+
+impl StackLock {
+    fn acquire() -> Result<Self> {
+        let path = Path::new(LOCK_FILE);
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).with_context(|| format!("Failed to create {}. Run with sudo.", dir.display()))?;
+        }
+        let file = fs::OpenOptions::new().create(true).truncate(false).write(true).open(path)
+            .with_context(|| format!("Failed to open {}. Run with sudo.", path.display()))?;
+        Flock::lock(file, FlockArg::LockExclusive)
+            .map(|f| Self { _flock: f })
+            .map_err(|(_, e)| e)
+            .with_context(|| format!("Failed to lock {}", path.display()))
+    }
+}
+
+/// When an override ends.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Expiry {
+    /// Unix time in seconds.
+    At { unix: i64 },
+    /// When the process exits. `start_time` (from /proc/<pid>/stat) guards
+    /// against the pid being reused.
+    Process { pid: i32, start_time: u64 },
+    /// Until removed by hand.
+    Manual,
+}
+
+impl Expiry {
+    pub fn after(d: Duration) -> Self {
+        Expiry::At { unix: Local::now().timestamp() + d.as_secs() as i64 }
+    }
+
+    /// The next time the local clock shows `HH:MM`.
+    pub fn until(hhmm: &str) -> Result<Self> {
+        let t = NaiveTime::parse_from_str(hhmm.trim(), "%H:%M").with_context(|| format!("{:?} is not HH:MM", hhmm))?;
+        let now = Local::now();
+        let mut at = now.date_naive().and_time(t);
+        if at <= now.naive_local() {
+            at += chrono::Duration::days(1);
+        }
+        let at = Local.from_local_datetime(&at).earliest().context("That time doesn't exist today (DST change)")?;
+        Ok(Expiry::At { unix: at.timestamp() })
+    }
+
+    pub fn process(pid: i32) -> Result<Self> {
+        let start_time = process_start(pid).with_context(|| format!("No process with pid {}", pid))?;
+        Ok(Expiry::Process { pid, start_time })
+    }
+
+    pub(crate) fn expired(&self, now: i64) -> bool {
+        match *self {
+            Expiry::At { unix } => now >= unix,
+            Expiry::Process { pid, start_time } => process_start(pid) != Some(start_time),
+            Expiry::Manual => false,
+        }
+    }
+
+    pub fn describe(&self, now: i64) -> String {
+        match *self {
+            Expiry::At { unix } => {
+                let at = Local.timestamp_opt(unix, 0).single().map(|t| t.format("%H:%M").to_string()).unwrap_or_default();
+                format!("until {} ({} min left)", at, ((unix - now).max(0) + 59) / 60)
+            }
+            Expiry::Process { pid, .. } => format!("while pid {} runs", pid),
+            Expiry::Manual => "until cleared".into(),
+        }
+    }
+}
+
+fn process_start(pid: i32) -> Option<u64> {
+    procfs::process::Process::new(pid).ok()?.stat().ok().map(|s| s.starttime)
+}
+
+/// A temporary change laid over the active profile and rules. Unset
+/// fields are left alone.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Override {
+    #[serde(default)]
+    pub id: u32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub profile: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub turbo: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min_freq_mhz: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_freq_mhz: Option<u32>,
+    pub expiry: Expiry,
+}
+
+impl Override {
+    pub fn new(expiry: Expiry) -> Self {
+        Self { id: 0, profile: None, turbo: None, min_freq_mhz: None, max_freq_mhz: None, expiry }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.profile.is_none() && self.turbo.is_none() && self.min_freq_mhz.is_none() && self.max_freq_mhz.is_none()
+    }
+
+    /// `Performance, turbo off`
+    pub fn describe(&self) -> String {
+        let mut parts: Vec<String> = self.profile.iter().cloned().collect();
+        if let Some(turbo) = self.turbo {
+            parts.push(format!("turbo {}", if turbo { "on" } else { "off" }));
+        }
+        if let Some(min) = self.min_freq_mhz {
+            parts.push(format!("≥ {} MHz", min));
+        }
+        if let Some(max) = self.max_freq_mhz {
+            parts.push(format!("≤ {} MHz", max));
+        }
+        parts.join(", ")
+    }
+}
+
+/// One-click overrides offered in the header popover and the tray.
+pub fn presets() -> Vec<(&'static str, Override)> {
+    let half_hour = Expiry::after(Duration::from_secs(30 * 60));
+    let hour = Expiry::after(Duration::from_secs(60 * 60));
+    vec![
+        ("Turbo off for 30 min", Override { turbo: Some(false), ..Override::new(half_hour) }),
+        ("Performance for 1 hour", Override { profile: Some("Performance".into()), ..Override::new(hour.clone()) }),
+        ("Power Saver for 1 hour", Override { profile: Some("Power Saver".into()), ..Override::new(hour) }),
+    ]
+}
+
+/// The active overrides, oldest first; later ones win where they overlap.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct OverrideStack {
+    #[serde(default)]
+    next_id: u32,
+    /// CPU state from before the first override, put back when the last ends.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    base: Option<CpuSnapshot>,
+    #[serde(default)]
+    pub overrides: Vec<Override>,
+}
+
+impl OverrideStack {
+    /// An empty stack when the file is missing or unreadable.
+    pub fn load() -> Self {
+        Self::load_from(Path::new(OVERRIDES_FILE))
+    }
+
+    fn load_from(path: &Path) -> Self {
+        let Ok(text) = fs::read_to_string(path) else { return Self::default() };
+        toml::from_str(&text)
+            .map_err(|e| log::warn!("Ignoring invalid {}: {}", path.display(), e))
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self) -> Result<()> {
+        let path = Path::new(OVERRIDES_FILE);
+        if self.overrides.is_empty() && self.base.is_none() {
+            return match fs::remove_file(path) {
+                Err(e) if e.kind() != std::io::ErrorKind::NotFound => {
+                    Err(e).with_context(|| format!("Failed to remove {}", path.display()))
+                }
+                _ => Ok(()),
+            };
+        }
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).with_context(|| format!("Failed to create {}. Run with sudo.", dir.display()))?;
+        }
+        // Write-then-rename so readers never see half a file.
+        let tmp = path.with_extension("toml.tmp");
+        fs::write(&tmp, toml::to_string(self).context("Failed to serialize overrides")?)
+            .with_context(|| format!("Failed to write {}. Run with sudo.", tmp.display()))?;
+        fs::rename(&tmp, path).with_context(|| format!("Failed to replace {}", path.display()))
+    }
+
+    pub fn is_empty(&self) -> bool { self.overrides.is_empty() }
+
+    /// Adds `o` on top. `base` is called only for the first override, to
+    /// capture the state to return to.
+    pub fn push(&mut self, mut o: Override, base: impl FnOnce() -> CpuSnapshot) -> Result<u32> {
+        if o.is_empty() {
+            bail!("Override changes nothing: give a profile, turbo or frequency limit");
+        }
+        if self.base.is_none() {
+            self.base = Some(base());
+        }
+        self.next_id += 1;
+        o.id = self.next_id;
+        self.overrides.push(o);
+        Ok(self.next_id)
+    }
+
+    /// Removes the override `id`, or all of them with `None`. Returns how
+    /// many were removed.
+    pub fn remove(&mut self, id: Option<u32>) -> usize {
+        let before = self.overrides.len();
+        self.overrides.retain(|o| id.is_some_and(|id| o.id != id));
+        before - self.overrides.len()
+    }
+
+    /// Drops overrides that have run out. Returns them.
+    pub fn expire(&mut self, now: i64) -> Vec<Override> {
+        let (ended, kept) = std::mem::take(&mut self.overrides).into_iter().partition(|o| o.expiry.expired(now));
+        self.overrides = kept;
+        ended
+    }
+
+    /// After overrides were removed: puts the base state back so the
+    /// remaining ones can be re-applied over it. The base is forgotten once
+    /// nothing is left.
+    pub fn restore_base(&mut self, cpu: &CpuManager) {
+        let Some(base) = &self.base else { return };
+        log::info!("Restoring the state from before the overrides");
+        base.restore(cpu);
+        if self.overrides.is_empty() {
+            self.base = None;
+        }
+    }
+
+    /// Lays the overrides over `target` (the rules' choice, if any).
+    pub fn overlay(&self, target: Option<Selection>, now: i64) -> Option<Selection> {
+        let Some(newest) = self.overrides.last() else { return target };
+        let mut sel = target.clone().unwrap_or_default();
+        for o in &self.overrides {
+            if o.profile.is_some() { sel.profile = o.profile.clone(); }
+            if o.turbo.is_some() { sel.turbo = o.turbo; }
+            if o.min_freq_mhz.is_some() { sel.min_freq_mhz = o.min_freq_mhz; }
+            if o.max_freq_mhz.is_some() { sel.max_freq_mhz = o.max_freq_mhz; }
+        }
+        sel.reason = format!("override #{} {}", newest.id, newest.expiry.describe(now));
+        if self.overrides.len() > 1 {
+            sel.reason.push_str(&format!(" (+{} more)", self.overrides.len() - 1));
+        }
+        if let Some(t) = target {
+            sel.reason.push_str(&format!("; underneath: {}", t.reason));
+        }
+        Some(sel)
+    }
+
+    /// `#2  turbo off — until 14:32 (23 min left)`, newest first.
+    pub fn lines(&self, now: i64) -> Vec<String> {
+        self.overrides.iter().rev()
+            .map(|o| format!("#{}  {} — {}", o.id, o.describe(), o.expiry.describe(now)))
+            .collect()
+    }
+}
+
+/// Follows the override stack from the service or GUI loop: expires
+/// overrides and puts the base state back whenever overrides go away,
+/// whoever removed them.
+#[derive(Debug, Default)]
+pub struct OverrideWatcher {
+    /// Ids present last time; `None` before the first update.
+    seen: Option<Vec<u32>>,
+}
+
+impl OverrideWatcher {
+    /// The current stack, and whether the base state was restored (so the
+    /// caller must re-apply its own target).
+    pub fn update(&mut self, cpu: &CpuManager) -> (OverrideStack, bool) {
+        // Best effort: without it (not root) the save below fails anyway.
+        let _lock = StackLock::acquire().ok();
+        let mut stack = OverrideStack::load();
+        let ended = stack.expire(Local::now().timestamp());
+        for o in &ended {
+            log::info!("Override #{} ({}) ended", o.id, o.describe());
+        }
+        let gone = self.seen.iter().flatten().any(|id| !stack.overrides.iter().any(|o| o.id == *id));
+        // Left behind by a process that went away before restoring it.
+        let stale = self.seen.is_none() && stack.is_empty() && stack.base.is_some();
+        let restored = gone || stale || !ended.is_empty();
+        if restored {
+            stack.restore_base(cpu);
+        }
+        if !ended.is_empty() || stale {
+            if let Err(e) = stack.save() {
+                log::warn!("Failed to update overrides: {:#}", e);
+            }
+        }
+        self.seen = Some(stack.overrides.iter().map(|o| o.id).collect());
+        (stack, restored)
+    }
+}
+
+/// Pushes `o`, saves the stack and, with `apply`, applies the combined
+/// overrides right away (otherwise the background service picks them up).
+pub fn push(o: Override, profiles: &ProfileManager, cpu: &CpuManager, apply: bool) -> Result<u32> {
+    let _lock = StackLock::acquire()?;
+    let mut stack = OverrideStack::load();
+    let id = stack.push(o, || CpuSnapshot::capture(cpu))?;
+    stack.save()?;
+    if apply {
+        if let Some(sel) = stack.overlay(None, Local::now().timestamp()) {
+            sel.apply(profiles, cpu)?;
+        }
+    }
+    Ok(id)
+}
+
+/// Removes one override (or all), restores the base state and re-applies
+/// what is left, unless `apply` is false.
+pub fn remove(id: Option<u32>, profiles: &ProfileManager, cpu: &CpuManager, apply: bool) -> Result<usize> {
+    let _lock = StackLock::acquire()?;
+    let mut stack = OverrideStack::load();
+    let removed = stack.remove(id);
+    if removed > 0 && apply {
+        stack.restore_base(cpu);
+        if let Some(sel) = stack.overlay(None, Local::now().timestamp()) {
+            sel.apply(profiles, cpu)?;
+        }
+    }
+    stack.save()?;
+    Ok(removed)
+}
+
+/// `30m`, `1h30m`, `90s`, `2h`; a bare number is minutes.
+pub fn parse_duration(s: &str) -> Result<Duration> {
+    let s = s.trim();
+    if let Ok(mins) = s.parse::<u64>() {
+        return Ok(Duration::from_secs(mins * 60));
+    }
+    let mut total = 0u64;
+    let mut num = String::new();
+    for c in s.chars() {
+        match c {
+            '0'..='9' => num.push(c),
+            'h' | 'm' | 's' if !num.is_empty() => {
+                let n: u64 = num.parse()?;
+                total += n * match c { 'h' => 3600, 'm' => 60, _ => 1 };
+                num.clear();
+            }
+            _ => bail!("{:?} is not a duration like 30m or 1h30m", s),
+        }
+    }
+    if !num.is_empty() || total == 0 {
+        bail!("{:?} is not a duration like 30m or 1h30m", s);
+    }
+    Ok(Duration::from_secs(total))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_duration() {
+        assert_eq!(parse_duration("30m").unwrap(), Duration::from_secs(1800));
+        assert_eq!(parse_duration("1h30m").unwrap(), Duration::from_secs(5400));
+        assert_eq!(parse_duration("90s").unwrap(), Duration::from_secs(90));
+        assert_eq!(parse_duration("15").unwrap(), Duration::from_secs(900));
+        assert!(parse_duration("1d").is_err());
+        assert!(parse_duration("h").is_err());
+        assert!(parse_duration("").is_err());
+    }
+
+    #[test]
+    fn test_override_stacking() {
+        let mut stack = OverrideStack::default();
+        let base = || CpuSnapshot { turbo: Some(true), ..Default::default() };
+        let turbo_off = Override { turbo: Some(false), ..Override::new(Expiry::At { unix: 1000 }) };
+        let perf = Override { profile: Some("Performance".into()), ..Override::new(Expiry::Manual) };
+        assert!(stack.push(Override::new(Expiry::Manual), base).is_err());
+        assert_eq!(stack.push(turbo_off, base).unwrap(), 1);
+        assert_eq!(stack.push(perf, || unreachable!()).unwrap(), 2);
+
+        let rules = Selection { profile: Some("Balanced".into()), reason: "on AC power".into(), ..Default::default() };
+        let sel = stack.overlay(Some(rules.clone()), 500).unwrap();
+        assert_eq!((sel.profile.as_deref(), sel.turbo), (Some("Performance"), Some(false)));
+        assert!(sel.reason.starts_with("override #2 until cleared (+1 more)"));
+        assert!(sel.reason.ends_with("underneath: on AC power"));
+
+        assert!(stack.expire(999).is_empty());
+        assert_eq!(stack.expire(1000).len(), 1);
+        let sel = stack.overlay(None, 1000).unwrap();
+        assert_eq!((sel.profile.as_deref(), sel.turbo), (Some("Performance"), None));
+        assert_eq!(stack.remove(None), 1);
+        assert_eq!(stack.overlay(Some(rules.clone()), 1000), Some(rules));
+
+        // Round-trips through the shared file format.
+        let text = toml::to_string(&stack).unwrap();
+        assert_eq!(toml::from_str::<OverrideStack>(&text).unwrap(), stack);
+    }
+}
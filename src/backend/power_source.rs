@@ -0,0 +1,192 @@
+use anyhow::Result;
+use std::fs;
+use std::os::fd::{AsRawFd, FromRawFd, OwnedFd};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::time::{Duration, Instant};
+use crate::backend::power_supply::POWER_SUPPLY_BASE;
+
+/// Re-check interval when netlink is unavailable, and a safety net when it is.
+const FALLBACK_POLL: Duration = Duration::from_secs(5);
+
+/// The single place that decides whether the machine runs on external power.
+pub struct PowerSource;
+
+impl PowerSource {
+    /// True when any `Mains` or `USB` (USB-C PD) supply reports `online`.
+    /// Without any such supply, a discharging battery means battery power;
+    /// otherwise (desktops, VMs) AC is assumed.
+    pub fn on_ac() -> bool {
+        let mut saw_adapter = false;
+        let mut discharging = false;
+        let Ok(entries) = fs::read_dir(POWER_SUPPLY_BASE) else { return true };
+        for entry in entries.filter_map(|e| e.ok()) {
+            let path = entry.path();
+            let attr = |name: &str| fs::read_to_string(path.join(name)).map(|s| s.trim().to_string()).unwrap_or_default();
+            match attr("type").as_str() {
+                "Mains" | "USB" => {
+                    saw_adapter = true;
+                    if attr("online") == "1" {
+                        return true;
+                    }
+                }
+                "Battery" if attr("scope") != "Device" => discharging |= attr("status") == "Discharging",
+                _ => {}
+            }
+        }
+        !saw_adapter && !discharging
+    }
+}
+
+/// Sent on every settled change, and once at start with the initial state.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PowerSourceEvent {
+    pub on_ac: bool,
+}
+
+/// Collapses a burst of uevents (docks flapping, USB-C renegotiation) into
+/// one check once things have been quiet for `window`.
+#[derive(Debug)]
+struct Debouncer {
+    window: Duration,
+    pending_since: Option<Instant>,
+}
+
+impl Debouncer {
+    fn new(window: Duration) -> Self { Self { window, pending_since: None } }
+
+    fn event(&mut self, now: Instant) { self.pending_since = Some(now); }
+
+    /// Time left before the pending check is due; `None` when idle.
+    fn remaining(&self, now: Instant) -> Option<Duration> {
+        self.pending_since.map(|t| self.window.saturating_sub(now.duration_since(t)))
+    }
+
+    /// True (once) when a pending check has settled.
+    fn take_due(&mut self, now: Instant) -> bool {
+        if self.remaining(now) == Some(Duration::ZERO) {
+            self.pending_since = None;
+            return true;
+        }
+        false
+    }
+}
+
+/// Watches power_supply uevents on a background thread and reports
+/// settled AC/battery changes. Falls back to polling without netlink.
+pub struct PowerSourceWatcher;
+
+impl PowerSourceWatcher {
+    pub fn spawn(debounce: Duration) -> Receiver<PowerSourceEvent> {
+        let (tx, rx) = mpsc::channel();
+        let socket = match open_uevent_socket() {
+            Ok(fd) => Some(fd),
+            Err(e) => {
+                log::warn!("Power-source uevents unavailable ({}), polling every {:?}", e, FALLBACK_POLL);
+                None
+            }
+        };
+        std::thread::Builder::new()
+            .name("power-source".into())
+            .spawn(move || watch(socket, debounce, tx))
+            .map_err(|e| log::error!("Failed to start power-source watcher: {}", e))
+            .ok();
+        rx
+    }
+}
+
+fn watch(socket: Option<OwnedFd>, debounce: Duration, tx: Sender<PowerSourceEvent>) {
+    let mut last = PowerSource::on_ac();
+    if tx.send(PowerSourceEvent { on_ac: last }).is_err() {
+        return;
+    }
+    let mut debouncer = Debouncer::new(debounce);
+    let mut buf = vec![0u8; 8192];
+    loop {
+        let timeout = debouncer.remaining(Instant::now()).unwrap_or(FALLBACK_POLL);
+        // Without netlink, or on an idle wake-up, re-check directly in case
+        // an event was missed.
+        let mut recheck = true;
+        match &socket {
+            Some(fd) if wait_readable(fd, timeout) => {
+                recheck = false;
+                // SAFETY: buf is valid for buf.len() bytes for the duration of the call.
+                let n = unsafe { libc::recv(fd.as_raw_fd(), buf.as_mut_ptr().cast(), buf.len(), 0) };
+                if n > 0 && is_power_supply_uevent(&buf[..n as usize]) {
+                    debouncer.event(Instant::now());
+                }
+            }
+            Some(_) => {}
+            None => std::thread::sleep(timeout),
+        }
+        if debouncer.take_due(Instant::now()) || (recheck && debouncer.remaining(Instant::now()).is_none()) {
+            let on_ac = PowerSource::on_ac();
+            if on_ac != last {
+                last = on_ac;
+                log::info!("Power source changed: {}", if on_ac { "AC" } else { "battery" });
+                if tx.send(PowerSourceEvent { on_ac }).is_err() {
+                    return; // receiver gone
+                }
+            }
+        }
+    }
+}
+
+fn open_uevent_socket() -> Result<OwnedFd> {
+    // SAFETY: plain socket/bind syscalls; the fd is owned immediately.
+    unsafe {
+        let fd = libc::socket(libc::AF_NETLINK, libc::SOCK_DGRAM | libc::SOCK_CLOEXEC, libc::NETLINK_KOBJECT_UEVENT);
+        if fd < 0 {
+            return Err(std::io::Error::last_os_error().into());
+        }
+        let fd = OwnedFd::from_raw_fd(fd);
+        let mut addr: libc::sockaddr_nl = std::mem::zeroed();
+        addr.nl_family = libc::AF_NETLINK as libc::sa_family_t;
+        addr.nl_groups = 1; // kernel uevent multicast group
+        let rc = libc::bind(
+            fd.as_raw_fd(),
+            (&addr as *const libc::sockaddr_nl).cast(),
+            std::mem::size_of::<libc::sockaddr_nl>() as libc::socklen_t,
+        );
+        if rc < 0 {
+            return Err(std::io::Error::last_os_error().into());
+        }
+        Ok(fd)
+    }
+}
+
+fn wait_readable(fd: &OwnedFd, timeout: Duration) -> bool {
+    let mut pfd = libc::pollfd { fd: fd.as_raw_fd(), events: libc::POLLIN, revents: 0 };
+    let ms = timeout.as_millis().min(i32::MAX as u128) as i32;
+    // SAFETY: one valid pollfd.
+    unsafe { libc::poll(&mut pfd, 1, ms) > 0 && pfd.revents & libc::POLLIN != 0 }
+}
+
+/// Kernel uevents are `action@devpath\0KEY=VALUE\0…`.
+fn is_power_supply_uevent(msg: &[u8]) -> bool {
+    msg.split(|&b| b == 0).any(|field| field == b"SUBSYSTEM=power_supply")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_uevent_filter() {
+        let ac = b"change@/devices/LNXSYSTM:00/ACPI0003:00/power_supply/AC\0ACTION=change\0SUBSYSTEM=power_supply\0POWER_SUPPLY_ONLINE=0\0";
+        let usb = b"add@/devices/pci0000:00/usb1/1-1\0ACTION=add\0SUBSYSTEM=usb\0";
+        assert!(is_power_supply_uevent(ac));
+        assert!(!is_power_supply_uevent(usb));
+    }
+
+    #[test]
+    fn test_debounce() {
+        let mut d = Debouncer::new(Duration::from_secs(2));
+        let t0 = Instant::now();
+        assert!(!d.take_due(t0));
+        d.event(t0);
+        d.event(t0 + Duration::from_secs(1)); // flap restarts the window
+        assert!(!d.take_due(t0 + Duration::from_secs(2)));
+        assert!(d.take_due(t0 + Duration::from_secs(3)));
+        assert!(!d.take_due(t0 + Duration::from_secs(4)));
+    }
+}
@@ -1,97 +1,901 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
 use std::fs;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::time::Instant;
+use crate::backend::hwmon::HwmonReader;
+use crate::backend::power_source::PowerSource;
+use crate::utils::smoothing::{Smoother, SmoothingMethod};
 
+pub(crate) const POWER_SUPPLY_BASE: &str = "/sys/class/power_supply";
+
+/// One `/sys/class/power_supply/*` device of type `Battery`. Energy values
+/// are in Wh; batteries that only report `charge_*` (µAh) are converted
+/// with the design voltage.
+#[derive(Debug, Clone, Default)]
+pub struct Battery {
+    pub path: PathBuf,
+    /// Directory name, e.g. `BAT0`.
+    pub name: String,
+    pub status: String,
+    pub capacity_percent: Option<f32>,
+    pub energy_now_wh: Option<f32>,
+    pub energy_full_wh: Option<f32>,
+    pub energy_full_design_wh: Option<f32>,
+    /// Raw `charge_*` in Ah, for batteries that report charge rather than energy.
+    pub charge_now_ah: Option<f32>,
+    pub charge_full_ah: Option<f32>,
+    pub charge_full_design_ah: Option<f32>,
+    pub voltage_v: Option<f32>,
+    pub power_w: Option<f32>,
+    pub cycle_count: Option<u32>,
+    pub technology: Option<String>,
+    pub manufacturer: Option<String>,
+    pub model: Option<String>,
+    /// `charge_control_start_threshold` / `_end_threshold`, where the driver has them.
+    pub charge_start_threshold: Option<u8>,
+    pub charge_end_threshold: Option<u8>,
+    /// Active `charge_behaviour` (`auto`, `inhibit-charge`, `force-discharge`).
+    pub charge_behaviour: Option<String>,
+    pub charge_behaviours: Vec<String>,
+}
+
+impl Battery {
+    /// Full capacity as a percentage of the design capacity.
+    pub fn health_percent(&self) -> Option<f32> {
+        health(self.energy_full_wh, self.energy_full_design_wh)
+    }
+
+    fn read(path: &Path) -> Option<Battery> {
+        let attr = |name: &str| fs::read_to_string(path.join(name)).ok().map(|s| s.trim().to_string()).filter(|s| !s.is_empty());
+        let micro = |name: &str| attr(name)?.parse::<f64>().ok().map(|v| (v / 1e6) as f32);
+        if attr("type").as_deref() != Some("Battery") || attr("scope").as_deref() == Some("Device") {
+            return None; // AC adapters, and mice/keyboards/headsets reporting a battery
+        }
+        if attr("present").as_deref() == Some("0") {
+            return None;
+        }
+
+        let voltage_v = micro("voltage_now");
+        let design_v = micro("voltage_min_design").or(voltage_v);
+        let (charge_now_ah, charge_full_ah, charge_full_design_ah) =
+            (micro("charge_now"), micro("charge_full"), micro("charge_full_design"));
+        let to_wh = |ah: Option<f32>| Some(ah? * design_v?);
+        let power_w = micro("power_now").or_else(|| Some((micro("current_now")? * voltage_v?).abs()));
+
+        // "[auto] inhibit-charge force-discharge": the bracketed one is active.
+        let behaviours = attr("charge_behaviour").unwrap_or_default();
+        Some(Battery {
+            path: path.to_path_buf(),
+            name: path.file_name()?.to_string_lossy().to_string(),
+            status: attr("status").unwrap_or_else(|| "Unknown".into()),
+            capacity_percent: attr("capacity").and_then(|s| s.parse().ok()),
+            energy_now_wh: micro("energy_now").or_else(|| to_wh(charge_now_ah)),
+            energy_full_wh: micro("energy_full").or_else(|| to_wh(charge_full_ah)),
+            energy_full_design_wh: micro("energy_full_design").or_else(|| to_wh(charge_full_design_ah)),
+            charge_now_ah,
+            charge_full_ah,
+            charge_full_design_ah,
+            voltage_v,
+            power_w,
+            cycle_count: attr("cycle_count").and_then(|s| s.parse().ok()).filter(|&c| c > 0),
+            technology: attr("technology").filter(|t| t != "Unknown"),
+            manufacturer: attr("manufacturer"),
+            model: attr("model_name"),
+            charge_start_threshold: attr("charge_control_start_threshold").and_then(|s| s.parse().ok()),
+            charge_end_threshold: attr("charge_control_end_threshold").and_then(|s| s.parse().ok()),
+            charge_behaviour: behaviours.split_whitespace()
+                .find_map(|b| b.strip_prefix('[')?.strip_suffix(']'))
+                .map(str::to_string),
+            charge_behaviours: behaviours.split_whitespace()
+                .map(|b| b.trim_matches(|c| c == '[' || c == ']').to_string())
+                .collect(),
+        })
+    }
+
+    pub fn supports_charge_thresholds(&self) -> bool { self.charge_end_threshold.is_some() }
+
+    /// Writes the thresholds in whichever order keeps start < end at every
+    /// step, since drivers reject an intermediate inverted pair. Batteries
+    /// without a start threshold only get the end one.
+    pub fn set_charge_thresholds(&self, t: ChargeThresholds) -> Result<()> {
+        t.validate()?;
+        if !self.supports_charge_thresholds() {
+            anyhow::bail!("{} has no charge_control_end_threshold", self.name);
+        }
+        check_root()?;
+        let write = |file: &str, v: u8| {
+            fs::write(self.path.join(file), v.to_string())
+                .with_context(|| format!("Failed to write {} on {}", file, self.name))
+        };
+        let has_start = self.charge_start_threshold.is_some();
+        let current_end = self.charge_end_threshold.unwrap_or(100);
+        if has_start && t.start < current_end {
+            write("charge_control_start_threshold", t.start)?;
+            write("charge_control_end_threshold", t.end)?;
+        } else {
+            write("charge_control_end_threshold", t.end)?;
+            if has_start {
+                write("charge_control_start_threshold", t.start)?;
+            }
+        }
+        log::info!("Set {} charge thresholds to {}–{}%", self.name, t.start, t.end);
+        Ok(())
+    }
+
+    pub fn set_charge_behaviour(&self, behaviour: &str) -> Result<()> {
+        if !self.charge_behaviours.iter().any(|b| b == behaviour) {
+            anyhow::bail!(
+                "{} does not support charge behaviour '{}' (available: {})",
+                self.name, behaviour, self.charge_behaviours.join(", ")
+            );
+        }
+        check_root()?;
+        fs::write(self.path.join("charge_behaviour"), behaviour)
+            .with_context(|| format!("Failed to set charge behaviour on {}", self.name))?;
+        log::info!("Set {} charge behaviour to {}", self.name, behaviour);
+        Ok(())
+    }
+}
+
+/// Charging resumes below `start` % and stops at `end` %.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ChargeThresholds {
+    pub start: u8,
+    pub end: u8,
+}
+
+impl ChargeThresholds {
+    /// (name, description, thresholds)
+    pub const PRESETS: [(&'static str, &'static str, ChargeThresholds); 3] = [
+        ("full", "Full capacity 0–100%", ChargeThresholds { start: 0, end: 100 }),
+        ("balanced", "Balanced 75–90%", ChargeThresholds { start: 75, end: 90 }),
+        ("conserve", "Conserve 40–80%", ChargeThresholds { start: 40, end: 80 }),
+    ];
+
+    pub fn preset(name: &str) -> Option<ChargeThresholds> {
+        Self::PRESETS.iter().find(|(n, _, _)| n.eq_ignore_ascii_case(name)).map(|&(_, _, t)| t)
+    }
+
+    pub fn validate(&self) -> Result<()> {
+        if !(1..=100).contains(&self.end) {
+            anyhow::bail!("End threshold must be between 1 and 100% (got {})", self.end);
+        }
+        if self.start >= self.end {
+            anyhow::bail!("Start threshold ({}%) must be below end threshold ({}%)", self.start, self.end);
+        }
+        Ok(())
+    }
+}
+
+fn health(full: Option<f32>, design: Option<f32>) -> Option<f32> {
+    let (full, design) = (full?, design?);
+    (design > 0.0).then(|| full / design * 100.0)
+}
+
+/// All batteries combined.
 #[derive(Debug, Clone)]
 pub struct BatteryInfo {
     pub present: bool,
     pub on_ac: bool,
+    /// Energy-weighted over all batteries when they report energy,
+    /// otherwise the mean of their `capacity`.
     pub charge_percent: f32,
     pub power_now_w: Option<f32>,
     pub status: String,
+    pub energy_now_wh: Option<f32>,
+    pub energy_full_wh: Option<f32>,
+    pub energy_full_design_wh: Option<f32>,
+    pub batteries: Vec<Battery>,
+}
+
+impl BatteryInfo {
+    pub fn health_percent(&self) -> Option<f32> {
+        health(self.energy_full_wh, self.energy_full_design_wh)
+    }
+
+    pub fn is_charging(&self) -> bool { self.status == "Charging" }
+    pub fn is_discharging(&self) -> bool { self.status == "Discharging" }
+
+    fn aggregate(batteries: Vec<Battery>, on_ac: bool) -> BatteryInfo {
+        // A sum is only meaningful if every battery reports the value.
+        let sum = |f: fn(&Battery) -> Option<f32>| -> Option<f32> {
+            batteries.iter().map(f).sum::<Option<f32>>().filter(|_| !batteries.is_empty())
+        };
+        let energy_now_wh = sum(|b| b.energy_now_wh);
+        let energy_full_wh = sum(|b| b.energy_full_wh);
+        let charge_percent = match (energy_now_wh, energy_full_wh) {
+            (Some(now), Some(full)) if full > 0.0 => (now / full * 100.0).clamp(0.0, 100.0),
+            _ => {
+                let caps: Vec<f32> = batteries.iter().filter_map(|b| b.capacity_percent).collect();
+                if caps.is_empty() { 0.0 } else { caps.iter().sum::<f32>() / caps.len() as f32 }
+            }
+        };
+        // Paired with the energy sum: a pack-wide ETA needs every battery's draw.
+        let power_now_w = sum(|b| b.power_w);
+        let any = |st: &str| batteries.iter().any(|b| b.status == st);
+        let status = if any("Discharging") { "Discharging".to_string() }
+            else if any("Charging") { "Charging".to_string() }
+            else if let Some(b) = batteries.first() { b.status.clone() }
+            else if on_ac { "AC Power".into() }
+            else { "DC (No Battery)".into() };
+        BatteryInfo {
+            present: !batteries.is_empty(),
+            on_ac,
+            charge_percent,
+            power_now_w,
+            status,
+            energy_now_wh,
+            energy_full_wh,
+            energy_full_design_wh: sum(|b| b.energy_full_design_wh),
+            batteries,
+        }
+    }
+}
+
+/// Time-to-empty / time-to-full from an EMA of the battery power, reset
+/// whenever the battery switches between charging and discharging.
+#[derive(Debug, Default)]
+pub struct BatteryEstimator {
+    power: Smoother,
+    charging: Option<bool>,
+}
+
+impl BatteryEstimator {
+    /// `window` is the EMA span in samples.
+    pub fn new(window: usize) -> Self {
+        Self { power: Smoother::new(SmoothingMethod::Ema, window), charging: None }
+    }
+
+    /// Feeds one reading; returns `(seconds, charging)` until empty or full.
+    pub fn update(&mut self, bat: &BatteryInfo) -> Option<(f32, bool)> {
+        let charging = match (bat.is_charging(), bat.is_discharging()) {
+            (true, false) => true,
+            (false, true) => false,
+            _ => {
+                self.charging = None;
+                return None;
+            }
+        };
+        if self.charging != Some(charging) {
+            self.power.reset();
+            self.charging = Some(charging);
+        }
+        let watts = self.power.push(bat.power_now_w.filter(|&w| w > 0.1)?);
+        let now = bat.energy_now_wh?;
+        let remaining_wh = if charging { bat.energy_full_wh? - now } else { now };
+        Some(((remaining_wh.max(0.0) / watts) * 3600.0, charging))
+    }
 }
 
 pub struct PowerSupplyReader;
 
 impl PowerSupplyReader {
     pub fn read() -> BatteryInfo {
-        let on_ac = Self::check_ac();
-        for prefix in &["BAT", "CMB"] {
-            for i in 0..5 {
-                let base = format!("/sys/class/power_supply/{}{}", prefix, i);
-                if Path::new(&base).exists() {
-                    return Self::read_battery(&base, on_ac);
-                }
+        BatteryInfo::aggregate(Self::batteries(), PowerSource::on_ac())
+    }
+
+    /// `read` with the power source already known, e.g. from a
+    /// `PowerSourceWatcher` event, so the adapters aren't scanned again.
+    pub fn read_with_source(on_ac: bool) -> BatteryInfo {
+        BatteryInfo::aggregate(Self::batteries(), on_ac)
+    }
+
+    /// Every system battery, in name order.
+    pub fn batteries() -> Vec<Battery> {
+        let mut batteries: Vec<Battery> = fs::read_dir(POWER_SUPPLY_BASE)
+            .map(|entries| entries.filter_map(|e| e.ok()).filter_map(|e| Battery::read(&e.path())).collect())
+            .unwrap_or_default();
+        batteries.sort_by(|a, b| a.name.cmp(&b.name));
+        batteries
+    }
+
+    /// Applies thresholds (and optionally a charge behaviour) to every
+    /// battery that supports them. Fails if none do.
+    pub fn apply_charge_settings(thresholds: ChargeThresholds, behaviour: Option<&str>) -> Result<()> {
+        thresholds.validate()?;
+        let batteries: Vec<Battery> = Self::batteries().into_iter()
+            .filter(|b| b.supports_charge_thresholds())
+            .collect();
+        if batteries.is_empty() {
+            anyhow::bail!("No battery exposes charge_control_end_threshold (driver support is required)");
+        }
+        for b in &batteries {
+            b.set_charge_thresholds(thresholds)?;
+            if let Some(behaviour) = behaviour.filter(|_| !b.charge_behaviours.is_empty()) {
+                b.set_charge_behaviour(behaviour)?;
             }
         }
-        BatteryInfo {
-            present: false,
-            on_ac,
-            charge_percent: 0.0,
-            power_now_w: None,
-            status: if on_ac { "AC Power".into() } else { "DC (No Battery)".into() },
+        Ok(())
+    }
+
+    /// True when every battery with thresholds already has these settings.
+    pub fn charge_settings_match(thresholds: ChargeThresholds, behaviour: Option<&str>) -> bool {
+        Self::batteries().iter().filter(|b| b.supports_charge_thresholds()).all(|b| {
+            b.charge_end_threshold == Some(thresholds.end)
+                && b.charge_start_threshold.is_none_or(|s| s == thresholds.start)
+                && behaviour.is_none_or(|want| b.charge_behaviours.is_empty() || b.charge_behaviour.as_deref() == Some(want))
+        })
+    }
+}
+
+/// What an energy counter measures.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EnergyDomainKind {
+    Package,
+    Core,
+    Uncore,
+    Dram,
+    Psys,
+    Other,
+}
+
+impl EnergyDomainKind {
+    fn from_name(name: &str) -> Self {
+        let n = name.to_lowercase();
+        if n.starts_with("package") || n.starts_with("esocket") { Self::Package }
+        else if n == "core" || n.starts_with("ecore") { Self::Core }
+        else if n == "uncore" { Self::Uncore }
+        else if n == "dram" { Self::Dram }
+        else if n == "psys" { Self::Psys }
+        else { Self::Other }
+    }
+}
+
+/// One energy counter: a powercap zone/subzone, or an `amd_energy` hwmon source.
+#[derive(Debug, Clone)]
+pub struct EnergyDomain {
+    /// Stable identifier, e.g. `intel-rapl:1:0` or `amd_energy:Esocket0`.
+    pub id: String,
+    /// Display name, e.g. `package-1`, `dram`, `cores`.
+    pub name: String,
+    pub kind: EnergyDomainKind,
+    pub socket: Option<usize>,
+    /// Counter files in µJ; summed when a domain aggregates several channels.
+    counters: Vec<PathBuf>,
+    /// Wraparound point from `max_energy_range_uj`; `None` for 64-bit accumulators.
+    max_energy_range_uj: Option<u64>,
+}
+
+impl EnergyDomain {
+    /// Every powercap zone and subzone with an energy counter, across all sockets.
+    /// Falls back to the `amd_energy` hwmon driver when powercap has no packages.
+    pub fn discover() -> Vec<EnergyDomain> {
+        let zones = RaplZone::all();
+        let mut domains: Vec<EnergyDomain> = zones.iter()
+            .filter(|z| z.has_energy && !z.id.contains("mmio"))
+            .map(|z| {
+                // Subzones (core/uncore/dram) inherit the socket of their package.
+                let package = z.parent.as_ref()
+                    .and_then(|p| zones.iter().find(|pz| &pz.id == p))
+                    .unwrap_or(z);
+                EnergyDomain {
+                    id: z.id.clone(),
+                    name: z.name.clone(),
+                    kind: EnergyDomainKind::from_name(&z.name),
+                    socket: package.name.strip_prefix("package-").and_then(|n| n.parse().ok()),
+                    counters: vec![z.path.join("energy_uj")],
+                    max_energy_range_uj: z.max_energy_range_uj,
+                }
+            })
+            .collect();
+        if !domains.iter().any(|d| d.kind == EnergyDomainKind::Package) {
+            domains.extend(Self::discover_amd_energy());
         }
+        domains
     }
 
-    fn check_ac() -> bool {
-        for name in &["AC0", "AC", "ACAD", "ADP0", "ADP1"] {
-            let path = format!("/sys/class/power_supply/{}/online", name);
-            if let Ok(s) = fs::read_to_string(&path) {
-                return s.trim() == "1";
+    /// `amd_energy` exposes `energyN_input` (µJ) labelled `EsocketN` and `EcoreNNN`.
+    /// Socket channels become package domains; the per-core channels are summed.
+    fn discover_amd_energy() -> Vec<EnergyDomain> {
+        let Some(base) = HwmonReader::find_device("amd_energy") else { return Vec::new() };
+        let mut domains = Vec::new();
+        let mut cores = Vec::new();
+        for n in 1..=512 {
+            let input = base.join(format!("energy{}_input", n));
+            if !input.exists() { break; }
+            let label = fs::read_to_string(base.join(format!("energy{}_label", n)))
+                .map(|s| s.trim().to_string())
+                .unwrap_or_default();
+            match label.strip_prefix("Esocket") {
+                Some(sock) => domains.push(EnergyDomain {
+                    id: format!("amd_energy:{}", label),
+                    name: format!("package-{}", sock),
+                    kind: EnergyDomainKind::Package,
+                    socket: sock.parse().ok(),
+                    counters: vec![input],
+                    max_energy_range_uj: None,
+                }),
+                None => cores.push(input),
             }
         }
-        true
-    }
-
-    fn read_battery(base: &str, on_ac: bool) -> BatteryInfo {
-        let charge = fs::read_to_string(format!("{}/capacity", base))
-            .ok().and_then(|s| s.trim().parse().ok()).unwrap_or(0.0f32);
-        let status = fs::read_to_string(format!("{}/status", base))
-            .map(|s| s.trim().to_string()).unwrap_or_else(|_| "Unknown".into());
-        let power_now_w = fs::read_to_string(format!("{}/power_now", base))
-            .ok().and_then(|s| s.trim().parse::<u64>().ok())
-            .map(|uw| uw as f32 / 1_000_000.0)
-            .or_else(|| {
-                let ua = fs::read_to_string(format!("{}/current_now", base))
-                    .ok()?.trim().parse::<f64>().ok()?;
-                let uv = fs::read_to_string(format!("{}/voltage_now", base))
-                    .ok()?.trim().parse::<f64>().ok()?;
-                Some((ua * uv / 1e12) as f32)
+        if !cores.is_empty() {
+            domains.push(EnergyDomain {
+                id: "amd_energy:cores".into(),
+                name: "cores".into(),
+                kind: EnergyDomainKind::Core,
+                socket: None,
+                counters: cores,
+                max_energy_range_uj: None,
             });
-        BatteryInfo { present: true, on_ac, charge_percent: charge, power_now_w, status }
+        }
+        domains
+    }
+
+    fn read_energy_uj(&self) -> Option<u64> {
+        self.counters.iter().map(|p| read_u64(p)).sum()
     }
 }
 
-/// Tracks CPU package power draw via Intel RAPL energy counter (two-sample delta).
+/// Power drawn by one domain over the last sample interval.
+#[derive(Debug, Clone)]
+pub struct DomainPower {
+    pub id: String,
+    pub name: String,
+    pub kind: EnergyDomainKind,
+    pub socket: Option<usize>,
+    pub watts: f32,
+}
+
+/// Energy consumed between two counter reads, handling wraparound at
+/// `max_energy_range_uj`. Returns `None` if the counter went backwards and
+/// the range is unknown (e.g. a driver reload), so the sample can be dropped.
+fn energy_delta_uj(prev: u64, now: u64, max_range: Option<u64>) -> Option<u64> {
+    if now >= prev {
+        Some(now - prev)
+    } else {
+        max_range.filter(|&r| r >= prev).map(|r| r - prev + now)
+    }
+}
+
+/// Tracks CPU power draw for every RAPL domain (two-sample energy delta).
+/// Instantiate once, call `sample()` or `get_power_w()` each tick.
 pub struct RaplTracker {
-    prev_energy_uj: u64,
+    domains: Vec<EnergyDomain>,
+    prev_energy_uj: Vec<Option<u64>>,
     prev_time: Instant,
+    last: Vec<DomainPower>,
 }
 
 impl RaplTracker {
-    const PATH: &'static str = "/sys/class/powercap/intel-rapl/intel-rapl:0/energy_uj";
-
     pub fn new() -> Self {
+        let domains = EnergyDomain::discover();
+        log::info!("Discovered {} RAPL energy domains", domains.len());
         Self {
-            prev_energy_uj: Self::read_energy().unwrap_or(0),
+            prev_energy_uj: domains.iter().map(|d| d.read_energy_uj()).collect(),
+            domains,
             prev_time: Instant::now(),
+            last: Vec::new(),
         }
     }
 
-    pub fn is_available() -> bool { Path::new(Self::PATH).exists() }
+    pub fn domains(&self) -> &[EnergyDomain] { &self.domains }
 
-    /// Watts since last call. Returns None until at least one full interval has elapsed.
-    pub fn get_power_w(&mut self) -> Option<f32> {
-        let energy = Self::read_energy()?;
+    /// Per-domain watts since the last call. Returns None until at least one
+    /// full interval has elapsed.
+    pub fn sample(&mut self) -> Option<&[DomainPower]> {
+        if self.domains.is_empty() { return None; }
         let elapsed = self.prev_time.elapsed().as_secs_f64();
         if elapsed < 0.1 { return None; }
-        let delta_uj = energy.wrapping_sub(self.prev_energy_uj);
-        self.prev_energy_uj = energy;
         self.prev_time = Instant::now();
-        Some((delta_uj as f64 / elapsed / 1_000_000.0) as f32)
+        let mut out = Vec::with_capacity(self.domains.len());
+        for (domain, prev) in self.domains.iter().zip(self.prev_energy_uj.iter_mut()) {
+            let now = domain.read_energy_uj();
+            if let (Some(p), Some(n)) = (*prev, now) {
+                if let Some(delta) = energy_delta_uj(p, n, domain.max_energy_range_uj) {
+                    out.push(DomainPower {
+                        id: domain.id.clone(),
+                        name: domain.name.clone(),
+                        kind: domain.kind,
+                        socket: domain.socket,
+                        watts: (delta as f64 / elapsed / 1_000_000.0) as f32,
+                    });
+                }
+            }
+            *prev = now;
+        }
+        self.last = out;
+        Some(&self.last)
     }
 
-    fn read_energy() -> Option<u64> {
-        fs::read_to_string(Self::PATH).ok()?.trim().parse().ok()
+    /// Breakdown from the most recent `sample()`.
+    pub fn last_breakdown(&self) -> &[DomainPower] { &self.last }
+
+    /// Total CPU package watts (all sockets) since last call, or psys when no
+    /// package counter exists. Returns None until a full interval has elapsed.
+    pub fn get_power_w(&mut self) -> Option<f32> {
+        let breakdown = self.sample()?;
+        Self::total_w(breakdown)
+    }
+
+    pub fn total_w(breakdown: &[DomainPower]) -> Option<f32> {
+        for kind in [EnergyDomainKind::Package, EnergyDomainKind::Psys] {
+            let parts: Vec<f32> = breakdown.iter().filter(|d| d.kind == kind).map(|d| d.watts).collect();
+            if !parts.is_empty() {
+                return Some(parts.iter().sum());
+            }
+        }
+        None
+    }
+}
+
+/// Package energy (all sockets, or psys without a package counter) summed
+/// from counter deltas, for measuring a run exactly instead of integrating
+/// sampled power.
+pub struct EnergyMeter {
+    domains: Vec<EnergyDomain>,
+    prev_energy_uj: Vec<Option<u64>>,
+    total_uj: u64,
+}
+
+impl EnergyMeter {
+    /// Reads the starting counters now.
+    pub fn new() -> Self {
+        let all = EnergyDomain::discover();
+        let domains: Vec<EnergyDomain> = [EnergyDomainKind::Package, EnergyDomainKind::Psys].iter()
+            .map(|&kind| all.iter().filter(|d| d.kind == kind).cloned().collect::<Vec<_>>())
+            .find(|d| !d.is_empty())
+            .unwrap_or_default();
+        Self {
+            prev_energy_uj: domains.iter().map(|d| d.read_energy_uj()).collect(),
+            domains,
+            total_uj: 0,
+        }
+    }
+
+    /// Energy in joules since `new()`; `None` without a readable counter.
+    /// Call often enough that no counter wraps twice between reads.
+    pub fn read_j(&mut self) -> Option<f64> {
+        let mut readable = false;
+        for (domain, prev) in self.domains.iter().zip(self.prev_energy_uj.iter_mut()) {
+            let now = domain.read_energy_uj();
+            if let (Some(p), Some(n)) = (*prev, now) {
+                readable = true;
+                self.total_uj += energy_delta_uj(p, n, domain.max_energy_range_uj).unwrap_or(0);
+            }
+            *prev = now.or(*prev);
+        }
+        readable.then(|| self.total_uj as f64 / 1_000_000.0)
+    }
+}
+
+const POWERCAP_BASE: &str = "/sys/class/powercap";
+
+/// Package limits as the firmware set them, recorded before the first write
+/// so a profile without PL1/PL2 can put them back. Under `/run`, so the GUI,
+/// the service and the CLI share it; the unit preserves the directory across
+/// service restarts, and the record carries the boot id so one left over from
+/// a previous boot is never restored.
+pub const FIRMWARE_LIMITS_FILE: &str = "/run/cpu-power-manager/rapl-defaults.toml";
+
+/// One `constraint_N_*` group of a powercap zone.
+/// On Intel, constraint 0 is `long_term` (PL1) and constraint 1 is `short_term` (PL2).
+#[derive(Debug, Clone)]
+pub struct PowerConstraint {
+    pub id: usize,
+    pub name: String,
+    pub power_limit_uw: u64,
+    /// Upper bound reported by the kernel; `None` when absent or reported as 0.
+    pub max_power_uw: Option<u64>,
+    pub time_window_us: Option<u64>,
+}
+
+impl PowerConstraint {
+    pub fn power_limit_w(&self) -> f32 { self.power_limit_uw as f32 / 1_000_000.0 }
+    pub fn max_power_w(&self) -> Option<f32> { self.max_power_uw.map(|uw| uw as f32 / 1_000_000.0) }
+}
+
+/// A powercap zone such as `intel-rapl:0` (package-0) or `intel-rapl-mmio:0`.
+#[derive(Debug, Clone)]
+pub struct RaplZone {
+    pub path: PathBuf,
+    /// Directory name, e.g. `intel-rapl:0:1`.
+    pub id: String,
+    /// Contents of `name`, e.g. `package-0`, `core`, `dram`.
+    pub name: String,
+    /// Id of the enclosing zone for subzones (`intel-rapl:0` for `intel-rapl:0:1`).
+    pub parent: Option<String>,
+    pub has_energy: bool,
+    pub max_energy_range_uj: Option<u64>,
+    pub constraints: Vec<PowerConstraint>,
+}
+
+impl RaplZone {
+    /// Every powercap zone and subzone (they are all linked flat under the class dir).
+    pub fn all() -> Vec<RaplZone> {
+        let mut zones: Vec<RaplZone> = fs::read_dir(POWERCAP_BASE)
+            .map(|entries| {
+                entries
+                    .filter_map(|e| e.ok())
+                    .filter(|e| e.file_name().to_string_lossy().contains(':'))
+                    .filter_map(|e| Self::read(&e.path()))
+                    .collect()
+            })
+            .unwrap_or_default();
+        zones.sort_by(|a, b| a.id.cmp(&b.id));
+        zones
+    }
+
+    /// All powercap zones that expose at least one power-limit constraint.
+    pub fn discover() -> Vec<RaplZone> {
+        Self::all().into_iter().filter(|z| !z.constraints.is_empty()).collect()
+    }
+
+    /// Package zones only — the ones PL1/PL2 in a profile apply to.
+    pub fn packages() -> Vec<RaplZone> {
+        Self::discover().into_iter().filter(|z| z.is_package()).collect()
+    }
+
+    /// MSR package zones. `intel-rapl-mmio` mirrors the same package under
+    /// its own name, and firmware often locks or ignores it.
+    pub fn is_package(&self) -> bool {
+        self.name.starts_with("package") && !self.id.contains("mmio")
+    }
+
+    fn read(path: &Path) -> Option<RaplZone> {
+        let id = path.file_name()?.to_string_lossy().to_string();
+        let name = fs::read_to_string(path.join("name")).ok()?.trim().to_string();
+        let mut constraints = Vec::new();
+        for n in 0..8 {
+            let limit = match read_u64(&path.join(format!("constraint_{}_power_limit_uw", n))) {
+                Some(v) => v,
+                None => break,
+            };
+            constraints.push(PowerConstraint {
+                id: n,
+                name: fs::read_to_string(path.join(format!("constraint_{}_name", n)))
+                    .map(|s| s.trim().to_string())
+                    .unwrap_or_else(|_| format!("constraint_{}", n)),
+                power_limit_uw: limit,
+                max_power_uw: read_u64(&path.join(format!("constraint_{}_max_power_uw", n)))
+                    .filter(|&v| v > 0),
+                time_window_us: read_u64(&path.join(format!("constraint_{}_time_window_us", n))),
+            });
+        }
+        let parent = id.rsplit_once(':')
+            .map(|(head, _)| head)
+            .filter(|head| head.contains(':'))
+            .map(str::to_string);
+        Some(RaplZone {
+            path: path.to_path_buf(),
+            parent,
+            has_energy: path.join("energy_uj").exists(),
+            max_energy_range_uj: read_u64(&path.join("max_energy_range_uj")),
+            id,
+            name,
+            constraints,
+        })
+    }
+
+    /// Constraint by kernel name (`long_term`, `short_term`, `peak_power`).
+    pub fn constraint(&self, name: &str) -> Option<&PowerConstraint> {
+        self.constraints.iter().find(|c| c.name == name)
+    }
+
+    pub fn set_power_limit(&self, constraint: usize, watts: f32) -> Result<()> {
+        let c = self.constraints.iter().find(|c| c.id == constraint)
+            .with_context(|| format!("{} has no constraint {}", self.id, constraint))?;
+        let uw = validate_power_limit(watts, c.max_power_uw)
+            .with_context(|| format!("Invalid {} limit for {}", c.name, self.id))?;
+        check_root()?;
+        if self.is_package() {
+            record_firmware_limits();
+        }
+        let path = self.path.join(format!("constraint_{}_power_limit_uw", constraint));
+        fs::write(&path, uw.to_string()).with_context(|| {
+            format!("Failed to set {} power limit on {}. Run with sudo or enable PolicyKit.", c.name, self.id)
+        })?;
+        log::info!("Set {} {} limit to {:.1} W", self.id, c.name, watts);
+        Ok(())
+    }
+
+    /// Writes several `(constraint, watts)` limits at once. Rejects PL1 > PL2 and
+    /// orders the writes (lowering before raising) so that never happens transiently.
+    pub fn set_limits(&self, limits: &[(usize, f32)]) -> Result<()> {
+        let target = |name: &str| {
+            let c = self.constraint(name)?;
+            limits.iter().find(|(id, _)| *id == c.id).map(|&(_, w)| w).or(Some(c.power_limit_w()))
+        };
+        if let (Some(pl1), Some(pl2)) = (target("long_term"), target("short_term")) {
+            if pl1 > pl2 {
+                anyhow::bail!("PL1 ({:.1} W) must not exceed PL2 ({:.1} W) on {}", pl1, pl2, self.id);
+            }
+        }
+        let current = |id: usize| self.constraints.iter().find(|c| c.id == id).map(|c| c.power_limit_w());
+        let mut ordered = limits.to_vec();
+        ordered.sort_by_key(|&(id, w)| {
+            if current(id).is_some_and(|cur| w < cur) { (0, id as i64) } else { (1, -(id as i64)) }
+        });
+        for (id, watts) in ordered {
+            self.set_power_limit(id, watts)?;
+        }
+        Ok(())
+    }
+
+    pub fn set_time_window(&self, constraint: usize, window_us: u64) -> Result<()> {
+        if window_us == 0 {
+            anyhow::bail!("Time window must be greater than zero");
+        }
+        check_root()?;
+        let path = self.path.join(format!("constraint_{}_time_window_us", constraint));
+        fs::write(&path, window_us.to_string()).with_context(|| {
+            format!("Failed to set time window for constraint {} on {}", constraint, self.id)
+        })?;
+        log::info!("Set {} constraint {} time window to {} us", self.id, constraint, window_us);
+        Ok(())
+    }
+}
+
+const BOOT_ID: &str = "/proc/sys/kernel/random/boot_id";
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct FirmwareLimits {
+    /// `BOOT_ID` at the time of recording.
+    #[serde(default)]
+    boot_id: String,
+    /// (zone id, constraint id, watts)
+    limits: Vec<(String, usize, f32)>,
+}
+
+fn boot_id() -> String {
+    fs::read_to_string(BOOT_ID).map(|s| s.trim().to_string()).unwrap_or_default()
+}
+
+/// (zone id, constraint id, watts) from `FIRMWARE_LIMITS_FILE`; `None`
+/// when nothing has changed the package limits during this boot.
+fn recorded_firmware_limits() -> Option<Vec<(String, usize, f32)>> {
+    let text = fs::read_to_string(FIRMWARE_LIMITS_FILE).ok()?;
+    toml::from_str::<FirmwareLimits>(&text).ok()
+        .filter(|f| f.boot_id == boot_id())
+        .map(|f| f.limits)
+}
+
+/// Records the current package limits in `FIRMWARE_LIMITS_FILE` unless that
+/// already happened during this boot. Called by `set_power_limit` before it
+/// writes a package zone; `apply_package_limits` only reads the record.
+fn record_firmware_limits() {
+    if recorded_firmware_limits().is_some() {
+        return;
+    }
+    let path = Path::new(FIRMWARE_LIMITS_FILE);
+    let limits = RaplZone::packages().iter()
+        .flat_map(|z| z.constraints.iter().map(|c| (z.id.clone(), c.id, c.power_limit_w())))
+        .collect();
+    let saved = FirmwareLimits { boot_id: boot_id(), limits };
+    let written = path.parent().map_or(Ok(()), fs::create_dir_all)
+        .map_err(anyhow::Error::from)
+        .and_then(|_| Ok(fs::write(path, toml::to_string(&saved)?)?));
+    if let Err(e) = written {
+        log::warn!("Failed to record firmware power limits in {}: {:#}", path.display(), e);
+    }
+}
+
+/// Writes PL1 (`long_term`) and PL2 (`short_term`) on every package zone.
+/// A limit given as `None` goes back to the firmware value recorded before
+/// the first change, moved as far as needed to keep PL1 ≤ PL2 against the
+/// one that was given; limits already at their target aren't rewritten.
+pub fn apply_package_limits(pl1_w: Option<u32>, pl2_w: Option<u32>) -> Result<()> {
+    let zones = RaplZone::packages();
+    if zones.is_empty() {
+        if pl1_w.is_none() && pl2_w.is_none() {
+            return Ok(());
+        }
+        anyhow::bail!("No RAPL package zones found under {}", POWERCAP_BASE);
+    }
+    // Only read once something has to go back.
+    let mut firmware = None;
+    for zone in &zones {
+        let mut target = |name: &str, watts: Option<u32>| {
+            let c = zone.constraint(name)?;
+            let w = match watts {
+                Some(w) => w as f32,
+                None => firmware.get_or_insert_with(recorded_firmware_limits).iter().flatten()
+                    .find(|(id, k, _)| *id == zone.id && *k == c.id)
+                    .map_or(c.power_limit_w(), |&(_, _, w)| w),
+            };
+            Some((c, w))
+        };
+        let (mut pl1, mut pl2) = (target("long_term", pl1_w), target("short_term", pl2_w));
+        if let (Some((_, w1)), Some((_, w2))) = (&mut pl1, &mut pl2) {
+            match (pl1_w, pl2_w) {
+                (Some(_), None) => *w2 = w2.max(*w1),
+                (None, Some(_)) => *w1 = w1.min(*w2),
+                _ => {}
+            }
+        }
+        let limits: Vec<(usize, f32)> = [pl1, pl2].into_iter().flatten()
+            .filter(|(c, w)| *w != c.power_limit_w())
+            .map(|(c, w)| (c.id, w))
+            .collect();
+        if !limits.is_empty() {
+            zone.set_limits(&limits)?;
+        }
+    }
+    Ok(())
+}
+
+/// Converts watts to µW and checks it against the kernel-reported maximum.
+fn validate_power_limit(watts: f32, max_power_uw: Option<u64>) -> Result<u64> {
+    if !watts.is_finite() || watts <= 0.0 {
+        anyhow::bail!("Power limit must be a positive number of watts");
+    }
+    let uw = (watts as f64 * 1_000_000.0).round() as u64;
+    if let Some(max) = max_power_uw {
+        if uw > max {
+            anyhow::bail!(
+                "{:.1} W exceeds the hardware maximum of {:.1} W",
+                watts, max as f64 / 1_000_000.0
+            );
+        }
+    }
+    Ok(uw)
+}
+
+fn read_u64(path: &Path) -> Option<u64> {
+    fs::read_to_string(path).ok()?.trim().parse().ok()
+}
+
+fn check_root() -> Result<()> {
+    if !nix::unistd::Uid::effective().is_root() {
+        anyhow::bail!("Root privileges required. Please run with 'sudo' or configure PolicyKit.");
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate_power_limit() {
+        assert_eq!(validate_power_limit(15.0, None).unwrap(), 15_000_000);
+        assert_eq!(validate_power_limit(28.5, Some(64_000_000)).unwrap(), 28_500_000);
+        assert!(validate_power_limit(65.0, Some(64_000_000)).is_err());
+        assert!(validate_power_limit(0.0, None).is_err());
+        assert!(validate_power_limit(f32::NAN, None).is_err());
+    }
+
+    #[test]
+    fn test_energy_delta_wraparound() {
+        assert_eq!(energy_delta_uj(1_000, 5_000, None), Some(4_000));
+        // 32-bit-ish counter wrapping at 262143328850 µJ
+        let range = 262_143_328_850;
+        assert_eq!(energy_delta_uj(range - 100, 50, Some(range)), Some(150));
+        assert_eq!(energy_delta_uj(5_000, 1_000, None), None);
+    }
+
+    #[test]
+    fn test_charge_thresholds() {
+        assert!(ChargeThresholds::preset("Conserve").unwrap().validate().is_ok());
+        assert!(ChargeThresholds { start: 80, end: 80 }.validate().is_err());
+        assert!(ChargeThresholds { start: 0, end: 0 }.validate().is_err());
+        assert!(ChargeThresholds { start: 0, end: 101 }.validate().is_err());
+    }
+
+    #[test]
+    fn test_battery_aggregate_and_estimate() {
+        let bat = |name: &str, now: f32, full: f32, w: f32| Battery {
+            name: name.into(),
+            status: "Discharging".into(),
+            energy_now_wh: Some(now),
+            energy_full_wh: Some(full),
+            energy_full_design_wh: Some(50.0),
+            power_w: Some(w),
+            ..Default::default()
+        };
+        let info = BatteryInfo::aggregate(vec![bat("BAT0", 20.0, 40.0, 6.0), bat("BAT1", 10.0, 20.0, 4.0)], false);
+        assert_eq!(info.charge_percent, 50.0);
+        assert_eq!(info.power_now_w, Some(10.0));
+        assert!((info.health_percent().unwrap() - 60.0).abs() < 1e-3);
+        // 30 Wh at 10 W: three hours.
+        let (secs, charging) = BatteryEstimator::new(10).update(&info).unwrap();
+        assert!(!charging);
+        assert!((secs - 3.0 * 3600.0).abs() < 1.0);
+
+        // One battery without a power or current reading: no pack ETA.
+        let blind = Battery { power_w: None, ..bat("BAT1", 10.0, 20.0, 0.0) };
+        let info = BatteryInfo::aggregate(vec![bat("BAT0", 20.0, 40.0, 6.0), blind], false);
+        assert_eq!(info.power_now_w, None);
+        assert!(BatteryEstimator::new(10).update(&info).is_none());
     }
 }
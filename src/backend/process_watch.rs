@@ -0,0 +1,177 @@
+use anyhow::{Context, Result};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// `[[auto_tune.app_rules]]`: use `profile` while a matching process runs.
+/// Every criterion that is set must match; a rule with none never matches.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AppRule {
+    /// Label used in logs and switch reasons.
+    pub name: String,
+    pub profile: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub turbo: Option<bool>,
+    /// Higher wins when several rules match; ties go to the earlier rule.
+    #[serde(default)]
+    pub priority: i32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub exe: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub path: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cmdline: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cgroup: Option<String>,
+}
+
+impl AppRule {
+    pub fn matcher(&self) -> ProcessMatch {
+        ProcessMatch {
+            exe: self.exe.clone(),
+            path: self.path.clone(),
+            cmdline: self.cmdline.clone(),
+            cgroup: self.cgroup.clone(),
+        }
+    }
+}
+
+/// What a process must look like. Every criterion that is set must match;
+/// a matcher with none never matches.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ProcessMatch {
+    /// Executable name, compared with the binary's file name and the
+    /// process name (`comm`), e.g. `cargo`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub exe: Option<String>,
+    /// Full executable path; a trailing `/` matches everything below it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub path: Option<String>,
+    /// Regular expression searched in the space-joined command line.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cmdline: Option<String>,
+    /// Substring of the process's cgroup path, e.g. `app-steam`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cgroup: Option<String>,
+}
+
+impl ProcessMatch {
+    fn is_empty(&self) -> bool {
+        self.exe.is_none() && self.path.is_none() && self.cmdline.is_none() && self.cgroup.is_none()
+    }
+
+    /// The compiled `cmdline` pattern, to pass to `matches`.
+    pub fn compile(&self) -> Result<Option<Regex>> {
+        self.cmdline.as_deref().map(Regex::new).transpose().context("Invalid cmdline regex")
+    }
+
+    pub fn matches(&self, p: &ProcessInfo, cmdline: Option<&Regex>) -> bool {
+        !self.is_empty()
+            && self.exe.as_ref().is_none_or(|want| p.comm == *want || p.exe_name() == Some(want.as_str()))
+            && self.path.as_ref().is_none_or(|want| p.exe.as_ref().is_some_and(|exe| {
+                if want.ends_with('/') { exe.starts_with(want.as_str()) } else { exe == want }
+            }))
+            && (self.cmdline.is_none() || cmdline.is_some_and(|re| re.is_match(&p.cmdline)))
+            && self.cgroup.as_ref().is_none_or(|want| p.cgroup.contains(want.as_str()))
+    }
+}
+
+/// Which optional /proc files a scan reads; the rest stay empty.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ScanFields {
+    pub exe: bool,
+    pub cmdline: bool,
+    pub cgroup: bool,
+}
+
+impl ScanFields {
+    pub fn add(&mut self, m: &ProcessMatch) {
+        self.exe |= m.exe.is_some() || m.path.is_some();
+        self.cmdline |= m.cmdline.is_some();
+        self.cgroup |= m.cgroup.is_some();
+    }
+}
+
+/// The parts of a process the matchers look at.
+#[derive(Debug, Clone, Default)]
+pub struct ProcessInfo {
+    pub pid: i32,
+    pub comm: String,
+    /// `None` when /proc/<pid>/exe is unreadable (another user's process
+    /// without root, or a kernel thread).
+    pub exe: Option<String>,
+    pub cmdline: String,
+    pub cgroup: String,
+}
+
+impl ProcessInfo {
+    fn exe_name(&self) -> Option<&str> {
+        self.exe.as_deref().and_then(|e| Path::new(e).file_name()).and_then(|n| n.to_str())
+    }
+
+    /// Short description for switch reasons, e.g. `cargo (pid 4242)`.
+    pub fn describe(&self) -> String {
+        format!("{} (pid {})", self.exe_name().unwrap_or(&self.comm), self.pid)
+    }
+}
+
+pub fn scan_processes(fields: ScanFields) -> Result<Vec<ProcessInfo>> {
+    let own = std::process::id() as i32;
+    let mut out = Vec::new();
+    for proc in procfs::process::all_processes().context("Failed to list /proc")?.flatten() {
+        if proc.pid == own {
+            continue;
+        }
+        // Processes can exit mid-scan; skip what can't be read.
+        let Ok(stat) = proc.stat() else { continue };
+        let mut info = ProcessInfo { pid: proc.pid, comm: stat.comm, ..Default::default() };
+        if fields.exe {
+            info.exe = proc.exe().ok().map(|p| p.to_string_lossy().into_owned());
+        }
+        if fields.cmdline {
+            info.cmdline = proc.cmdline().map(|args| args.join(" ")).unwrap_or_default();
+        }
+        if fields.cgroup {
+            info.cgroup = proc.cgroups()
+                .map(|cg| cg.0.iter().map(|g| g.pathname.as_str()).collect::<Vec<_>>().join(" "))
+                .unwrap_or_default();
+        }
+        out.push(info);
+    }
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn process(pid: i32, comm: &str, exe: &str, cmdline: &str) -> ProcessInfo {
+        ProcessInfo { pid, comm: comm.into(), exe: Some(exe.into()), cmdline: cmdline.into(), cgroup: String::new() }
+    }
+
+    #[test]
+    fn test_process_matching() {
+        let build = ProcessMatch { exe: Some("cargo".into()), ..Default::default() };
+        let game = ProcessMatch { path: Some("/opt/games/".into()), ..Default::default() };
+        let encode = ProcessMatch { cmdline: Some(r"ffmpeg .*-c:v libx26[45]".into()), ..Default::default() };
+
+        // rustup's proxy: exe is rustup, comm is cargo.
+        let cargo = process(10, "cargo", "/home/u/.cargo/bin/rustup", "cargo build");
+        let ffmpeg = process(20, "ffmpeg", "/usr/bin/ffmpeg", "ffmpeg -i a.mkv -c:v libx265 b.mkv");
+        let game_proc = process(30, "game.x86_64", "/opt/games/foo/game.x86_64", "./game.x86_64");
+        let bash = process(40, "bash", "/usr/bin/bash", "bash");
+
+        let matching = |m: &ProcessMatch| {
+            let re = m.compile().unwrap();
+            [&cargo, &ffmpeg, &game_proc, &bash].iter()
+                .filter(|p| m.matches(p, re.as_ref()))
+                .map(|p| p.pid)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(matching(&build), vec![10]);
+        assert_eq!(matching(&game), vec![30]);
+        assert_eq!(matching(&encode), vec![20]);
+        assert!(matching(&ProcessMatch::default()).is_empty());
+        assert!(ProcessMatch { cmdline: Some("(".into()), ..Default::default() }.compile().is_err());
+    }
+}
@@ -1,6 +1,7 @@
 use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
 use crate::backend::cpu::CpuManager;
+use crate::backend::power_supply;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Profile {
@@ -14,6 +15,12 @@ pub struct Profile {
     pub epp: Option<String>,
     #[serde(default)]
     pub epb: Option<u8>,
+    /// RAPL long-term package power limit (PL1), in watts.
+    #[serde(default)]
+    pub pl1_watts: Option<u32>,
+    /// RAPL short-term package power limit (PL2), in watts.
+    #[serde(default)]
+    pub pl2_watts: Option<u32>,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
@@ -35,6 +42,8 @@ impl Profile {
             max_freq_mhz: None,
             epp: Some("performance".to_string()),
             epb: Some(0),
+            pl1_watts: None,
+            pl2_watts: None,
         }
     }
 
@@ -48,6 +57,8 @@ impl Profile {
             max_freq_mhz: None,
             epp: Some("balance_performance".to_string()),
             epb: Some(6),
+            pl1_watts: None,
+            pl2_watts: None,
         }
     }
 
@@ -61,6 +72,8 @@ impl Profile {
             max_freq_mhz: Some(2400),
             epp: Some("power".to_string()),
             epb: Some(15),
+            pl1_watts: None,
+            pl2_watts: None,
         }
     }
 
@@ -74,6 +87,8 @@ impl Profile {
             max_freq_mhz: Some(2000),
             epp: Some("power".to_string()),
             epb: Some(15),
+            pl1_watts: None,
+            pl2_watts: None,
         }
     }
 
@@ -155,6 +170,11 @@ impl Profile {
             }
         }
 
+        // Package power limits the profile doesn't set go back to the firmware's.
+        if let Err(e) = power_supply::apply_package_limits(self.pl1_watts, self.pl2_watts) {
+            log::warn!("Failed to set RAPL power limits: {} (may not be supported)", e);
+        }
+
         log::info!("Profile '{}' applied successfully", self.name);
         Ok(())
     }
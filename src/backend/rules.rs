@@ -0,0 +1,554 @@
+use anyhow::{bail, Result};
+use chrono::NaiveDateTime;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+use crate::backend::auto_tune::Selection;
+use crate::backend::idle::IdleMonitor;
+use crate::backend::process_watch::{scan_processes, ProcessInfo, ProcessMatch, ScanFields};
+use crate::backend::schedule::TimeWindow;
+use crate::utils::units::fmt_temp;
+
+/// Running processes are rescanned at most this often, and only while some
+/// rule has a `process` condition.
+const SCAN_INTERVAL: Duration = Duration::from_secs(2);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PowerState {
+    Ac,
+    Battery,
+}
+
+/// `[rules.when]`: every condition that is set must hold. An empty `when`
+/// always holds.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Conditions {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub power: Option<PowerState>,
+    /// Battery percentage; never holds without a battery.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub battery_below: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub battery_above: Option<f32>,
+    /// CPU temperature in °C, from the configured temperature source.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub temp_above: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub temp_below: Option<f32>,
+    /// Overall CPU load in percent.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub load_above: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub load_below: Option<f32>,
+    /// Holds while any matching process runs.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub process: Option<ProcessMatch>,
+    /// As for schedules: `daily`, `weekdays`, `mon-thu`, …
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub days: Option<String>,
+    /// Local time window `HH:MM-HH:MM`; may run past midnight.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub time: Option<String>,
+    /// Holds once the session has been idle this long (per logind).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub idle_above_seconds: Option<u64>,
+}
+
+/// `[rules.then]`. Profile, turbo and limits hold while the rule is active;
+/// `notify` and `command` run once each time it becomes active.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Actions {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub profile: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub turbo: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub min_freq_mhz: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_freq_mhz: Option<u32>,
+    /// Desktop notification body; `{temp}`, `{battery}` and `{load}` are
+    /// filled in.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub notify: Option<String>,
+    /// Run with `sh -c`, with `CPM_RULE`, `CPM_TEMP`, `CPM_BATTERY` and
+    /// `CPM_LOAD` set. The background service runs it as root.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub command: Option<String>,
+}
+
+/// `[[rules]]`: when `when` holds, do `then`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Rule {
+    /// Unique; a user rule with a built-in rule's name replaces it.
+    pub name: String,
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    /// Where active rules disagree, the highest priority wins each action
+    /// separately; ties go to the earlier rule.
+    #[serde(default)]
+    pub priority: i32,
+    /// Minimum time between two runs of `notify` and `command`; profile,
+    /// turbo and limits still follow the conditions.
+    #[serde(default)]
+    pub cooldown_seconds: u64,
+    /// Once active, numeric thresholds are relaxed by this much (percent
+    /// points or °C) so the rule doesn't flip at the boundary.
+    #[serde(default)]
+    pub hysteresis: f32,
+    #[serde(default)]
+    pub when: Conditions,
+    #[serde(default)]
+    pub then: Actions,
+}
+
+fn default_true() -> bool { true }
+
+impl Rule {
+    pub fn new(name: &str, priority: i32, when: Conditions, then: Actions) -> Self {
+        Self { name: name.to_string(), enabled: true, priority, cooldown_seconds: 0, hysteresis: 0.0, when, then }
+    }
+
+    pub fn validate(&self) -> Result<()> {
+        if self.name.trim().is_empty() {
+            bail!("Rule has no name");
+        }
+        Compiled::new(&self.when)?;
+        if let (Some(min), Some(max)) = (self.then.min_freq_mhz, self.then.max_freq_mhz) {
+            if min > max {
+                bail!("min_freq_mhz {} is above max_freq_mhz {}", min, max);
+            }
+        }
+        Ok(())
+    }
+}
+
+/// What the conditions are checked against.
+#[derive(Debug, Clone, Copy)]
+pub struct Inputs {
+    /// `None` until the power source is known.
+    pub on_ac: Option<bool>,
+    pub battery_percent: Option<f32>,
+    pub temp_celsius: Option<f32>,
+    pub load_percent: Option<f32>,
+    pub now: NaiveDateTime,
+}
+
+/// A one-shot action from a rule that just became active.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Fired {
+    pub rule: String,
+    pub notify: Option<String>,
+    pub command: Option<String>,
+    env: Vec<(&'static str, String)>,
+}
+
+impl Fired {
+    pub fn notify(&self) {
+        if let Some(body) = &self.notify {
+            show_notification(&self.rule, body);
+        }
+    }
+
+    /// Starts the command without waiting for it.
+    pub fn run_command(&self) {
+        let Some(cmd) = &self.command else { return };
+        match std::process::Command::new("sh").arg("-c").arg(cmd).envs(self.env.iter().cloned()).spawn() {
+            // Reap it in the background so it doesn't linger as a zombie.
+            Ok(mut child) => { std::thread::spawn(move || child.wait()); }
+            Err(e) => log::warn!("Rule {:?}: failed to run {:?}: {}", self.rule, cmd, e),
+        }
+    }
+}
+
+/// A rule's desktop notification, titled with the rule name.
+pub fn show_notification(rule: &str, body: &str) {
+    if let Err(e) = notify_rust::Notification::new()
+        .summary(rule)
+        .body(body)
+        .icon("dialog-warning")
+        .timeout(notify_rust::Timeout::Milliseconds(5000))
+        .show()
+    {
+        log::warn!("Rule {:?}: notification failed: {}", rule, e);
+    }
+}
+
+/// An active rule and why its conditions hold.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RuleMatch {
+    pub name: String,
+    pub priority: i32,
+    pub why: String,
+}
+
+#[derive(Debug, Default)]
+pub struct Evaluation {
+    /// Profile, turbo and limits to apply; `None` when no active rule sets any.
+    pub target: Option<Selection>,
+    pub fired: Vec<Fired>,
+    /// Highest priority first.
+    pub active: Vec<RuleMatch>,
+}
+
+#[derive(Debug, Default)]
+struct RuleState {
+    active: bool,
+    last_activated: Option<Instant>,
+}
+
+/// Parsed conditions, kept until the rule's `when` changes.
+#[derive(Debug)]
+struct Compiled {
+    cmdline: Option<Regex>,
+    window: Option<TimeWindow>,
+}
+
+impl Compiled {
+    fn new(when: &Conditions) -> Result<Self> {
+        let window = match (&when.days, &when.time) {
+            (None, None) => None,
+            (days, time) => Some(TimeWindow::parse(days.as_deref(), time.as_deref())?),
+        };
+        let cmdline = match &when.process {
+            Some(p) => p.compile()?,
+            None => None,
+        };
+        Ok(Self { cmdline, window })
+    }
+}
+
+/// Evaluates `[[rules]]` each tick, remembering which rules are active for
+/// hysteresis, cooldowns and one-shot actions.
+#[derive(Default)]
+pub struct RuleEngine {
+    states: HashMap<String, RuleState>,
+    /// Keyed by rule name, with the conditions they were built from. `None`
+    /// for a rule whose conditions don't parse; it never matches.
+    compiled: HashMap<String, (Conditions, Option<Compiled>)>,
+    processes: Vec<ProcessInfo>,
+    scanned: Option<(Instant, ScanFields)>,
+    idle: IdleMonitor,
+}
+
+impl RuleEngine {
+    pub fn new() -> Self { Self::default() }
+
+    pub fn evaluate(&mut self, rules: &[Rule], inputs: &Inputs) -> Evaluation {
+        let rules: Vec<&Rule> = rules.iter().filter(|r| r.enabled).collect();
+        self.states.retain(|name, _| rules.iter().any(|r| &r.name == name));
+        self.compiled.retain(|name, _| rules.iter().any(|r| &r.name == name));
+        self.refresh_processes(&rules);
+
+        let mut eval = Evaluation::default();
+        let mut ended = Vec::new();
+        let mut active = Vec::new();
+        for rule in rules {
+            let was_active = self.states.get(&rule.name).is_some_and(|s| s.active);
+            let why = self.check(rule, inputs, was_active);
+            let state = self.states.entry(rule.name.clone()).or_default();
+            let now_active = why.is_some();
+            if now_active && !was_active {
+                let cooling = state.last_activated
+                    .is_some_and(|t| t.elapsed() < Duration::from_secs(rule.cooldown_seconds));
+                if !cooling {
+                    state.last_activated = Some(Instant::now());
+                    if rule.then.notify.is_some() || rule.then.command.is_some() {
+                        eval.fired.push(fired(rule, inputs));
+                    }
+                }
+            }
+            if was_active && !now_active {
+                ended.push(rule.name.clone());
+            }
+            state.active = now_active;
+            if let (true, Some(why)) = (now_active, why) {
+                active.push((rule, why));
+            }
+        }
+        // Stable, so equal priorities keep config order.
+        active.sort_by_key(|(r, _)| std::cmp::Reverse(r.priority));
+
+        let pick = |f: &dyn Fn(&Actions) -> bool| active.iter().find(|(r, _)| f(&r.then));
+        let profile = pick(&|a| a.profile.is_some());
+        let turbo = pick(&|a| a.turbo.is_some());
+        let min = pick(&|a| a.min_freq_mhz.is_some());
+        let max = pick(&|a| a.max_freq_mhz.is_some());
+        if let Some(&(lead, ref why)) = profile.or(turbo).or(min).or(max) {
+            let mut reason = format!("{} (rule {:?})", why, lead.name);
+            let mut others: Vec<&str> = Vec::new();
+            for (r, _) in [turbo, min, max].into_iter().flatten() {
+                if r.name != lead.name && !others.contains(&r.name.as_str()) {
+                    others.push(&r.name);
+                }
+            }
+            if !others.is_empty() {
+                reason.push_str(&format!("; turbo/limits from {}", others.join(", ")));
+            }
+            if !ended.is_empty() {
+                reason.push_str(&format!(" — {} ended", ended.join(", ")));
+            }
+            eval.target = Some(Selection {
+                profile: profile.and_then(|(r, _)| r.then.profile.clone()),
+                turbo: turbo.and_then(|(r, _)| r.then.turbo),
+                min_freq_mhz: min.and_then(|(r, _)| r.then.min_freq_mhz),
+                max_freq_mhz: max.and_then(|(r, _)| r.then.max_freq_mhz),
+                reason,
+            });
+        }
+        eval.active = active.into_iter()
+            .map(|(r, why)| RuleMatch { name: r.name.clone(), priority: r.priority, why })
+            .collect();
+        eval
+    }
+
+    fn refresh_processes(&mut self, rules: &[&Rule]) {
+        let mut fields = ScanFields::default();
+        let mut wanted = false;
+        for m in rules.iter().filter_map(|r| r.when.process.as_ref()) {
+            fields.add(m);
+            wanted = true;
+        }
+        if !wanted {
+            self.processes.clear();
+            self.scanned = None;
+            return;
+        }
+        if self.scanned.is_some_and(|(at, f)| f == fields && at.elapsed() < SCAN_INTERVAL) {
+            return;
+        }
+        self.scanned = Some((Instant::now(), fields));
+        match scan_processes(fields) {
+            Ok(list) => self.processes = list,
+            Err(e) => log::warn!("Process scan failed: {}", e),
+        }
+    }
+
+    /// Why the rule's conditions hold, or `None` if they don't.
+    fn check(&mut self, rule: &Rule, inputs: &Inputs, active: bool) -> Option<String> {
+        let when = &rule.when;
+        let stale = self.compiled.get(&rule.name).is_none_or(|(source, _)| source != when);
+        if stale {
+            let compiled = Compiled::new(when)
+                .map_err(|e| log::warn!("Rule {:?} disabled: {:#}", rule.name, e))
+                .ok();
+            self.compiled.insert(rule.name.clone(), (when.clone(), compiled));
+        }
+        let compiled = self.compiled.get(&rule.name).and_then(|(_, c)| c.as_ref())?;
+        let h = if active { rule.hysteresis.max(0.0) } else { 0.0 };
+        let mut why = Vec::new();
+
+        if let Some(power) = when.power {
+            let on_ac = inputs.on_ac?;
+            if on_ac != (power == PowerState::Ac) {
+                return None;
+            }
+            why.push(if on_ac { "on AC power".to_string() } else { "on battery".to_string() });
+        }
+        if when.battery_below.is_some() || when.battery_above.is_some() {
+            let pct = inputs.battery_percent?;
+            // "on battery at 25% (below 30%)" rather than two phrases.
+            let battery_why = match when.power {
+                Some(PowerState::Battery) => { why.pop(); "on battery" }
+                _ => "battery",
+            };
+            threshold(pct, when.battery_below, when.battery_above, h)?;
+            why.push(match (when.battery_below, when.battery_above) {
+                (Some(below), _) => format!("{} at {:.0}% (below {:.0}%)", battery_why, pct, below),
+                (None, Some(above)) => format!("{} at {:.0}% (above {:.0}%)", battery_why, pct, above),
+                (None, None) => unreachable!(),
+            });
+        }
+        if when.temp_above.is_some() || when.temp_below.is_some() {
+            let temp = inputs.temp_celsius?;
+            threshold(temp, when.temp_below, when.temp_above, h)?;
+            why.push(match (when.temp_above, when.temp_below) {
+                (Some(above), _) => format!("CPU at {} (above {})", fmt_temp(temp, 0), fmt_temp(above, 0)),
+                (None, Some(below)) => format!("CPU at {} (below {})", fmt_temp(temp, 0), fmt_temp(below, 0)),
+                (None, None) => unreachable!(),
+            });
+        }
+        if when.load_above.is_some() || when.load_below.is_some() {
+            let load = inputs.load_percent?;
+            threshold(load, when.load_below, when.load_above, h)?;
+            why.push(match (when.load_above, when.load_below) {
+                (Some(above), _) => format!("load {:.0}% (above {:.0}%)", load, above),
+                (None, Some(below)) => format!("load {:.0}% (below {:.0}%)", load, below),
+                (None, None) => unreachable!(),
+            });
+        }
+        if let Some(window) = &compiled.window {
+            if !window.contains(inputs.now) {
+                return None;
+            }
+            why.push(match (&when.days, &when.time) {
+                (Some(days), Some(time)) => format!("{} {}", days, time),
+                (Some(days), None) => days.clone(),
+                (None, Some(time)) => format!("between {}", time),
+                (None, None) => unreachable!(),
+            });
+        }
+        if let Some(m) = &when.process {
+            let p = self.processes.iter().find(|p| m.matches(p, compiled.cmdline.as_ref()))?;
+            why.push(format!("{} running", p.describe()));
+        }
+        if let Some(secs) = when.idle_above_seconds {
+            let idle = self.idle.idle_for()?;
+            if idle < Duration::from_secs(secs) {
+                return None;
+            }
+            why.push(format!("idle for {} min", idle.as_secs() / 60));
+        }
+        Some(if why.is_empty() { "always".to_string() } else { why.join(", ") })
+    }
+}
+
+/// `Some(())` if `value` is below `below` and above `above` (each optional),
+/// with both bounds widened by `h`.
+fn threshold(value: f32, below: Option<f32>, above: Option<f32>, h: f32) -> Option<()> {
+    let ok = below.is_none_or(|b| value < b + h) && above.is_none_or(|a| value > a - h);
+    ok.then_some(())
+}
+
+fn fired(rule: &Rule, inputs: &Inputs) -> Fired {
+    let fmt = |v: Option<f32>| v.map(|v| format!("{:.0}", v)).unwrap_or_default();
+    let notify = rule.then.notify.as_ref().map(|body| {
+        body.replace("{temp}", &inputs.temp_celsius.map(|t| fmt_temp(t, 1)).unwrap_or_else(|| "?".into()))
+            .replace("{battery}", &inputs.battery_percent.map(|b| format!("{:.0}%", b)).unwrap_or_else(|| "?".into()))
+            .replace("{load}", &inputs.load_percent.map(|l| format!("{:.0}%", l)).unwrap_or_else(|| "?".into()))
+    });
+    Fired {
+        rule: rule.name.clone(),
+        notify,
+        command: rule.then.command.clone(),
+        env: vec![
+            ("CPM_RULE", rule.name.clone()),
+            ("CPM_TEMP", fmt(inputs.temp_celsius)),
+            ("CPM_BATTERY", fmt(inputs.battery_percent)),
+            ("CPM_LOAD", fmt(inputs.load_percent)),
+        ],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+
+    fn inputs(on_ac: bool, battery: f32) -> Inputs {
+        Inputs {
+            on_ac: Some(on_ac),
+            battery_percent: Some(battery),
+            temp_celsius: Some(50.0),
+            load_percent: Some(10.0),
+            // 2024-01-03 was a Wednesday.
+            now: NaiveDate::from_ymd_opt(2024, 1, 3).unwrap().and_hms_opt(10, 30, 0).unwrap(),
+        }
+    }
+
+    fn profile_rule(name: &str, priority: i32, when: Conditions, profile: &str) -> Rule {
+        Rule::new(name, priority, when, Actions { profile: Some(profile.into()), ..Default::default() })
+    }
+
+    #[test]
+    fn test_battery_hysteresis_and_priority() {
+        let battery = |below| Conditions { power: Some(PowerState::Battery), battery_below: Some(below), ..Default::default() };
+        let rules = vec![
+            profile_rule("AC", 0, Conditions { power: Some(PowerState::Ac), ..Default::default() }, "Performance"),
+            profile_rule("Battery", 0, Conditions { power: Some(PowerState::Battery), ..Default::default() }, "Balanced"),
+            Rule {
+                hysteresis: 3.0,
+                then: Actions { profile: Some("Silent".into()), turbo: Some(false), ..Default::default() },
+                ..Rule::new("Critical", 2002, battery(10.0), Actions::default())
+            },
+            Rule { hysteresis: 3.0, ..profile_rule("Low", 2001, battery(30.0), "Power Saver") },
+        ];
+        let mut engine = RuleEngine::new();
+        let mut pick = |on_ac, pct| engine.evaluate(&rules, &inputs(on_ac, pct)).target.unwrap();
+
+        assert_eq!(pick(false, 50.0).profile.unwrap(), "Balanced");
+        assert_eq!(pick(false, 29.0).profile.unwrap(), "Power Saver");
+        // Within the 3-point band above 30%: holds.
+        assert_eq!(pick(false, 31.0).profile.unwrap(), "Power Saver");
+        assert_eq!(pick(false, 33.5).profile.unwrap(), "Balanced");
+        // Not active any more, so the band no longer applies.
+        assert_eq!(pick(false, 31.0).profile.unwrap(), "Balanced");
+        let critical = pick(false, 9.0);
+        assert_eq!((critical.profile.as_deref(), critical.turbo), (Some("Silent"), Some(false)));
+        assert_eq!(critical.reason, r#"on battery at 9% (below 10%) (rule "Critical")"#);
+        assert_eq!(pick(false, 11.0).profile.unwrap(), "Silent");
+        let low = pick(false, 14.0);
+        assert_eq!((low.profile.as_deref(), low.turbo), (Some("Power Saver"), None));
+        assert!(low.reason.ends_with("— Critical ended"));
+        assert_eq!(pick(true, 14.0).profile.unwrap(), "Performance");
+    }
+
+    #[test]
+    fn test_schedule_priority_and_cooldown() {
+        let window = |days: &str, time: &str| Conditions { days: Some(days.into()), time: Some(time.into()), ..Default::default() };
+        let hot = Rule {
+            cooldown_seconds: 60,
+            then: Actions { notify: Some("CPU is at {temp}".into()), turbo: Some(false), ..Default::default() },
+            ..Rule::new("Hot", 0, Conditions { temp_above: Some(90.0), ..Default::default() }, Actions::default())
+        };
+        let rules = vec![
+            profile_rule("Work", 1000, window("weekdays", "09:00-18:00"), "Balanced"),
+            profile_rule("Meeting", 1005, window("wed", "10:00-11:00"), "Silent"),
+            profile_rule("Broken", 5000, window("someday", "10:00-11:00"), "Performance"),
+            hot,
+        ];
+        let mut engine = RuleEngine::new();
+        let mut now = inputs(true, 80.0);
+        let eval = engine.evaluate(&rules, &now);
+        assert_eq!(eval.target.unwrap().profile.unwrap(), "Silent");
+        assert_eq!(eval.active.iter().map(|m| m.name.as_str()).collect::<Vec<_>>(), ["Meeting", "Work"]);
+        assert!(eval.fired.is_empty());
+
+        now.temp_celsius = Some(95.0);
+        let eval = engine.evaluate(&rules, &now);
+        assert_eq!(eval.fired.len(), 1);
+        assert_eq!(eval.target.as_ref().unwrap().turbo, Some(false));
+        assert!(eval.target.unwrap().reason.contains("turbo/limits from Hot"));
+        // Still hot: no repeat.
+        assert!(engine.evaluate(&rules, &now).fired.is_empty());
+        // Cools down and heats up again within the cooldown: stays quiet,
+        // but turbo still goes off.
+        now.temp_celsius = Some(60.0);
+        assert_eq!(engine.evaluate(&rules, &now).target.unwrap().turbo, None);
+        now.temp_celsius = Some(95.0);
+        let eval = engine.evaluate(&rules, &now);
+        assert!(eval.fired.is_empty());
+        assert_eq!(eval.target.unwrap().turbo, Some(false));
+    }
+
+    #[test]
+    fn test_process_rule_priority() {
+        let process = |m: ProcessMatch| Conditions { process: Some(m), ..Default::default() };
+        let rules = vec![
+            profile_rule("Build", 3000, process(ProcessMatch { exe: Some("cargo".into()), ..Default::default() }), "Performance"),
+            profile_rule("Encode", 3005, process(ProcessMatch { cmdline: Some("^ffmpeg ".into()), ..Default::default() }), "Balanced"),
+            profile_rule("Game", 3010, process(ProcessMatch { path: Some("/opt/games/".into()), ..Default::default() }), "Gaming"),
+        ];
+        let proc = |pid, comm: &str, exe: &str, cmdline: &str| ProcessInfo {
+            pid, comm: comm.into(), exe: Some(exe.into()), cmdline: cmdline.into(), cgroup: String::new(),
+        };
+        let cargo = proc(10, "cargo", "/usr/bin/cargo", "cargo build");
+        let ffmpeg = proc(20, "ffmpeg", "/usr/bin/ffmpeg", "ffmpeg -i a.mkv b.mkv");
+        let game = proc(30, "game", "/opt/games/foo/game", "./game");
+        let bash = proc(40, "bash", "/usr/bin/bash", "bash");
+
+        let mut engine = RuleEngine::new();
+        let mut pick = |running: Vec<ProcessInfo>| {
+            engine.scanned = Some((Instant::now(), ScanFields { exe: true, cmdline: true, cgroup: false }));
+            engine.processes = running;
+            engine.evaluate(&rules, &inputs(true, 80.0)).target.and_then(|t| t.profile)
+        };
+        assert_eq!(pick(vec![cargo.clone()]).unwrap(), "Performance");
+        assert_eq!(pick(vec![cargo.clone(), ffmpeg.clone()]).unwrap(), "Balanced");
+        assert_eq!(pick(vec![cargo, ffmpeg, game]).unwrap(), "Gaming");
+        assert_eq!(pick(vec![bash]), None);
+    }
+}
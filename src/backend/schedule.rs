@@ -0,0 +1,178 @@
+use anyhow::{bail, Context, Result};
+use chrono::{Datelike, NaiveDateTime, NaiveTime, Weekday};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+/// `[[auto_tune.schedule]]`: use `profile` during a weekly time window.
+/// Times are local wall-clock times, so a window keeps its clock hours
+/// across DST changes (and a window inside a skipped hour never fires).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ScheduleRule {
+    pub name: String,
+    pub profile: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub turbo: Option<bool>,
+    /// Days the window starts on: `daily`, `weekdays`, `weekends`, day
+    /// names (`mon`, `tue`, …) and ranges (`mon-thu`), comma-separated.
+    #[serde(default = "default_days")]
+    pub days: String,
+    /// `HH:MM`. An `end` at or before `start` runs past midnight.
+    pub start: String,
+    pub end: String,
+    /// Higher wins where windows overlap; ties go to the earlier rule.
+    #[serde(default)]
+    pub priority: i32,
+}
+
+fn default_days() -> String { "daily".into() }
+
+impl ScheduleRule {
+    pub fn validate(&self) -> Result<()> {
+        TimeWindow::new(&self.days, &self.start, &self.end).map(|_| ())
+    }
+
+    /// The `time` condition form, `HH:MM-HH:MM`.
+    pub fn time_range(&self) -> String {
+        format!("{}-{}", self.start.trim(), self.end.trim())
+    }
+}
+
+/// A weekly window in local time; bit 0 of `days` is Monday.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TimeWindow {
+    days: u8,
+    start: NaiveTime,
+    end: NaiveTime,
+}
+
+impl TimeWindow {
+    pub fn new(days: &str, start: &str, end: &str) -> Result<Self> {
+        Ok(Self {
+            days: parse_days(days)?,
+            start: parse_time(start).context("Invalid start time")?,
+            end: parse_time(end).context("Invalid end time")?,
+        })
+    }
+
+    /// `days` as for `ScheduleRule` (default every day); `range` is
+    /// `HH:MM-HH:MM` (default the whole day).
+    pub fn parse(days: Option<&str>, range: Option<&str>) -> Result<Self> {
+        let (start, end) = match range {
+            Some(r) => r.split_once('-').with_context(|| format!("{:?} is not HH:MM-HH:MM", r))?,
+            None => ("00:00", "00:00"),
+        };
+        Self::new(days.unwrap_or("daily"), start, end)
+    }
+
+    fn on(&self, day: Weekday) -> bool {
+        self.days & (1 << day.num_days_from_monday()) != 0
+    }
+
+    pub fn contains(&self, now: NaiveDateTime) -> bool {
+        let (day, t) = (now.weekday(), now.time());
+        if self.start < self.end {
+            self.on(day) && t >= self.start && t < self.end
+        } else {
+            // Overnight (or a full 24 h when start == end): the tail after
+            // midnight belongs to the previous day's window.
+            (self.on(day) && t >= self.start) || (self.on(day.pred()) && t < self.end)
+        }
+    }
+}
+
+fn parse_time(s: &str) -> Result<NaiveTime> {
+    let s = s.trim();
+    if s == "24:00" {
+        return Ok(NaiveTime::MIN);
+    }
+    NaiveTime::parse_from_str(s, "%H:%M").with_context(|| format!("{:?} is not HH:MM", s))
+}
+
+fn parse_day(s: &str) -> Result<u32> {
+    let day: Weekday = s.parse().map_err(|_| anyhow::anyhow!("Unknown day {:?}", s))?;
+    Ok(day.num_days_from_monday())
+}
+
+fn parse_days(s: &str) -> Result<u8> {
+    let mut mask = 0u8;
+    for part in s.split(',').map(|p| p.trim().to_lowercase()).filter(|p| !p.is_empty()) {
+        mask |= match part.as_str() {
+            "daily" | "every day" => 0b111_1111,
+            "weekdays" => 0b001_1111,
+            "weekends" => 0b110_0000,
+            range => match range.split_once('-') {
+                Some((from, to)) => {
+                    let (from, to) = (parse_day(from.trim())?, parse_day(to.trim())?);
+                    // Ranges may wrap, e.g. fri-mon.
+                    (0..=(to + 7 - from) % 7).fold(0, |m, i| m | 1 << ((from + i) % 7))
+                }
+                None => 1 << parse_day(range)?,
+            },
+        };
+    }
+    if mask == 0 {
+        bail!("No days given");
+    }
+    Ok(mask)
+}
+
+/// Notices a resume from suspend: CLOCK_BOOTTIME keeps counting while the
+/// machine sleeps, CLOCK_MONOTONIC doesn't.
+#[derive(Debug, Default)]
+pub struct ResumeDetector {
+    slept: Option<Duration>,
+}
+
+impl ResumeDetector {
+    /// True once after each suspend/resume cycle.
+    pub fn check(&mut self) -> bool {
+        let slept = clock(libc::CLOCK_BOOTTIME).saturating_sub(clock(libc::CLOCK_MONOTONIC));
+        let resumed = self.slept.is_some_and(|prev| slept > prev + Duration::from_secs(1));
+        self.slept = Some(slept);
+        resumed
+    }
+}
+
+pub(crate) fn clock(id: libc::clockid_t) -> Duration {
+    let mut ts = libc::timespec { tv_sec: 0, tv_nsec: 0 };
+    // SAFETY: ts is a valid timespec to write into.
+    unsafe { libc::clock_gettime(id, &mut ts) };
+    Duration::new(ts.tv_sec as u64, ts.tv_nsec as u32)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+
+    fn at(day: u32, hh: u32, mm: u32) -> NaiveDateTime {
+        // 2024-01-01 was a Monday.
+        NaiveDate::from_ymd_opt(2024, 1, day).unwrap().and_hms_opt(hh, mm, 0).unwrap()
+    }
+
+    #[test]
+    fn test_day_parsing() {
+        assert_eq!(parse_days("weekdays").unwrap(), 0b001_1111);
+        assert_eq!(parse_days("Sat, sun").unwrap(), 0b110_0000);
+        assert_eq!(parse_days("fri-mon").unwrap(), 0b111_0001);
+        assert!(parse_days("someday").is_err());
+        assert!(parse_time("25:00").is_err());
+    }
+
+    #[test]
+    fn test_time_windows() {
+        let work = TimeWindow::new("weekdays", "09:00", "18:00").unwrap();
+        let night = TimeWindow::new("mon-fri", "22:00", "07:00").unwrap();
+        assert!(work.contains(at(1, 9, 0)));
+        assert!(!work.contains(at(1, 18, 0)));
+        assert!(!work.contains(at(6, 10, 0)));
+        // Friday night's window runs into Saturday morning.
+        assert!(night.contains(at(6, 6, 59)));
+        assert!(!night.contains(at(6, 9, 30)));
+        assert!(!night.contains(at(7, 6, 59)));
+        assert!(!night.contains(at(8, 6, 59)));
+        assert_eq!(TimeWindow::parse(Some("weekdays"), Some("09:00-18:00")).unwrap(), work);
+        assert!(TimeWindow::parse(None, None).unwrap().contains(at(7, 3, 0)));
+        assert!(TimeWindow::parse(None, Some("09:00")).is_err());
+    }
+}
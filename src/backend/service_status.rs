@@ -0,0 +1,167 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+use crate::backend::auto_tune::Selection;
+use crate::backend::emergency::EmergencyEvent;
+use crate::backend::rules::{show_notification, Fired, RuleMatch};
+
+/// Written by the background service each time its rule state changes, so
+/// the GUI can show what the service applies instead of evaluating its own
+/// copy of the rules.
+pub const STATUS_FILE: &str = "/run/cpu-power-manager/status.toml";
+
+/// Notifications kept for the GUI to pick up.
+const NOTICES: usize = 10;
+
+/// A rule or emergency notification the service can't show itself.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Notice {
+    /// Increases by one per notice within a service run.
+    pub seq: u64,
+    pub rule: String,
+    pub body: String,
+}
+
+impl Notice {
+    pub fn show(&self) {
+        show_notification(&self.rule, &self.body);
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ActiveRule {
+    pub name: String,
+    pub why: String,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ServiceStatus {
+    /// Unix time the service started; a new value restarts `seq`.
+    pub started: i64,
+    /// Profile of the last applied selection, where it named one.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub profile: Option<String>,
+    /// `Selection::summary()` of what was last applied.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub applied: Option<String>,
+    #[serde(default)]
+    pub reason: String,
+    /// Highest priority first.
+    #[serde(default)]
+    pub active: Vec<ActiveRule>,
+    /// Oldest first.
+    #[serde(default)]
+    pub notices: Vec<Notice>,
+}
+
+impl ServiceStatus {
+    pub fn new(started: i64) -> Self {
+        Self { started, ..Default::default() }
+    }
+
+    /// `None` when the service hasn't written one or it is unreadable.
+    /// A file left behind by a crashed service is still returned; check
+    /// that the service is running first.
+    pub fn load() -> Option<Self> {
+        let text = fs::read_to_string(STATUS_FILE).ok()?;
+        toml::from_str(&text)
+            .map_err(|e| log::debug!("Ignoring invalid {}: {}", STATUS_FILE, e))
+            .ok()
+    }
+
+    pub fn save(&self) -> Result<()> {
+        let path = Path::new(STATUS_FILE);
+        let tmp = path.with_extension("toml.tmp");
+        fs::write(&tmp, toml::to_string(self).context("Failed to serialize service status")?)
+            .with_context(|| format!("Failed to write {}", tmp.display()))?;
+        fs::rename(&tmp, path).with_context(|| format!("Failed to replace {}", path.display()))
+    }
+
+    pub fn remove() {
+        let _ = fs::remove_file(STATUS_FILE);
+    }
+
+    /// Records the last applied selection; `None` once no rule sets anything.
+    pub fn set_applied(&mut self, selection: Option<&Selection>) {
+        self.profile = selection.and_then(|s| s.profile.clone());
+        self.applied = selection.map(Selection::summary);
+        self.reason = selection.map(|s| s.reason.clone()).unwrap_or_default();
+    }
+
+    pub fn set_active(&mut self, active: &[RuleMatch]) {
+        self.active = active.iter().map(|m| ActiveRule { name: m.name.clone(), why: m.why.clone() }).collect();
+    }
+
+    /// Queues the notification bodies of rules that just fired.
+    pub fn push_notices(&mut self, fired: &[Fired]) {
+        for f in fired {
+            if let Some(body) = &f.notify {
+                self.push_notice(&f.rule, body);
+            }
+        }
+    }
+
+    /// Queues the notification for an emergency clamp or its release.
+    pub fn push_emergency(&mut self, event: &EmergencyEvent) {
+        let (summary, body) = event.message();
+        self.push_notice(summary, &body);
+    }
+
+    fn push_notice(&mut self, rule: &str, body: &str) {
+        let seq = self.notices.last().map_or(1, |n| n.seq + 1);
+        self.notices.push(Notice { seq, rule: rule.to_string(), body: body.to_string() });
+        let excess = self.notices.len().saturating_sub(NOTICES);
+        self.notices.drain(..excess);
+    }
+
+    /// Notices newer than `seen` (a `(started, seq)` the caller keeps),
+    /// advancing it. The first call only catches up, so a GUI started after
+    /// the service doesn't replay old notifications.
+    pub fn new_notices(&self, seen: &mut Option<(i64, u64)>) -> Vec<Notice> {
+        let last = self.notices.last().map_or(0, |n| n.seq);
+        let fresh = match *seen {
+            Some((started, seq)) if started == self.started => {
+                self.notices.iter().filter(|n| n.seq > seq).cloned().collect()
+            }
+            // The service restarted: everything it queued since is new.
+            Some(_) => self.notices.clone(),
+            None => Vec::new(),
+        };
+        *seen = Some((self.started, last));
+        fresh
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_notices() {
+        let mut status = ServiceStatus::new(100);
+        let mut seen = None;
+        status.push_notice("Hot", "CPU at 95°C");
+        assert!(status.new_notices(&mut seen).is_empty());
+
+        status.push_notice("Low", "Battery at 9%");
+        let fresh = status.new_notices(&mut seen);
+        assert_eq!(fresh.iter().map(|n| n.rule.as_str()).collect::<Vec<_>>(), ["Low"]);
+        assert!(status.new_notices(&mut seen).is_empty());
+
+        for i in 0..15 {
+            status.push_notice(&format!("R{}", i), "x");
+        }
+        assert_eq!(status.notices.len(), NOTICES);
+        assert_eq!(status.new_notices(&mut seen).len(), NOTICES);
+
+        status.push_emergency(&EmergencyEvent::Triggered { temp_celsius: 96.0 });
+        let fresh = status.new_notices(&mut seen);
+        assert_eq!(fresh.len(), 1);
+        assert_eq!(fresh[0].rule, "CPU Thermal Emergency");
+
+        let mut restarted = ServiceStatus::new(200);
+        restarted.push_notice("Hot", "again");
+        assert_eq!(restarted.new_notices(&mut seen).len(), 1);
+    }
+}
@@ -0,0 +1,74 @@
+use serde::{Deserialize, Serialize};
+use crate::backend::cpu::CpuManager;
+use crate::backend::power_supply::RaplZone;
+
+/// The CPU settings profiles and overrides change, captured so they can
+/// be put back afterwards.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct CpuSnapshot {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub governor: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub epp: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub turbo: Option<bool>,
+    /// (core, scaling_min MHz, scaling_max MHz)
+    #[serde(default)]
+    pub limits: Vec<(usize, u32, u32)>,
+    /// (zone id, constraint id, watts) for the RAPL package zones.
+    #[serde(default)]
+    pub power_limits: Vec<(String, usize, f32)>,
+}
+
+impl CpuSnapshot {
+    pub fn capture(cpu: &CpuManager) -> Self {
+        let limits = (0..cpu.core_count())
+            .filter_map(|core| Some((core, cpu.get_scaling_min_freq(core).ok()?, cpu.get_scaling_max_freq(core).ok()?)))
+            .collect();
+        let power_limits = RaplZone::packages().iter()
+            .flat_map(|z| z.constraints.iter().map(|c| (z.id.clone(), c.id, c.power_limit_w())))
+            .collect();
+        Self {
+            governor: cpu.get_governor(0).ok(),
+            epp: cpu.get_epp(0).ok(),
+            turbo: cpu.is_turbo_enabled().ok(),
+            limits,
+            power_limits,
+        }
+    }
+
+    /// Best effort: every setting is attempted, failures are logged.
+    pub fn restore(&self, cpu: &CpuManager) {
+        let warn = |what: &str, r: anyhow::Result<()>| {
+            if let Err(e) = r {
+                log::warn!("Failed to restore {}: {:#}", what, e);
+            }
+        };
+        if let Some(governor) = &self.governor {
+            warn("governor", cpu.set_governor_all(governor));
+        }
+        for &(core, min, max) in &self.limits {
+            // Lower min first so the old max is always accepted.
+            if let Ok(hw_min) = cpu.get_hardware_min_freq(core) {
+                let _ = cpu.set_scaling_min_freq(core, hw_min);
+            }
+            warn("max frequency", cpu.set_scaling_max_freq(core, max));
+            warn("min frequency", cpu.set_scaling_min_freq(core, min));
+        }
+        if let Some(epp) = &self.epp {
+            warn("EPP", cpu.set_epp(epp));
+        }
+        if let Some(turbo) = self.turbo {
+            warn("turbo", cpu.set_turbo(turbo));
+        }
+        for zone in RaplZone::packages() {
+            let limits: Vec<(usize, f32)> = self.power_limits.iter()
+                .filter(|(id, c, w)| *id == zone.id && zone.constraints.iter().any(|k| k.id == *c && k.power_limit_w() != *w))
+                .map(|&(_, c, w)| (c, w))
+                .collect();
+            if !limits.is_empty() {
+                warn("package power limits", zone.set_limits(&limits));
+            }
+        }
+    }
+}
@@ -1,16 +1,32 @@
 use anyhow::{Context, Result};
 use std::fs;
+use std::os::unix::fs::PermissionsExt;
 use std::path::PathBuf;
+use std::sync::Mutex;
 use serde::{Deserialize, Serialize};
+use crate::backend::hwmon::{HwmonReader, SensorKind};
 
 const THERMAL_BASE: &str = "/sys/class/thermal";
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ThermalZone {
+    /// Index into this manager's zone list.
     pub id: usize,
+    /// Sysfs directory name, e.g. `thermal_zone3`; its number can differ
+    /// from `id` when the kernel leaves gaps.
+    #[serde(default)]
+    pub name: String,
     pub type_name: String,
     pub temp_celsius: f32,
     pub trip_points: Vec<TripPoint>,
+    #[serde(default)]
+    pub bindings: Vec<CoolingBinding>,
+    /// `enabled` / `disabled`; absent on some platform drivers.
+    #[serde(default)]
+    pub mode: Option<String>,
+    /// Thermal governor, e.g. `step_wise` or `user_space`.
+    #[serde(default)]
+    pub policy: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -18,17 +34,176 @@ pub struct TripPoint {
     pub id: usize,
     pub temp_celsius: f32,
     pub trip_type: String,
+    #[serde(default)]
+    pub hysteresis_celsius: Option<f32>,
+    /// The driver exposes `trip_point_N_temp` as writable.
+    #[serde(default)]
+    pub writable: bool,
+}
+
+impl TripPoint {
+    /// Degrees left before this trip fires; negative once crossed.
+    pub fn margin(&self, temp_celsius: f32) -> f32 {
+        self.temp_celsius - temp_celsius
+    }
+}
+
+/// A temperature at which the CPU starts being slowed down, from a CPU
+/// thermal zone trip or a CPU hwmon channel's max/crit.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ThrottleThreshold {
+    /// e.g. `x86_pkg_temp passive trip` or `coretemp Package id 0 crit`.
+    pub name: String,
+    pub temp_celsius: f32,
+}
+
+/// A `cdevN` link from a thermal zone to a cooling device.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CoolingBinding {
+    pub cooling_device: usize,
+    /// Trip that activates the device; `None` when bound without a trip.
+    pub trip_point: Option<usize>,
+    pub weight: Option<u32>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CoolingKind {
+    /// ACPI processor throttling (P-states, then T-states).
+    Processor,
+    /// Idle injection; state is the forced idle percentage.
+    PowerClamp,
+    Fan,
+    /// Intel TCC offset; state is degrees below TjMax.
+    TccOffset,
+    /// cpufreq cooling (ARM, some x86 DT platforms).
+    CpuFreq,
+    Other,
+}
+
+impl CoolingKind {
+    fn from_type(type_name: &str) -> Self {
+        let t = type_name.to_lowercase();
+        match t.as_str() {
+            "processor" => Self::Processor,
+            "intel_powerclamp" => Self::PowerClamp,
+            "tcc offset" | "tcc_offset" => Self::TccOffset,
+            _ if t.contains("fan") => Self::Fan,
+            _ if t.contains("cpufreq") => Self::CpuFreq,
+            _ => Self::Other,
+        }
+    }
+
+    /// What one unit of `cur_state` means for this kind.
+    pub fn state_unit(&self) -> &'static str {
+        match self {
+            Self::Processor  => "throttle level",
+            Self::PowerClamp => "% idle",
+            Self::Fan        => "speed level",
+            Self::TccOffset  => "°C below TjMax",
+            Self::CpuFreq    => "frequency step",
+            Self::Other      => "state",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CoolingDevice {
+    pub id: usize,
+    pub type_name: String,
+    pub kind: CoolingKind,
+    pub cur_state: u64,
+    pub max_state: u64,
+}
+
+/// Where `get_cpu_temperature` reads from. Sensor ids are `zone:<type>`,
+/// `hwmon:<device>/<label>` or `amd:die`. Where two sensors share an id both
+/// are qualified with where they live, `zone:<type>@thermal_zoneN` or
+/// `hwmon:<device>@<parent>/<label>`, which survives reboots and rescans.
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+#[serde(tag = "mode", rename_all = "snake_case")]
+pub enum TempSource {
+    /// Highest-ranked CPU sensor from `temp_candidates()`.
+    #[default]
+    Auto,
+    Single { sensor: String },
+    Max { sensors: Vec<String> },
+    Average { sensors: Vec<String> },
+}
+
+/// A temperature sensor that could serve as the CPU temperature.
+#[derive(Debug, Clone)]
+pub struct TempCandidate {
+    pub id: String,
+    pub label: String,
+    pub temp_celsius: Option<f32>,
+    /// Higher is a better CPU temperature source; ≤ 0 is never auto-picked.
+    pub score: i32,
+    pub reason: &'static str,
+    /// File the reading comes from; `None` for the computed `amd:die`.
+    pub path: Option<PathBuf>,
+}
+
+/// A resolved source sensor: a millidegree file, or the AMD die estimate.
+#[derive(Debug, Clone)]
+enum Reading {
+    File(PathBuf),
+    AmdDie,
+}
+
+impl Reading {
+    fn read(&self) -> Result<f32> {
+        match self {
+            Self::File(path) => {
+                let milli: i32 = fs::read_to_string(path)
+                    .with_context(|| format!("Failed to read {}", path.display()))?
+                    .trim()
+                    .parse()
+                    .with_context(|| format!("Failed to parse {}", path.display()))?;
+                Ok(milli as f32 / 1000.0)
+            }
+            Self::AmdDie => HwmonReader::get_amd_temps()
+                .and_then(|t| t.die_temp())
+                .context("AMD die temperature unavailable"),
+        }
+    }
+}
+
+/// Ranks a sensor as a CPU temperature source from its zone type or its
+/// hwmon device name and channel label.
+fn score_sensor(device: &str, label: &str) -> (i32, &'static str) {
+    let dev = device.to_lowercase();
+    let lbl = label.to_lowercase();
+    const NOT_CPU: &[&str] = &["nvme", "drivetemp", "amdgpu", "nouveau", "radeon", "iwlwifi", "wifi", "ath1", "mt79", "pch_", "bat", "gpu"];
+    match (dev.as_str(), lbl.as_str()) {
+        ("x86_pkg_temp", _) => (100, "Intel package sensor"),
+        ("amd", "die") => (96, "AMD die temperature (Tctl minus the model's offset)"),
+        ("coretemp", l) if l.starts_with("package id") => (95, "coretemp package sensor"),
+        ("k10temp" | "zenpower", "tdie") => (95, "AMD die temperature"),
+        ("tcpu" | "tcpu_pci" | "b0d4", _) => (80, "Intel processor thermal device"),
+        ("k10temp" | "zenpower", "tctl") => (70, "AMD control temperature; may include a fan offset"),
+        ("k10temp" | "zenpower", l) if l.starts_with("tccd") => (65, "a single CCD, not the whole package"),
+        ("coretemp", l) if l.starts_with("core") => (60, "a single core, not the whole package"),
+        (d, _) if NOT_CPU.iter().any(|n| d.contains(n)) => (-100, "not a CPU sensor"),
+        (d, _) if d.contains("cpu") => (75, "CPU thermal zone"),
+        (_, l) if l.contains("cpu") => (50, "board sensor near the CPU socket"),
+        ("acpitz", _) => (20, "ACPI zone; often a board or chassis sensor"),
+        _ => (10, "unrecognised sensor"),
+    }
 }
 
 pub struct ThermalManager {
     zones: Vec<PathBuf>,
+    source: TempSource,
+    /// Sensors `source` resolved to; empty means the hottest zone. Cleared
+    /// by `set_source` and re-resolved after a failed read.
+    resolved: Mutex<Option<Vec<Reading>>>,
 }
 
 impl ThermalManager {
     pub fn new() -> Result<Self> {
         let zones = Self::discover_thermal_zones()?;
         log::info!("Discovered {} thermal zones", zones.len());
-        Ok(Self { zones })
+        Ok(Self { zones, source: TempSource::Auto, resolved: Mutex::new(None) })
     }
 
     fn discover_thermal_zones() -> Result<Vec<PathBuf>> {
@@ -43,7 +218,12 @@ impl ThermalManager {
             }
         }
         
-        zones.sort();
+        // Numeric order, so thermal_zone10 comes after thermal_zone9
+        zones.sort_by_key(|p| {
+            p.file_name()
+                .and_then(|n| n.to_string_lossy().strip_prefix("thermal_zone")?.parse::<usize>().ok())
+                .unwrap_or(usize::MAX)
+        });
         Ok(zones)
     }
 
@@ -88,19 +268,33 @@ impl ThermalManager {
         let temp_celsius = self.get_temperature(zone)?;
         let type_name = self.get_zone_type(zone)?;
         let trip_points = self.get_trip_points(zone)?;
+        let bindings = self.get_bindings(zone);
+        let read = |f: &str| fs::read_to_string(self.zones[zone].join(f)).ok().map(|s| s.trim().to_string());
 
         Ok(ThermalZone {
             id: zone,
+            name: self.zones[zone].file_name().map(|n| n.to_string_lossy().into_owned()).unwrap_or_default(),
             type_name,
             temp_celsius,
             trip_points,
+            bindings,
+            mode: read("mode"),
+            policy: read("policy"),
         })
     }
 
+    /// Every readable zone. Zones whose temperature read fails (disabled
+    /// ACPI zones often return ENODATA) are skipped rather than failing all.
     pub fn get_all_zones(&self) -> Result<Vec<ThermalZone>> {
-        (0..self.zones.len())
-            .map(|zone| self.get_zone_info(zone))
-            .collect()
+        Ok((0..self.zones.len())
+            .filter_map(|zone| match self.get_zone_info(zone) {
+                Ok(info) => Some(info),
+                Err(e) => {
+                    log::debug!("Skipping thermal zone {}: {}", zone, e);
+                    None
+                }
+            })
+            .collect())
     }
 
     fn get_trip_points(&self, zone: usize) -> Result<Vec<TripPoint>> {
@@ -126,10 +320,20 @@ impl ThermalManager {
                 .trim()
                 .to_string();
 
+            let hysteresis_celsius = fs::read_to_string(self.zones[zone].join(format!("trip_point_{}_hyst", trip_id)))
+                .ok()
+                .and_then(|h| h.trim().parse::<i32>().ok())
+                .map(|h| h as f32 / 1000.0);
+            let writable = fs::metadata(&temp_path)
+                .map(|m| m.permissions().mode() & 0o222 != 0)
+                .unwrap_or(false);
+
             trip_points.push(TripPoint {
                 id: trip_id,
                 temp_celsius: temp_millicelsius as f32 / 1000.0,
                 trip_type,
+                hysteresis_celsius,
+                writable,
             });
 
             trip_id += 1;
@@ -138,6 +342,89 @@ impl ThermalManager {
         Ok(trip_points)
     }
 
+    /// Moves a passive or active trip point. Critical and hot trips are
+    /// refused: they trigger shutdown and firmware sets them for a reason.
+    pub fn set_trip_point(&self, zone: usize, trip: usize, temp_celsius: f32) -> Result<()> {
+        let trip_point = self.get_trip_points(zone)?
+            .into_iter()
+            .find(|t| t.id == trip)
+            .with_context(|| format!("Thermal zone {} has no trip point {}", zone, trip))?;
+        if !matches!(trip_point.trip_type.as_str(), "passive" | "active") {
+            anyhow::bail!("Refusing to change {} trip point {} of zone {}", trip_point.trip_type, trip, zone);
+        }
+        if !trip_point.writable {
+            anyhow::bail!("Trip point {} of zone {} is read-only", trip, zone);
+        }
+        if !(20.0..=125.0).contains(&temp_celsius) {
+            anyhow::bail!("Trip temperature {:.1}°C is out of range (20–125°C)", temp_celsius);
+        }
+        let path = self.zones[zone].join(format!("trip_point_{}_temp", trip));
+        fs::write(&path, ((temp_celsius * 1000.0).round() as i32).to_string()).with_context(|| {
+            format!("Failed to set trip point {} of zone {}. Run with sudo or enable PolicyKit.", trip, zone)
+        })
+    }
+
+    fn get_bindings(&self, zone: usize) -> Vec<CoolingBinding> {
+        let Ok(entries) = fs::read_dir(&self.zones[zone]) else { return vec![] };
+        let mut bindings: Vec<(usize, CoolingBinding)> = entries
+            .filter_map(|e| e.ok())
+            .filter_map(|e| {
+                let name = e.file_name().to_string_lossy().to_string();
+                let n: usize = name.strip_prefix("cdev")?.parse().ok()?;
+                let target = fs::read_link(e.path()).ok()?;
+                let cooling_device = target.file_name()?.to_string_lossy()
+                    .strip_prefix("cooling_device")?.parse().ok()?;
+                let read = |suffix: &str| fs::read_to_string(self.zones[zone].join(format!("cdev{}_{}", n, suffix))).ok();
+                // The kernel reports -1 for a binding without a trip.
+                let trip_point = read("trip_point").and_then(|t| t.trim().parse::<i64>().ok())
+                    .and_then(|t| usize::try_from(t).ok());
+                let weight = read("weight").and_then(|w| w.trim().parse().ok());
+                Some((n, CoolingBinding { cooling_device, trip_point, weight }))
+            })
+            .collect();
+        bindings.sort_by_key(|(n, _)| *n);
+        bindings.into_iter().map(|(_, b)| b).collect()
+    }
+
+    pub fn get_cooling_devices(&self) -> Result<Vec<CoolingDevice>> {
+        let entries = fs::read_dir(THERMAL_BASE)
+            .context("Failed to read thermal directory")?;
+        let mut devices: Vec<CoolingDevice> = entries
+            .filter_map(|e| e.ok())
+            .filter_map(|e| {
+                let id: usize = e.file_name().to_string_lossy().strip_prefix("cooling_device")?.parse().ok()?;
+                let read = |f: &str| fs::read_to_string(e.path().join(f)).ok().map(|s| s.trim().to_string());
+                let type_name = read("type")?;
+                Some(CoolingDevice {
+                    id,
+                    kind: CoolingKind::from_type(&type_name),
+                    type_name,
+                    cur_state: read("cur_state").and_then(|s| s.parse().ok()).unwrap_or(0),
+                    max_state: read("max_state").and_then(|s| s.parse().ok()).unwrap_or(0),
+                })
+            })
+            .collect();
+        devices.sort_by_key(|d| d.id);
+        Ok(devices)
+    }
+
+    /// Forces a cooling device's state. The kernel's governor may overwrite
+    /// it on the next thermal poll, so this is mainly for testing.
+    pub fn set_cooling_state(&self, device: usize, state: u64) -> Result<()> {
+        let path = PathBuf::from(THERMAL_BASE).join(format!("cooling_device{}", device));
+        let max_state: u64 = fs::read_to_string(path.join("max_state"))
+            .with_context(|| format!("Cooling device {} does not exist", device))?
+            .trim()
+            .parse()
+            .context("Failed to parse max_state")?;
+        if state > max_state {
+            anyhow::bail!("State {} exceeds max_state {} of cooling device {}", state, max_state, device);
+        }
+        fs::write(path.join("cur_state"), state.to_string()).with_context(|| {
+            format!("Failed to set cooling device {} state. Run with sudo or enable PolicyKit.", device)
+        })
+    }
+
     pub fn get_max_temperature(&self) -> Result<f32> {
         let temps = self.get_all_temperatures()?;
         temps.into_iter()
@@ -145,22 +432,175 @@ impl ThermalManager {
             .ok_or_else(|| anyhow::anyhow!("No thermal zones found"))
     }
 
-    pub fn get_cpu_temperature(&self) -> Result<f32> {
-        // Try to find CPU package temperature
-        for (zone_id, zone_path) in self.zones.iter().enumerate() {
-            let type_path = zone_path.join("type");
-            if let Ok(zone_type) = fs::read_to_string(&type_path) {
-                let zone_type = zone_type.trim().to_lowercase();
-                if zone_type.contains("x86_pkg_temp") || 
-                   zone_type.contains("cpu") ||
-                   zone_type.contains("core") {
-                    return self.get_temperature(zone_id);
+    pub fn source(&self) -> &TempSource { &self.source }
+
+    pub fn set_source(&mut self, source: TempSource) {
+        self.source = source;
+        *self.resolved.get_mut().unwrap_or_else(|e| e.into_inner()) = None;
+    }
+
+    /// Every zone and hwmon temperature channel, best CPU source first.
+    pub fn temp_candidates(&self) -> Vec<TempCandidate> {
+        let candidate = |id: String, label: String, temp_celsius: Option<f32>, (score, reason): (i32, &'static str), path: Option<PathBuf>| {
+            TempCandidate { id, label, temp_celsius, score, reason, path }
+        };
+        // Each candidate with the id it falls back to when its plain id repeats.
+        let mut raw: Vec<(TempCandidate, String)> = Vec::new();
+        for (zone, path) in self.zones.iter().enumerate() {
+            let Ok(type_name) = self.get_zone_type(zone) else { continue };
+            let name = path.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+            raw.push((
+                candidate(
+                    format!("zone:{}", type_name),
+                    format!("{} ({})", type_name, name),
+                    self.get_temperature(zone).ok(),
+                    score_sensor(&type_name, ""),
+                    Some(path.join("temp")),
+                ),
+                format!("zone:{}@{}", type_name, name),
+            ));
+        }
+        if let Some(amd) = HwmonReader::get_amd_temps() {
+            let c = candidate("amd:die".into(), format!("{} die", amd.driver), amd.die_temp(), score_sensor("amd", "die"), None);
+            raw.push((c, "amd:die".into()));
+        }
+        for dev in HwmonReader::devices() {
+            let temps: Vec<_> = dev.channels_of(SensorKind::Temp).collect();
+            for ch in &temps {
+                // Label, or the channel name where the chip repeats a label.
+                let key = if temps.iter().filter(|o| o.label == ch.label).count() > 1 {
+                    format!("temp{}", ch.index)
+                } else {
+                    ch.label.clone()
+                };
+                raw.push((
+                    candidate(
+                        format!("hwmon:{}/{}", dev.name, key),
+                        format!("{} {} ({})", dev.name, ch.label, dev.id),
+                        ch.value.map(|v| v as f32),
+                        score_sensor(&dev.name, &ch.label),
+                        Some(dev.attr_path(ch, "input")),
+                    ),
+                    format!("hwmon:{}@{}/{}", dev.name, dev.device.as_deref().unwrap_or(&dev.id), key),
+                ));
+            }
+        }
+
+        let mut candidates: Vec<TempCandidate> = Vec::new();
+        for (mut c, qualified) in raw.iter().cloned() {
+            if raw.iter().filter(|(o, _)| o.id == c.id).count() > 1 {
+                c.id = qualified;
+            }
+            if c.temp_celsius.is_none() {
+                c.score -= 200;
+                c.reason = "no reading";
+            }
+            candidates.push(c);
+        }
+        candidates.sort_by_key(|c| std::cmp::Reverse(c.score));
+        candidates
+    }
+
+    /// Trip points and sensor limits on CPU sensors (score > 0) where
+    /// throttling begins, lowest first. Implausible values are dropped.
+    pub fn throttle_thresholds(&self) -> Vec<ThrottleThreshold> {
+        let plausible = |t: f32| (40.0..=150.0).contains(&t);
+        let mut out = Vec::new();
+        for zone in self.get_all_zones().unwrap_or_default() {
+            if score_sensor(&zone.type_name, "").0 <= 0 {
+                continue;
+            }
+            for trip in &zone.trip_points {
+                if matches!(trip.trip_type.as_str(), "passive" | "hot" | "critical") && plausible(trip.temp_celsius) {
+                    out.push(ThrottleThreshold {
+                        name: format!("{} {} trip", zone.type_name, trip.trip_type),
+                        temp_celsius: trip.temp_celsius,
+                    });
                 }
             }
         }
+        for dev in HwmonReader::devices() {
+            for ch in dev.channels_of(SensorKind::Temp) {
+                if score_sensor(&dev.name, &ch.label).0 < 50 {
+                    continue;
+                }
+                for (what, limit) in [("max", ch.max), ("crit", ch.crit)] {
+                    if let Some(t) = limit.map(|t| t as f32).filter(|&t| plausible(t)) {
+                        out.push(ThrottleThreshold { name: format!("{} {} {}", dev.name, ch.label, what), temp_celsius: t });
+                    }
+                }
+            }
+        }
+        out.sort_by(|a, b| a.temp_celsius.total_cmp(&b.temp_celsius));
+        out
+    }
 
-        // Fallback to max temperature
-        self.get_max_temperature()
+    /// The sensor `TempSource::Auto` picks, if any scores above zero.
+    pub fn auto_candidate(&self) -> Option<TempCandidate> {
+        self.temp_candidates().into_iter().find(|c| c.score > 0 && c.temp_celsius.is_some())
+    }
+
+    /// One line describing where the CPU temperature currently comes from.
+    pub fn describe_source(&self) -> String {
+        match &self.source {
+            TempSource::Auto => match self.auto_candidate() {
+                Some(c) => format!("Auto: {} — {}", c.label, c.reason),
+                None => "Auto: no CPU sensor recognised, using the hottest thermal zone".into(),
+            },
+            TempSource::Single { sensor } => sensor.clone(),
+            TempSource::Max { sensors } => format!("Maximum of {}", sensors.join(", ")),
+            TempSource::Average { sensors } => format!("Average of {}", sensors.join(", ")),
+        }
+    }
+
+    /// CPU temperature from the configured `TempSource`. A configured sensor
+    /// that has disappeared falls back to auto-detection. The sensors are
+    /// resolved once and re-resolved only after a failed read.
+    pub fn get_cpu_temperature(&self) -> Result<f32> {
+        let mut resolved = self.resolved.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(Ok(t)) = resolved.as_deref().map(|r| self.read_resolved(r)) {
+            return Ok(t);
+        }
+        let readings = self.resolve();
+        let t = self.read_resolved(&readings);
+        *resolved = Some(readings);
+        t
+    }
+
+    fn resolve(&self) -> Vec<Reading> {
+        let candidates = self.temp_candidates();
+        let reading = |c: &TempCandidate| c.path.clone().map_or(Reading::AmdDie, Reading::File);
+        let sensors: &[String] = match &self.source {
+            TempSource::Auto => &[],
+            TempSource::Single { sensor } => std::slice::from_ref(sensor),
+            TempSource::Max { sensors } | TempSource::Average { sensors } => sensors,
+        };
+        let configured: Vec<Reading> = candidates.iter()
+            .filter(|c| sensors.contains(&c.id) && c.temp_celsius.is_some())
+            .map(reading)
+            .collect();
+        if !configured.is_empty() {
+            return configured;
+        }
+        if !sensors.is_empty() {
+            log::debug!("Configured temperature sensors unavailable, using auto-detection");
+        }
+        candidates.iter()
+            .find(|c| c.score > 0 && c.temp_celsius.is_some())
+            .map(reading)
+            .into_iter()
+            .collect()
+    }
+
+    fn read_resolved(&self, readings: &[Reading]) -> Result<f32> {
+        if readings.is_empty() {
+            return self.get_max_temperature();
+        }
+        let temps = readings.iter().map(Reading::read).collect::<Result<Vec<f32>>>()?;
+        Ok(match self.source {
+            TempSource::Average { .. } => temps.iter().sum::<f32>() / temps.len() as f32,
+            _ => temps.into_iter().fold(f32::MIN, f32::max),
+        })
     }
 }
 
@@ -168,6 +608,27 @@ impl ThermalManager {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_cooling_kind() {
+        assert_eq!(CoolingKind::from_type("Processor"), CoolingKind::Processor);
+        assert_eq!(CoolingKind::from_type("intel_powerclamp"), CoolingKind::PowerClamp);
+        assert_eq!(CoolingKind::from_type("TCC Offset"), CoolingKind::TccOffset);
+        assert_eq!(CoolingKind::from_type("Fan"), CoolingKind::Fan);
+        assert_eq!(CoolingKind::from_type("thermal-cpufreq-0"), CoolingKind::CpuFreq);
+        assert_eq!(CoolingKind::from_type("LCD"), CoolingKind::Other);
+    }
+
+    #[test]
+    fn test_score_sensor() {
+        let score = |d, l| score_sensor(d, l).0;
+        assert!(score("x86_pkg_temp", "") > score("coretemp", "Package id 0"));
+        assert!(score("coretemp", "Package id 0") > score("coretemp", "Core 3"));
+        assert!(score("k10temp", "Tdie") > score("k10temp", "Tctl"));
+        assert!(score("cpu-thermal", "") > score("acpitz", ""));
+        assert!(score("nvme", "Composite") < 0);
+        assert!(score("iwlwifi_1", "temp1") < 0);
+    }
+
     #[test]
     fn test_thermal_manager() {
         let manager = ThermalManager::new();
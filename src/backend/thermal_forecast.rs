@@ -0,0 +1,147 @@
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+use crate::backend::thermal::ThrottleThreshold;
+
+/// Slower heating than this counts as steady; no estimate is given.
+const MIN_RATE: f32 = 0.01;
+
+/// Estimated time until the next throttle threshold at the current
+/// heating rate.
+#[derive(Debug, Clone)]
+pub struct ThrottleForecast {
+    pub temp_celsius: f32,
+    /// Least-squares slope over the history window, °C/s.
+    pub rate_celsius_per_sec: f32,
+    /// The lowest threshold above the current temperature, or the highest
+    /// one already crossed.
+    pub threshold: ThrottleThreshold,
+    /// `Some(0.0)` once at or past the threshold; `None` when steady or cooling.
+    pub seconds: Option<f32>,
+}
+
+impl ThrottleForecast {
+    /// Short dashboard text, e.g. `~2m 10s to x86_pkg_temp passive trip`.
+    pub fn summary(&self) -> String {
+        match self.seconds {
+            Some(s) if s <= 0.0 => format!("At {}", self.threshold.name),
+            Some(s) => format!("~{} to {}", format_duration(s), self.threshold.name),
+            None => format!("Steady — {} not approaching", self.threshold.name),
+        }
+    }
+}
+
+pub fn format_duration(secs: f32) -> String {
+    let secs = secs.round() as u64;
+    match secs {
+        0..=59 => format!("{}s", secs),
+        60..=3599 => format!("{}m {:02}s", secs / 60, secs % 60),
+        _ => format!("{}h {:02}m", secs / 3600, secs % 3600 / 60),
+    }
+}
+
+/// Rolling CPU temperature history that extrapolates towards the
+/// throttle thresholds.
+#[derive(Debug, Clone)]
+pub struct ThrottleForecaster {
+    samples: VecDeque<(Instant, f32)>,
+    window: Duration,
+    thresholds: Vec<ThrottleThreshold>,
+}
+
+impl ThrottleForecaster {
+    pub fn new(window: Duration, thresholds: Vec<ThrottleThreshold>) -> Self {
+        let mut f = Self { samples: VecDeque::new(), window, thresholds: Vec::new() };
+        f.set_thresholds(thresholds);
+        f
+    }
+
+    pub fn set_thresholds(&mut self, mut thresholds: Vec<ThrottleThreshold>) {
+        thresholds.sort_by(|a, b| a.temp_celsius.total_cmp(&b.temp_celsius));
+        self.thresholds = thresholds;
+    }
+
+    pub fn push(&mut self, temp_celsius: f32) {
+        self.push_at(Instant::now(), temp_celsius);
+    }
+
+    fn push_at(&mut self, now: Instant, temp_celsius: f32) {
+        self.samples.push_back((now, temp_celsius));
+        while self.samples.front().is_some_and(|(t, _)| now.duration_since(*t) > self.window) {
+            self.samples.pop_front();
+        }
+    }
+
+    /// Heating rate in °C/s; needs at least three samples spanning 3 s.
+    pub fn rate(&self) -> Option<f32> {
+        let (first, _) = *self.samples.front()?;
+        let (last, _) = *self.samples.back()?;
+        if self.samples.len() < 3 || last.duration_since(first) < Duration::from_secs(3) {
+            return None;
+        }
+        let n = self.samples.len() as f32;
+        let xs: Vec<f32> = self.samples.iter().map(|(t, _)| t.duration_since(first).as_secs_f32()).collect();
+        let mean_x = xs.iter().sum::<f32>() / n;
+        let mean_y = self.samples.iter().map(|(_, y)| y).sum::<f32>() / n;
+        let (mut num, mut den) = (0.0, 0.0);
+        for (x, (_, y)) in xs.iter().zip(&self.samples) {
+            num += (x - mean_x) * (y - mean_y);
+            den += (x - mean_x) * (x - mean_x);
+        }
+        (den > 0.0).then(|| num / den)
+    }
+
+    /// Temperature expected `secs` from now if the current heating
+    /// continues; never below the latest reading.
+    pub fn predict(&self, secs: f32) -> Option<f32> {
+        let (_, temp) = *self.samples.back()?;
+        Some(temp + self.rate().unwrap_or(0.0).max(0.0) * secs)
+    }
+
+    pub fn forecast(&self) -> Option<ThrottleForecast> {
+        let (_, temp) = *self.samples.back()?;
+        let rate = self.rate()?;
+        let (threshold, seconds) = match self.thresholds.iter().find(|t| t.temp_celsius > temp) {
+            Some(t) => (t, (rate > MIN_RATE).then(|| (t.temp_celsius - temp) / rate)),
+            None => (self.thresholds.last()?, Some(0.0)),
+        };
+        Some(ThrottleForecast { temp_celsius: temp, rate_celsius_per_sec: rate, threshold: threshold.clone(), seconds })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn threshold(temp_celsius: f32) -> ThrottleThreshold {
+        ThrottleThreshold { name: format!("trip {}", temp_celsius), temp_celsius }
+    }
+
+    #[test]
+    fn test_linear_heating() {
+        let mut f = ThrottleForecaster::new(Duration::from_secs(30), vec![threshold(100.0), threshold(90.0)]);
+        let t0 = Instant::now();
+        // 0.5 °C/s from 70 °C, ending at 75 °C: 90 °C is 30 s away.
+        for s in 0..=10 {
+            f.push_at(t0 + Duration::from_secs(s), 70.0 + 0.5 * s as f32);
+        }
+        let fc = f.forecast().unwrap();
+        assert!((fc.rate_celsius_per_sec - 0.5).abs() < 1e-3);
+        assert_eq!(fc.threshold.temp_celsius, 90.0);
+        assert!((fc.seconds.unwrap() - 30.0).abs() < 0.1);
+        assert!((f.predict(10.0).unwrap() - 80.0).abs() < 0.1);
+    }
+
+    #[test]
+    fn test_cooling_and_crossed() {
+        let mut f = ThrottleForecaster::new(Duration::from_secs(30), vec![threshold(90.0)]);
+        let t0 = Instant::now();
+        for s in 0..5 {
+            f.push_at(t0 + Duration::from_secs(s), 80.0 - s as f32);
+        }
+        assert_eq!(f.forecast().unwrap().seconds, None);
+        for s in 5..10 {
+            f.push_at(t0 + Duration::from_secs(s), 91.0);
+        }
+        assert_eq!(f.forecast().unwrap().seconds, Some(0.0));
+    }
+}
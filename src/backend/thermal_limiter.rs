@@ -0,0 +1,304 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::sync::Mutex;
+use std::time::Instant;
+use crate::backend::cpu::CpuManager;
+use crate::backend::power_supply::RaplZone;
+
+/// Last failure `drive` logged, so a write that keeps failing warns once.
+static LAST_ERROR: Mutex<Option<String>> = Mutex::new(None);
+
+fn report(error: Option<String>) {
+    let mut last = LAST_ERROR.lock().unwrap_or_else(|e| e.into_inner());
+    if *last == error {
+        return;
+    }
+    match &error {
+        Some(e) => log::warn!("{}", e),
+        None => log::info!("Thermal limiter recovered"),
+    }
+    *last = error;
+}
+
+/// What the limiter turns to hold the temperature.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LimiterActuator {
+    /// RAPL PL1 when a package zone exposes it, otherwise frequency.
+    #[default]
+    Auto,
+    Frequency,
+    PowerLimit,
+}
+
+/// `[thermal.limiter]`: closed-loop control of the CPU temperature.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ThermalLimiterConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default = "default_target")]
+    pub target_celsius: f32,
+    #[serde(default)]
+    pub actuator: LimiterActuator,
+    /// Output change per °C of error.
+    #[serde(default = "default_kp")]
+    pub kp: f32,
+    /// Output change per °C of error per second.
+    #[serde(default = "default_ki")]
+    pub ki: f32,
+    /// Output change per °C/s of temperature rise (acts on the measurement).
+    #[serde(default = "default_kd")]
+    pub kd: f32,
+    /// Largest output change per second, as a fraction of the actuator range.
+    #[serde(default = "default_max_step")]
+    pub max_step_per_second: f32,
+    /// Floor for PL1 when limiting by power.
+    #[serde(default = "default_min_pl1")]
+    pub min_pl1_watts: f32,
+    /// Act on the temperature forecast this many seconds ahead instead of
+    /// the current reading, so clocks are capped before the target is hit.
+    /// The gains then scale the forecast error and its rate of rise.
+    #[serde(default)]
+    pub lead_seconds: f32,
+}
+
+fn default_target() -> f32 { 80.0 }
+fn default_kp() -> f32 { 0.05 }
+fn default_ki() -> f32 { 0.01 }
+fn default_kd() -> f32 { 0.02 }
+fn default_max_step() -> f32 { 0.1 }
+fn default_min_pl1() -> f32 { 5.0 }
+
+impl Default for ThermalLimiterConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            target_celsius: default_target(),
+            actuator: LimiterActuator::Auto,
+            kp: default_kp(),
+            ki: default_ki(),
+            kd: default_kd(),
+            max_step_per_second: default_max_step(),
+            min_pl1_watts: default_min_pl1(),
+            lead_seconds: 0.0,
+        }
+    }
+}
+
+/// PID on the temperature error, producing a fraction of the actuator
+/// range (1 = unrestricted). The integral starts full and is clamped to
+/// 0–1, so a cool CPU runs unrestricted and there is no windup.
+#[derive(Debug, Clone)]
+struct Pid {
+    integral: f32,
+    prev_temp: Option<f32>,
+}
+
+impl Pid {
+    fn new() -> Self { Self { integral: 1.0, prev_temp: None } }
+
+    fn step(&mut self, cfg: &ThermalLimiterConfig, temp: f32, dt: f32) -> f32 {
+        let error = cfg.target_celsius - temp;
+        let rising = match self.prev_temp {
+            Some(prev) if dt > 0.0 => (temp - prev) / dt,
+            _ => 0.0,
+        };
+        self.prev_temp = Some(temp);
+        self.integral = (self.integral + cfg.ki * error * dt).clamp(0.0, 1.0);
+        (cfg.kp * error + self.integral - cfg.kd * rising).clamp(0.0, 1.0)
+    }
+}
+
+enum Actuator {
+    Frequency { original_max_mhz: Vec<Option<u32>> },
+    /// (zone, PL1 constraint id, PL1 before we touched it)
+    PowerLimit { zones: Vec<(RaplZone, usize, f32)>, min_w: f32 },
+}
+
+/// One control step, for logging and the GUI.
+#[derive(Debug, Clone)]
+pub struct LimiterOutput {
+    pub temp_celsius: f32,
+    pub target_celsius: f32,
+    /// 0–1 fraction of the actuator range.
+    pub output: f32,
+    /// Actuator value written: MHz or W.
+    pub value: f32,
+    pub unit: &'static str,
+}
+
+/// Holds the CPU temperature at a target by moving scaling_max_freq or PL1.
+/// Original limits are restored by `release()`.
+pub struct ThermalLimiter {
+    requested: LimiterActuator,
+    pid: Pid,
+    actuator: Actuator,
+    output: f32,
+    last_update: Option<Instant>,
+    last_written: Option<f32>,
+}
+
+impl ThermalLimiter {
+    pub fn new(cfg: &ThermalLimiterConfig, cpu: &CpuManager) -> Result<Self> {
+        let pl1_zones: Vec<(RaplZone, usize, f32)> = RaplZone::packages().into_iter()
+            .filter_map(|z| {
+                let c = z.constraint("long_term")?;
+                let (id, watts) = (c.id, c.power_limit_w());
+                Some((z, id, watts))
+            })
+            .collect();
+        let use_power = match cfg.actuator {
+            LimiterActuator::PowerLimit if pl1_zones.is_empty() => {
+                anyhow::bail!("No RAPL package zone with a PL1 (long_term) constraint");
+            }
+            LimiterActuator::PowerLimit => true,
+            LimiterActuator::Auto => !pl1_zones.is_empty(),
+            LimiterActuator::Frequency => false,
+        };
+        let actuator = if use_power {
+            Actuator::PowerLimit { zones: pl1_zones, min_w: cfg.min_pl1_watts.max(1.0) }
+        } else {
+            cpu.get_hardware_max_freq(0).context("No cpufreq hardware limits to control")?;
+            Actuator::Frequency {
+                original_max_mhz: (0..cpu.core_count()).map(|c| cpu.get_scaling_max_freq(c).ok()).collect(),
+            }
+        };
+        log::info!(
+            "Thermal limiter started: target {:.1}°C via {}",
+            cfg.target_celsius, if use_power { "RAPL PL1" } else { "scaling_max_freq" }
+        );
+        Ok(Self { requested: cfg.actuator, pid: Pid::new(), actuator, output: 1.0, last_update: None, last_written: None })
+    }
+
+    /// One tick for the GUI loop and the service: starts, updates or stops the
+    /// limiter in `slot` to match `cfg`, restarting it when the actuator changes.
+    /// With `lead_seconds` set, callers pass the forecast temperature.
+    pub fn drive(
+        slot: &mut Option<ThermalLimiter>,
+        cfg: &ThermalLimiterConfig,
+        temp: Option<f32>,
+        cpu: &CpuManager,
+    ) -> Option<LimiterOutput> {
+        if slot.as_ref().is_some_and(|l| !cfg.enabled || l.requested != cfg.actuator) {
+            if let Some(mut l) = slot.take() {
+                l.release(cpu);
+            }
+        }
+        if !cfg.enabled {
+            LAST_ERROR.lock().unwrap_or_else(|e| e.into_inner()).take();
+            return None;
+        }
+        let temp = temp?;
+        if slot.is_none() {
+            match ThermalLimiter::new(cfg, cpu) {
+                Ok(l) => *slot = Some(l),
+                Err(e) => {
+                    report(Some(format!("Thermal limiter unavailable: {:#}", e)));
+                    return None;
+                }
+            }
+        }
+        let limiter = slot.as_mut()?;
+        match limiter.update(cfg, temp, cpu) {
+            Ok(out) => {
+                report(None);
+                Some(out)
+            }
+            Err(e) => {
+                report(Some(format!("Thermal limiter update failed: {:#}", e)));
+                None
+            }
+        }
+    }
+
+    pub fn update(&mut self, cfg: &ThermalLimiterConfig, temp: f32, cpu: &CpuManager) -> Result<LimiterOutput> {
+        let now = Instant::now();
+        let dt = self.last_update.map(|t| now.duration_since(t).as_secs_f32()).unwrap_or(1.0).clamp(0.05, 10.0);
+        self.last_update = Some(now);
+
+        let wanted = self.pid.step(cfg, temp, dt);
+        let max_step = cfg.max_step_per_second.max(0.001) * dt;
+        self.output += (wanted - self.output).clamp(-max_step, max_step);
+
+        let (value, unit, min_change) = match &self.actuator {
+            Actuator::Frequency { .. } => {
+                let hw_min = cpu.get_hardware_min_freq(0)? as f32;
+                let hw_max = cpu.get_hardware_max_freq(0)? as f32;
+                (hw_min + self.output * (hw_max - hw_min), "MHz", 25.0)
+            }
+            Actuator::PowerLimit { zones, min_w } => {
+                let max_w = zones.iter().map(|(_, _, w)| *w).fold(0.0, f32::max);
+                (min_w + self.output * (max_w - min_w).max(0.0), "W", 0.5)
+            }
+        };
+
+        // Skip sysfs writes that wouldn't change anything meaningful.
+        if self.last_written.is_none_or(|prev| (prev - value).abs() >= min_change) {
+            match &self.actuator {
+                Actuator::Frequency { .. } => { cpu.set_max_freq_fraction(self.output)?; }
+                Actuator::PowerLimit { zones, min_w } => {
+                    for (zone, id, original) in zones {
+                        let watts = min_w + self.output * (original - min_w).max(0.0);
+                        zone.set_power_limit(*id, watts)?;
+                    }
+                }
+            }
+            self.last_written = Some(value);
+            log::info!(
+                "Thermal limiter: {:.1}°C (target {:.1}) → {:.0}% = {:.1} {}",
+                temp, cfg.target_celsius, self.output * 100.0, value, unit
+            );
+        }
+
+        Ok(LimiterOutput { temp_celsius: temp, target_celsius: cfg.target_celsius, output: self.output, value, unit })
+    }
+
+    /// Puts back the limits that were in place before the limiter started.
+    /// Profiles, rules or overrides applied since then would be undone, so
+    /// the service and GUI loops re-apply their current selection after a
+    /// `drive` that stopped the limiter.
+    pub fn release(&mut self, cpu: &CpuManager) {
+        match &self.actuator {
+            Actuator::Frequency { original_max_mhz } => {
+                for (core, mhz) in original_max_mhz.iter().enumerate() {
+                    if let Some(mhz) = mhz {
+                        let _ = cpu.set_scaling_max_freq(core, *mhz);
+                    }
+                }
+            }
+            Actuator::PowerLimit { zones, .. } => {
+                for (zone, id, original) in zones {
+                    if let Err(e) = zone.set_power_limit(*id, *original) {
+                        log::warn!("Failed to restore PL1 on {}: {}", zone.id, e);
+                    }
+                }
+            }
+        }
+        self.last_written = None;
+        log::info!("Thermal limiter stopped, original limits restored");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pid_direction() {
+        let cfg = ThermalLimiterConfig::default();
+        let mut pid = Pid::new();
+        // Below target: unrestricted.
+        assert_eq!(pid.step(&cfg, 60.0, 1.0), 1.0);
+        // Sustained overshoot winds the output down, never below zero.
+        let mut out = 1.0;
+        for _ in 0..200 {
+            out = pid.step(&cfg, 90.0, 1.0);
+        }
+        assert_eq!(out, 0.0);
+        // Back under target it recovers.
+        for _ in 0..200 {
+            out = pid.step(&cfg, 70.0, 1.0);
+        }
+        assert_eq!(out, 1.0);
+    }
+}
@@ -1,8 +1,22 @@
 use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
+use std::cell::RefCell;
 use std::fs;
 use std::path::PathBuf;
+use crate::backend::auto_tune::BatteryRule;
+use crate::backend::emergency::EmergencyConfig;
+use crate::backend::fan::FanCurve;
+use crate::backend::power_supply::ChargeThresholds;
+use crate::backend::process_watch::AppRule;
 use crate::backend::profile::Profile;
+use crate::backend::rules::{Actions, Conditions, PowerState, Rule};
+use crate::backend::schedule::ScheduleRule;
+use crate::backend::thermal::{TempSource, ThrottleThreshold};
+use crate::backend::thermal_limiter::ThermalLimiterConfig;
+
+/// The background service's config directory; its unit sets
+/// `XDG_CONFIG_HOME=/etc`.
+pub const SERVICE_CONFIG_DIR: &str = "/etc/cpu-power-manager";
 
 pub fn config_dir() -> PathBuf {
     let base = std::env::var("XDG_CONFIG_HOME")
@@ -40,11 +54,16 @@ pub struct Config {
     #[serde(default)]
     pub thermal: ThermalConfig,
     #[serde(default)]
+    pub battery: BatteryConfig,
+    #[serde(default)]
     pub monitoring: MonitoringConfig,
     #[serde(default)]
     pub logging: LoggingConfig,
     #[serde(default)]
     pub custom_profiles: Vec<Profile>,
+    /// Declarative automation on top of the built-in rules; see `effective_rules`.
+    #[serde(default)]
+    pub rules: Vec<Rule>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -87,16 +106,88 @@ pub struct AutoTuneConfig {
     pub load_threshold_high: f32,
     #[serde(default = "default_load_low")]
     pub load_threshold_low: f32,
+    /// Power-source changes must settle this long before profiles switch,
+    /// so a flapping dock or USB-C charger doesn't cause a burst of switches.
+    #[serde(default = "default_power_source_debounce")]
+    pub power_source_debounce_ms: u64,
+    /// Checked in order on battery; the first match overrides `battery_profile`.
+    #[serde(default)]
+    pub battery_rules: Vec<BatteryRule>,
+    /// Profiles to use while particular programs run; these win over the
+    /// AC/battery choice and revert when the program exits.
+    #[serde(default)]
+    pub app_rules: Vec<AppRule>,
+    /// Weekly time windows; these replace the AC/battery profile while
+    /// active, but yield to low-battery and app rules.
+    #[serde(default)]
+    pub schedule: Vec<ScheduleRule>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ThermalConfig {
     #[serde(default = "default_max_temp")]
     pub max_temp_celsius: f32,
+    /// Sustained temperature that triggers the emergency clamp (see `emergency`).
     #[serde(default = "default_emergency_temp")]
     pub emergency_temp_celsius: f32,
     #[serde(default)]
+    pub emergency: EmergencyConfig,
+    #[serde(default)]
     pub fan_control_enabled: bool,
+    /// Temperature-to-duty curves, one per PWM channel. Only applied while
+    /// `fan_control_enabled` is set.
+    #[serde(default)]
+    pub fan_curves: Vec<FanCurve>,
+    /// Sensor(s) behind "CPU temperature" everywhere: dashboard,
+    /// notifications, fan curves and other thermal automation.
+    #[serde(default)]
+    pub cpu_temp_source: TempSource,
+    /// Closed-loop limiter holding the CPU temperature at a target.
+    #[serde(default)]
+    pub limiter: ThermalLimiterConfig,
+    /// Temperature history used for the time-to-throttle estimate.
+    #[serde(default = "default_forecast_window")]
+    pub forecast_window_seconds: u64,
+}
+
+impl ThermalConfig {
+    /// This app's own intervention points, forecast alongside the
+    /// firmware trips.
+    pub fn app_thresholds(&self) -> Vec<ThrottleThreshold> {
+        let mut out = Vec::new();
+        if self.limiter.enabled {
+            out.push(ThrottleThreshold { name: "limiter target".into(), temp_celsius: self.limiter.target_celsius });
+        }
+        if self.emergency.enabled {
+            out.push(ThrottleThreshold { name: "emergency clamp".into(), temp_celsius: self.emergency_temp_celsius });
+        }
+        out
+    }
+}
+
+/// `[battery]`: charge limits, re-applied by the background service at
+/// start, on config change and whenever the battery reports different values.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BatteryConfig {
+    #[serde(default)]
+    pub manage_charge_thresholds: bool,
+    #[serde(default = "default_charge_start")]
+    pub charge_start_threshold: u8,
+    #[serde(default = "default_charge_end")]
+    pub charge_end_threshold: u8,
+    /// `auto`, `inhibit-charge` or `force-discharge`; empty leaves it alone.
+    #[serde(default)]
+    pub charge_behaviour: String,
+}
+
+impl BatteryConfig {
+    pub fn thresholds(&self) -> ChargeThresholds {
+        ChargeThresholds { start: self.charge_start_threshold, end: self.charge_end_threshold }
+    }
+
+    pub fn behaviour(&self) -> Option<&str> {
+        Some(self.charge_behaviour.trim()).filter(|b| !b.is_empty())
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -107,6 +198,12 @@ pub struct MonitoringConfig {
     pub graph_history_seconds: u64,
     #[serde(default = "default_true")]
     pub show_per_core_stats: bool,
+    /// Smoothing for the displayed temperature and power: `none`, `ema` or `median`.
+    #[serde(default = "default_smoothing")]
+    pub smoothing: String,
+    /// Samples in the median window, or the EMA span.
+    #[serde(default = "default_smoothing_window")]
+    pub smoothing_window: usize,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -124,6 +221,8 @@ pub struct LoggingConfig {
 fn default_true() -> bool { true }
 fn default_polling_interval() -> u64 { 1000 }
 fn default_temp_unit() -> String { "celsius".to_string() }
+fn default_smoothing() -> String { "none".to_string() }
+fn default_smoothing_window() -> usize { 5 }
 fn default_notification_level() -> String { "important".to_string() }
 fn default_ac_profile() -> String { "performance".to_string() }
 fn default_battery_profile() -> String { "balanced".to_string() }
@@ -131,8 +230,12 @@ fn default_temp_high() -> f32 { 80.0 }
 fn default_temp_low() -> f32 { 60.0 }
 fn default_load_high() -> f32 { 70.0 }
 fn default_load_low() -> f32 { 30.0 }
+fn default_power_source_debounce() -> u64 { 2000 }
 fn default_max_temp() -> f32 { 90.0 }
 fn default_emergency_temp() -> f32 { 95.0 }
+fn default_forecast_window() -> u64 { 30 }
+fn default_charge_start() -> u8 { 40 }
+fn default_charge_end() -> u8 { 80 }
 fn default_graph_history() -> u64 { 300 }
 fn default_log_level() -> String { "info".to_string() }
 fn default_log_path() -> String {
@@ -171,16 +274,67 @@ impl Default for AutoTuneConfig {
             temp_threshold_low: 60.0,
             load_threshold_high: 70.0,
             load_threshold_low: 30.0,
+            power_source_debounce_ms: default_power_source_debounce(),
+            battery_rules: Vec::new(),
+            app_rules: Vec::new(),
+            schedule: Vec::new(),
         }
     }
 }
 
+impl AutoTuneConfig {
+    /// Auto-switching as rules. Priority bands, lowest first: AC/battery
+    /// profile (0), schedules (1000 + priority), low-battery rules (2000,
+    /// earlier rules higher), running applications (3000 + priority).
+    pub fn default_rules(&self) -> Vec<Rule> {
+        let profile = |name: &str, turbo| Actions { profile: Some(name.to_string()), turbo, ..Default::default() };
+        let power = |p| Conditions { power: Some(p), ..Default::default() };
+        let mut rules = vec![
+            Rule::new("AC power", 0, power(PowerState::Ac), profile(&self.ac_profile, None)),
+            Rule::new("Battery", 0, power(PowerState::Battery), profile(&self.battery_profile, None)),
+        ];
+        rules.extend(self.schedule.iter().map(|s| {
+            let when = Conditions { days: Some(s.days.clone()), time: Some(s.time_range()), ..Default::default() };
+            Rule::new(&s.name, 1000 + s.priority, when, profile(&s.profile, s.turbo))
+        }));
+        let n = self.battery_rules.len() as i32;
+        rules.extend(self.battery_rules.iter().enumerate().map(|(i, b)| {
+            let when = Conditions { battery_below: Some(b.below_percent), ..power(PowerState::Battery) };
+            Rule {
+                hysteresis: b.hysteresis_percent,
+                ..Rule::new(&format!("Battery below {:.0}%", b.below_percent), 2000 + n - i as i32, when, profile(&b.profile, b.turbo))
+            }
+        }));
+        rules.extend(self.app_rules.iter().map(|a| {
+            let when = Conditions { process: Some(a.matcher()), ..Default::default() };
+            Rule::new(&a.name, 3000 + a.priority, when, profile(&a.profile, a.turbo))
+        }));
+        rules
+    }
+}
+
 impl Default for ThermalConfig {
     fn default() -> Self {
         Self {
             max_temp_celsius: 90.0,
             emergency_temp_celsius: 95.0,
+            emergency: EmergencyConfig::default(),
             fan_control_enabled: false,
+            fan_curves: Vec::new(),
+            cpu_temp_source: TempSource::Auto,
+            limiter: ThermalLimiterConfig::default(),
+            forecast_window_seconds: 30,
+        }
+    }
+}
+
+impl Default for BatteryConfig {
+    fn default() -> Self {
+        Self {
+            manage_charge_thresholds: false,
+            charge_start_threshold: 40,
+            charge_end_threshold: 80,
+            charge_behaviour: String::new(),
         }
     }
 }
@@ -191,6 +345,8 @@ impl Default for MonitoringConfig {
             enable_graphs: true,
             graph_history_seconds: 300,
             show_per_core_stats: true,
+            smoothing: "none".to_string(),
+            smoothing_window: 5,
         }
     }
 }
@@ -212,9 +368,11 @@ impl Default for Config {
             general: GeneralConfig::default(),
             auto_tune: AutoTuneConfig::default(),
             thermal: ThermalConfig::default(),
+            battery: BatteryConfig::default(),
             monitoring: MonitoringConfig::default(),
             logging: LoggingConfig::default(),
             custom_profiles: Vec::new(),
+            rules: Vec::new(),
         }
     }
 }
@@ -222,13 +380,106 @@ impl Default for Config {
 pub struct ConfigManager {
     config: Config,
     config_path: PathBuf,
+    /// `ServiceSections` as last loaded or handed to the service, so `save`
+    /// only sends them when they changed.
+    synced: RefCell<serde_json::Value>,
+}
+
+/// What the background service runs — thermal automation, auto-switch
+/// rules, `[[rules]]`, custom profiles and the critical temperature
+/// notification. `save` sends these to it; charge thresholds are saved to
+/// its config directly.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ServiceSections {
+    pub thermal: ThermalConfig,
+    pub auto_tune: AutoTuneConfig,
+    pub rules: Vec<Rule>,
+    pub custom_profiles: Vec<Profile>,
+    pub critical_temp_notify: bool,
+}
+
+impl ServiceSections {
+    pub fn of(config: &Config) -> Self {
+        Self {
+            thermal: config.thermal.clone(),
+            auto_tune: config.auto_tune.clone(),
+            rules: config.rules.clone(),
+            custom_profiles: config.custom_profiles.clone(),
+            critical_temp_notify: config.general.critical_temp_notify,
+        }
+    }
+
+    pub fn apply_to(self, config: &mut Config) {
+        config.thermal = self.thermal;
+        config.auto_tune = self.auto_tune;
+        config.rules = self.rules;
+        config.custom_profiles = self.custom_profiles;
+        config.general.critical_temp_notify = self.critical_temp_notify;
+    }
+
+    fn fingerprint(config: &Config) -> serde_json::Value {
+        serde_json::to_value(Self::of(config)).unwrap_or_default()
+    }
+}
+
+impl Config {
+    /// The profile turbo and frequency limits return to when the rule that
+    /// set them ends without another rule picking a profile: the last one
+    /// applied by hand, else the AC or battery profile.
+    pub fn baseline_profile(&self, on_ac: Option<bool>) -> Option<String> {
+        let last = self.general.last_profile.trim();
+        if !last.is_empty() {
+            return Some(last.to_string());
+        }
+        on_ac.map(|ac| if ac { self.auto_tune.ac_profile.clone() } else { self.auto_tune.battery_profile.clone() })
+    }
+
+    /// Built-in rules from `[auto_tune]` (when enabled) and the critical
+    /// temperature notification, then `[[rules]]`. A user rule with a
+    /// built-in rule's name replaces it.
+    pub fn effective_rules(&self) -> Vec<Rule> {
+        let mut rules = Vec::new();
+        if self.auto_tune.enabled {
+            rules = self.auto_tune.default_rules();
+        }
+        if self.general.critical_temp_notify {
+            let when = Conditions { temp_above: Some(self.thermal.max_temp_celsius), ..Default::default() };
+            let then = Actions {
+                notify: Some("CPU is at {temp} — consider switching to Power Saver profile.".into()),
+                ..Default::default()
+            };
+            rules.push(Rule { cooldown_seconds: 60, hysteresis: 2.0, ..Rule::new("CPU Temperature Critical!", 0, when, then) });
+        }
+        for rule in &self.rules {
+            match rules.iter_mut().find(|r| r.name == rule.name) {
+                Some(builtin) => *builtin = rule.clone(),
+                None => rules.push(rule.clone()),
+            }
+        }
+        rules
+    }
 }
 
 impl ConfigManager {
     pub fn new() -> Result<Self> {
         let config_path = Self::get_config_path()?;
         let config = Self::load_config(&config_path)?;
-        Ok(Self { config, config_path })
+        Ok(Self::with(config, config_path))
+    }
+
+    fn with(config: Config, config_path: PathBuf) -> Self {
+        let synced = RefCell::new(ServiceSections::fingerprint(&config));
+        Self { config, config_path, synced }
+    }
+
+    /// The config the background service runs from, for settings only the
+    /// service keeps applied. Writing it needs root.
+    pub fn service() -> Result<Self> {
+        let dir = PathBuf::from(SERVICE_CONFIG_DIR);
+        fs::create_dir_all(&dir).context("Failed to create config directory")?;
+        let config_path = dir.join("config.toml");
+        let config = Self::load_config(&config_path)?;
+        Ok(Self::with(config, config_path))
     }
 
     fn get_config_path() -> Result<PathBuf> {
@@ -258,11 +509,47 @@ impl ConfigManager {
         }
     }
 
+    /// Writes the config. When the sections the background service acts on
+    /// changed, they are sent to it over its control socket, so edits take
+    /// effect there rather than only in this file. A failed hand-over is
+    /// logged and retried on the next save; the file is saved regardless.
     pub fn save(&self) -> Result<()> {
+        self.write()?;
+        if self.is_service() {
+            return Ok(());
+        }
+        let now = ServiceSections::fingerprint(&self.config);
+        if *self.synced.borrow() == now {
+            return Ok(());
+        }
+        match crate::backend::control::sync_config(ServiceSections::of(&self.config)) {
+            Ok(true) => *self.synced.borrow_mut() = now,
+            Ok(false) => {}
+            Err(e) => log::warn!("Failed to update the background service's config: {:#}", e),
+        }
+        Ok(())
+    }
+
+    fn write(&self) -> Result<()> {
         let config_str = toml::to_string_pretty(&self.config)?;
         fs::write(&self.config_path, config_str).context("Failed to save config file")
     }
 
+    fn is_service(&self) -> bool {
+        self.config_path.starts_with(SERVICE_CONFIG_DIR)
+    }
+
+    pub fn path(&self) -> &PathBuf {
+        &self.config_path
+    }
+
+    /// Re-reads the config file, keeping the current config if it fails to parse.
+    pub fn reload(&mut self) -> Result<()> {
+        self.config = Self::load_config(&self.config_path)?;
+        *self.synced.get_mut() = ServiceSections::fingerprint(&self.config);
+        Ok(())
+    }
+
     pub fn get_config(&self) -> &Config {
         &self.config
     }
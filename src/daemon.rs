@@ -0,0 +1,283 @@
+//! Background service (`cpu-power-manager service`): runs the thermal
+//! automation — fan curves, the thermal limiter and the emergency clamp —
+//! and the profile rules (AC/battery, schedules, apps, `[[rules]]`)
+//! without the GUI.
+
+use anyhow::{Context, Result};
+use std::fs;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, SystemTime};
+use crate::backend::{CpuManager, EmergencyGuard, PowerSourceWatcher, PowerSupplyReader, FanController, ThermalLimiter, RuleEngine, ThermalManager, ThrottleForecaster};
+use crate::backend::auto_tune::{self, Selection};
+use crate::backend::control::{self, ControlServer};
+use crate::backend::cpu::CpuUsageTracker;
+use crate::backend::emergency::EmergencyEvent;
+use crate::backend::fixed_freq;
+use crate::backend::overrides::OverrideWatcher;
+use crate::backend::profile::ProfileManager;
+use crate::backend::rules::Inputs;
+use crate::backend::schedule::ResumeDetector;
+use crate::backend::service_status::ServiceStatus;
+use crate::config::ConfigManager;
+
+pub const PID_FILE: &str = "/run/cpu-power-manager/daemon.pid";
+
+/// Ticks between charge threshold checks; failures back off up to 16×.
+const CHARGE_INTERVAL: u64 = 60;
+
+static STOP: AtomicBool = AtomicBool::new(false);
+static RELOAD: AtomicBool = AtomicBool::new(false);
+
+extern "C" fn on_stop(_: libc::c_int) { STOP.store(true, Ordering::SeqCst); }
+extern "C" fn on_reload(_: libc::c_int) { RELOAD.store(true, Ordering::SeqCst); }
+
+/// True when a service process recorded in the pidfile is still alive.
+/// The GUI uses this to leave thermal automation to the service. The
+/// process name must match ours, so a stale pidfile whose pid has been
+/// reused by something else doesn't count.
+pub fn is_running() -> bool {
+    let comm = |pid: &str| fs::read_to_string(format!("/proc/{}/comm", pid)).ok();
+    fs::read_to_string(PID_FILE)
+        .ok()
+        .and_then(|s| s.trim().parse::<u32>().ok())
+        .filter(|&pid| pid != std::process::id())
+        .and_then(|pid| comm(&pid.to_string()))
+        .is_some_and(|theirs| comm("self").is_some_and(|ours| ours == theirs))
+}
+
+fn install_signal_handlers() -> Result<()> {
+    use nix::sys::signal::{sigaction, SaFlags, SigAction, SigHandler, SigSet, Signal};
+    let stop = SigAction::new(SigHandler::Handler(on_stop), SaFlags::empty(), SigSet::empty());
+    let reload = SigAction::new(SigHandler::Handler(on_reload), SaFlags::empty(), SigSet::empty());
+    // SAFETY: the handlers only store to atomics.
+    unsafe {
+        sigaction(Signal::SIGINT, &stop)?;
+        sigaction(Signal::SIGTERM, &stop)?;
+        sigaction(Signal::SIGHUP, &reload)?;
+    }
+    Ok(())
+}
+
+fn config_mtime(cfg: &ConfigManager) -> Option<SystemTime> {
+    fs::metadata(cfg.path()).and_then(|m| m.modified()).ok()
+}
+
+/// Built-in profiles plus the custom ones from the config.
+pub fn profile_manager(cfg: &ConfigManager) -> ProfileManager {
+    let mut pm = ProfileManager::new();
+    for p in cfg.get_config().custom_profiles.iter().cloned() {
+        pm.add_profile(p);
+    }
+    pm
+}
+
+pub fn run() -> Result<()> {
+    if is_running() {
+        anyhow::bail!("Service is already running (see {})", PID_FILE);
+    }
+    if let Some(dir) = Path::new(PID_FILE).parent() {
+        fs::create_dir_all(dir).ok();
+    }
+    fs::write(PID_FILE, std::process::id().to_string())
+        .with_context(|| format!("Failed to write {}", PID_FILE))?;
+    install_signal_handlers()?;
+    crate::backend::fan::install_panic_hook();
+
+    let result = service_loop();
+
+    let _ = fs::remove_file(PID_FILE);
+    ServiceStatus::remove();
+    crate::backend::fan::restore_automatic();
+    log::info!("Background service stopped");
+    result
+}
+
+fn service_loop() -> Result<()> {
+    let cpu = CpuManager::new()?;
+    let mut thermal = ThermalManager::new()?;
+    let mut cfg = ConfigManager::new()?;
+    let mut mtime = config_mtime(&cfg);
+    let mut fans = FanController::new();
+    let mut limiter: Option<ThermalLimiter> = None;
+    let mut emergency = EmergencyGuard::new();
+    let window = Duration::from_secs(cfg.get_config().thermal.forecast_window_seconds.max(5));
+    let mut forecaster = ThrottleForecaster::new(window, Vec::new());
+    let mut profiles = profile_manager(&cfg);
+    let power_events = PowerSourceWatcher::spawn(Duration::from_millis(cfg.get_config().auto_tune.power_source_debounce_ms));
+    let mut engine = RuleEngine::new();
+    let mut overrides = OverrideWatcher::default();
+    let mut usage = CpuUsageTracker::new();
+    let mut resume = ResumeDetector::default();
+    let (mut on_ac, mut charge) = (None, None);
+    let mut last_switch: Option<Selection> = None;
+    let mut status = ServiceStatus::new(chrono::Local::now().timestamp());
+    let mut tick: u64 = 0;
+    // Lets `run` and the benchmarks push overrides without root.
+    let control = ControlServer::bind()
+        .map_err(|e| log::warn!("Override requests from other users disabled: {:#}", e))
+        .ok();
+    // Next tick to check the charge thresholds, and the wait after a failure.
+    let (mut charge_due, mut charge_backoff) = (0u64, CHARGE_INTERVAL);
+    log::info!("Background service started, config {}", cfg.path().display());
+
+    while !STOP.load(Ordering::SeqCst) {
+        let changed = RELOAD.swap(false, Ordering::SeqCst) || config_mtime(&cfg) != mtime;
+        if changed {
+            mtime = config_mtime(&cfg);
+            match cfg.reload() {
+                Ok(()) => log::info!("Configuration reloaded"),
+                Err(e) => log::warn!("Keeping previous configuration: {:#}", e),
+            }
+            profiles = profile_manager(&cfg);
+        }
+        let config = cfg.get_config().clone();
+        thermal.set_source(config.thermal.cpu_temp_source.clone());
+
+        // Firmware resets charge thresholds on some machines at boot or
+        // resume; put ours back whenever they drift.
+        let bat = &config.battery;
+        if bat.manage_charge_thresholds && (changed || tick >= charge_due) {
+            if changed { charge_backoff = CHARGE_INTERVAL; }
+            charge_due = tick + CHARGE_INTERVAL;
+            if !PowerSupplyReader::charge_settings_match(bat.thresholds(), bat.behaviour()) {
+                match PowerSupplyReader::apply_charge_settings(bat.thresholds(), bat.behaviour()) {
+                    Ok(()) => {
+                        log::info!("Applied battery charge thresholds {}–{}%", bat.charge_start_threshold, bat.charge_end_threshold);
+                        charge_backoff = CHARGE_INTERVAL;
+                    }
+                    Err(e) => {
+                        log::warn!("Battery charge thresholds not applied, retrying in {} ticks: {:#}", charge_backoff, e);
+                        charge_due = tick + charge_backoff;
+                        charge_backoff = (charge_backoff * 2).min(CHARGE_INTERVAL * 16);
+                    }
+                }
+            }
+        }
+
+        // After a resume, re-apply even if no rule changed.
+        let mut source_changed = resume.check();
+        if source_changed {
+            log::info!("Resumed from suspend, re-evaluating rules");
+            last_switch = None;
+        }
+        while let Ok(event) = power_events.try_recv() {
+            on_ac = Some(event.on_ac);
+            source_changed = true;
+        }
+
+        let temp = thermal.get_cpu_temperature()
+            .map_err(|e| log::warn!("Failed to read CPU temperature: {}", e))
+            .ok();
+        // Trips can be moved at runtime; re-read them now and then.
+        if tick.is_multiple_of(30) {
+            let mut thresholds = thermal.throttle_thresholds();
+            thresholds.extend(config.thermal.app_thresholds());
+            forecaster.set_thresholds(thresholds);
+        }
+        if let Some(temp) = temp {
+            forecaster.push(temp);
+        }
+
+        // Rules: AC/battery switching, low-battery rules, schedules, app rules
+        // and `[[rules]]`, with temporary overrides on top. Notifications,
+        // the emergency clamp's included, go through the status file to the
+        // GUI, which has the desktop session.
+        let rules = config.effective_rules();
+        let load = usage.get_usage();
+        if source_changed || tick.is_multiple_of(10) {
+            let bat = on_ac.map_or_else(PowerSupplyReader::read, PowerSupplyReader::read_with_source);
+            charge = bat.present.then_some(bat.charge_percent);
+        }
+        let inputs = Inputs {
+            on_ac,
+            battery_percent: charge,
+            temp_celsius: temp,
+            load_percent: Some(load),
+            now: chrono::Local::now().naive_local(),
+        };
+        let eval = engine.evaluate(&rules, &inputs);
+        for fired in &eval.fired {
+            fired.run_command();
+        }
+        let released = fixed_freq::recover_stale(&profiles, &cpu);
+        if let Some(control) = &control {
+            control.poll(|req, peer| control::handle(req, peer, &profiles, &cpu));
+        }
+        let (stack, restored) = overrides.update(&cpu);
+        if restored || released {
+            last_switch = None;
+        }
+        let target = stack.overlay(eval.target, chrono::Local::now().timestamp());
+        let baseline = config.baseline_profile(on_ac);
+        match auto_tune::restore_baseline(target, last_switch.as_ref(), baseline.as_deref()) {
+            Some(selection) if !last_switch.as_ref().is_some_and(|l| l.same_target(&selection)) => {
+                match selection.apply(&profiles, &cpu) {
+                    Ok(_) => log::info!("Applied {} ({})", selection.summary(), selection.reason),
+                    Err(e) => log::warn!("Applying rules failed: {:#}", e),
+                }
+                last_switch = Some(selection);
+            }
+            Some(_) => {}
+            None => last_switch = None,
+        }
+        tick += 1;
+        if let Some(fc) = forecaster.forecast() {
+            log::debug!(
+                "Throttle forecast at {:.1}°C: {} ({:+.2}°C/s)",
+                fc.temp_celsius, fc.summary(), fc.rate_celsius_per_sec
+            );
+        }
+        if config.thermal.fan_control_enabled && !config.thermal.fan_curves.is_empty() {
+            if let Some(temp) = temp {
+                fans.update(&config.thermal.fan_curves, temp);
+            }
+        } else {
+            fans.release();
+        }
+        let emergency_event = temp.and_then(|temp| emergency.update(
+            &config.thermal.emergency, config.thermal.emergency_temp_celsius, temp, &cpu,
+        ));
+        // A fixed-frequency lock pauses the limiter, but a thermal emergency
+        // still clamps; say so, since the lock no longer holds.
+        let fixed = fixed_freq::FixedLock::load();
+        if let (Some(EmergencyEvent::Triggered { .. }), Some(lock)) = (&emergency_event, &fixed) {
+            log::warn!("Thermal emergency overrides the fixed-frequency lock ({})", lock.describe());
+        }
+        // The emergency clamp wins; the limiter resumes once it is lifted.
+        let lead = config.thermal.limiter.lead_seconds;
+        let lim_temp = temp
+            .filter(|_| !emergency.is_active() && fixed.is_none())
+            .map(|t| forecaster.predict(lead).unwrap_or(t).max(t));
+        let limiting = limiter.is_some();
+        ThermalLimiter::drive(&mut limiter, &config.thermal.limiter, lim_temp, &cpu);
+        // Stopping put back the limits from when the limiter started; the
+        // rules and overrides re-apply what they select now on the next tick.
+        if limiting && limiter.is_none() {
+            last_switch = None;
+        }
+
+        let mut next = status.clone();
+        next.set_active(&eval.active);
+        next.set_applied(last_switch.as_ref());
+        next.push_notices(&eval.fired);
+        if let Some(event) = &emergency_event {
+            next.push_emergency(event);
+        }
+        if next != status {
+            if let Err(e) = next.save() {
+                log::warn!("Failed to write service status: {:#}", e);
+            }
+            status = next;
+        }
+
+        std::thread::sleep(Duration::from_millis(config.general.polling_interval_ms.max(250)));
+    }
+
+    emergency.release(&cpu);
+    if let Some(mut l) = limiter.take() {
+        l.release(&cpu);
+    }
+    fans.release();
+    Ok(())
+}
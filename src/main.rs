@@ -1,12 +1,13 @@
 mod app;
 mod backend;
 mod config;
+mod daemon;
 mod system;
 mod ui;
 mod utils;
 mod tray;
 
-use anyhow::Result;
+use anyhow::{Context, Result};
 use clap::{Parser, Subcommand};
 use env_logger::Env;
 use gtk4::prelude::*;
@@ -34,6 +35,36 @@ struct Cli {
 enum Commands {
     /// Show current CPU status
     Status,
+    /// Show CPU power draw per RAPL domain (package, core, uncore, DRAM, psys)
+    Power {
+        /// Sampling interval in milliseconds
+        #[arg(short, long, default_value_t = 1000)]
+        interval: u64,
+    },
+    /// Rank the sensors that could serve as the CPU temperature
+    TempSources,
+    /// List thermal cooling devices and the zone trips bound to them
+    Cooling,
+    /// Force a cooling device's cur_state (for testing)
+    SetCoolingState { device: usize, state: u64 },
+    /// Show or set battery charge thresholds (no options: show current)
+    ChargeThresholds {
+        /// full (0–100), balanced (75–90) or conserve (40–80)
+        #[arg(long, conflicts_with_all = ["start", "end"])]
+        preset: Option<String>,
+        /// Resume charging below this percentage
+        #[arg(long, requires = "end")]
+        start: Option<u8>,
+        /// Stop charging at this percentage
+        #[arg(long)]
+        end: Option<u8>,
+        /// charge_behaviour: auto, inhibit-charge or force-discharge
+        #[arg(long)]
+        behaviour: Option<String>,
+        /// Also store in /etc/cpu-power-manager/config.toml so the background service keeps them applied
+        #[arg(long)]
+        save: bool,
+    },
     /// Set CPU governor
     SetGovernor { governor: String },
     /// Set CPU frequency (in MHz)
@@ -42,7 +73,139 @@ enum Commands {
     SetTurbo { enabled: bool },
     /// Apply a profile
     ApplyProfile { name: String },
-    /// Start the background service
+    /// List the effective rules, which hold right now and why
+    Rules,
+    /// Temporarily change the profile, turbo or limits (no options: list)
+    Override {
+        /// Profile to switch to
+        #[arg(long)]
+        profile: Option<String>,
+        /// on or off
+        #[arg(long, value_parser = parse_on_off)]
+        turbo: Option<bool>,
+        /// Minimum frequency in MHz
+        #[arg(long)]
+        min_freq: Option<u32>,
+        /// Maximum frequency in MHz
+        #[arg(long)]
+        max_freq: Option<u32>,
+        /// How long, e.g. 30m, 1h30m, 90s
+        #[arg(long = "for", conflicts_with_all = ["until", "pid"])]
+        duration: Option<String>,
+        /// Until this local time (HH:MM)
+        #[arg(long, conflicts_with = "pid")]
+        until: Option<String>,
+        /// Until this process exits
+        #[arg(long)]
+        pid: Option<i32>,
+        /// Remove one override by id
+        #[arg(long, conflicts_with = "clear")]
+        remove: Option<u32>,
+        /// Remove every override and restore the previous state
+        #[arg(long)]
+        clear: bool,
+    },
+    /// Run a command under a profile or overrides, then restore the previous state
+    /// (needs root unless the background service is running)
+    Run {
+        /// Profile to run under
+        #[arg(long)]
+        profile: Option<String>,
+        /// on or off
+        #[arg(long, value_parser = parse_on_off)]
+        turbo: Option<bool>,
+        /// Minimum frequency in MHz
+        #[arg(long)]
+        min_freq: Option<u32>,
+        /// Maximum frequency in MHz
+        #[arg(long)]
+        max_freq: Option<u32>,
+        /// The command and its arguments, after `--`
+        #[arg(trailing_var_arg = true, allow_hyphen_values = true, required = true)]
+        command: Vec<String>,
+    },
+    /// Run a command and report its time, energy, frequency and temperature
+    Measure {
+        /// Print JSON instead of text
+        #[arg(long)]
+        json: bool,
+        /// Sampling interval in milliseconds
+        #[arg(long, default_value_t = 250)]
+        interval: u64,
+        /// The command and its arguments, after `--`
+        #[arg(trailing_var_arg = true, allow_hyphen_values = true, required = true)]
+        command: Vec<String>,
+    },
+    /// Run a workload under each profile and compare performance per watt
+    Benchmark {
+        /// Profiles to compare, comma-separated (default: all)
+        #[arg(long, value_delimiter = ',')]
+        profiles: Vec<String>,
+        /// Length of the built-in workload in seconds
+        #[arg(long, default_value_t = 30)]
+        seconds: u64,
+        /// Threads for the built-in workload (default: one per CPU)
+        #[arg(long)]
+        threads: Option<usize>,
+        /// Rest between runs in seconds
+        #[arg(long, default_value_t = 30)]
+        cooldown: u64,
+        /// Print JSON instead of a table
+        #[arg(long)]
+        json: bool,
+        /// Show saved results instead of running
+        #[arg(long)]
+        list: bool,
+        /// A command to time instead of the built-in workload, after `--`
+        #[arg(trailing_var_arg = true, allow_hyphen_values = true)]
+        command: Vec<String>,
+    },
+    /// Sweep the max frequency under a fixed load and suggest efficient limits
+    Calibrate {
+        /// Steps between the hardware limits when the driver lists no frequencies
+        #[arg(long, default_value_t = 8)]
+        steps: usize,
+        /// Measured seconds per step
+        #[arg(long, default_value_t = 10)]
+        step_seconds: u64,
+        /// Seconds of load before each measurement
+        #[arg(long, default_value_t = 3)]
+        settle: u64,
+        /// Threads for the load (default: one per CPU)
+        #[arg(long)]
+        threads: Option<usize>,
+        /// Print JSON instead of a table
+        #[arg(long)]
+        json: bool,
+        /// Show this machine's saved curve instead of sweeping
+        #[arg(long)]
+        show: bool,
+    },
+    /// Lock every core to one frequency with turbo off, for reproducible benchmarks
+    FixedFreq {
+        /// Frequency in MHz (the nearest one the driver offers)
+        #[arg(long, required_unless_present_any = ["unlock", "status"])]
+        freq: Option<u32>,
+        /// Also disable idle states deeper than C1
+        #[arg(long)]
+        no_cstates: bool,
+        /// Also disable SMT (hyper-threading)
+        #[arg(long)]
+        no_smt: bool,
+        /// Seconds to wait at most for the temperature to settle
+        #[arg(long, default_value_t = 120)]
+        settle_timeout: u64,
+        /// Release the current lock, wherever it was taken
+        #[arg(long, conflicts_with = "status")]
+        unlock: bool,
+        /// Show the current lock and read it back
+        #[arg(long)]
+        status: bool,
+        /// Run this command under the lock, after `--`; otherwise hold it until Ctrl-C
+        #[arg(trailing_var_arg = true, allow_hyphen_values = true)]
+        command: Vec<String>,
+    },
+    /// Run fan curves and the thermal limiter in the background (as root)
     Service,
     /// Show version information
     Version,
@@ -62,6 +225,25 @@ fn main() -> Result<()> {
 
     let app = Application::builder().application_id(APP_ID).build();
 
+    // Fan curves put PWM channels into manual mode; make sure firmware
+    // control comes back however the app goes away.
+    backend::fan::install_panic_hook();
+    app.connect_shutdown(|_| {
+        backend::fan::restore_automatic();
+        // A lock taken from the Control tab ends with the window.
+        if let (Ok(cfg), Ok(cpu)) = (config::ConfigManager::new(), backend::cpu::CpuManager::new()) {
+            backend::fixed_freq::release_owned(&daemon::profile_manager(&cfg), &cpu);
+        }
+    });
+    for signal in [libc::SIGINT, libc::SIGTERM, libc::SIGHUP] {
+        let app = app.clone();
+        glib::unix_signal_add_local(signal, move || {
+            log::info!("Received signal {}, shutting down", signal);
+            app.quit();
+            glib::ControlFlow::Break
+        });
+    }
+
     app.connect_startup(|_| {
         log::info!("Application startup");
     });
@@ -77,7 +259,7 @@ fn main() -> Result<()> {
         let window = app::AppWindow::new(app);
 
         // Spawn system tray — fails gracefully if no DBus session bus (e.g. sudo without -E)
-        tray::spawn(window.window_handle());
+        tray::spawn(window.window_handle(), window.override_handler());
 
         if !minimized {
             window.present();
@@ -85,13 +267,18 @@ fn main() -> Result<()> {
     });
 
     app.run();
+    backend::fan::restore_automatic();
     Ok(())
 }
 
 fn handle_cli_command(command: Commands) -> Result<()> {
     use backend::cpu::CpuManager;
+    use utils::units::{fmt_temp, TempUnit};
 
     let cpu_manager = CpuManager::new()?;
+    if let Ok(cfg) = config::ConfigManager::new() {
+        TempUnit::set_current(TempUnit::from_config(&cfg.get_config().general.temperature_unit));
+    }
 
     match command {
         Commands::Status => {
@@ -108,6 +295,101 @@ fn handle_cli_command(command: Commands) -> Result<()> {
                 "  Turbo: {}",
                 if cpu_manager.is_turbo_enabled()? { "Enabled" } else { "Disabled" }
             );
+            if let Ok(thermal) = configured_thermal_manager() {
+                if let Ok(temp) = thermal.get_cpu_temperature() {
+                    println!("  Temperature: {} ({})", fmt_temp(temp, 1), thermal.describe_source());
+                }
+            }
+            print_battery_status(&backend::PowerSupplyReader::read());
+        }
+        Commands::TempSources => {
+            let thermal = configured_thermal_manager()?;
+            println!("CPU temperature source: {}", thermal.describe_source());
+            println!("Candidates (best first):");
+            for c in thermal.temp_candidates() {
+                let temp = c.temp_celsius.map(|t| fmt_temp(t, 1)).unwrap_or_else(|| "--".into());
+                println!("  {:>5}  {:<40} {:>8}  {}  [{}]", c.score, c.label, temp, c.reason, c.id);
+            }
+        }
+        Commands::Power { interval } => {
+            let mut tracker = backend::RaplTracker::new();
+            if tracker.domains().is_empty() {
+                anyhow::bail!("No RAPL energy counters found (powercap or amd_energy)");
+            }
+            std::thread::sleep(std::time::Duration::from_millis(interval.max(100)));
+            let breakdown = tracker.sample().unwrap_or_default().to_vec();
+            println!("CPU Power ({} ms sample):", interval.max(100));
+            for d in &breakdown {
+                let socket = d.socket.map(|s| format!("socket {}", s)).unwrap_or_default();
+                println!("  {:<16} {:<10} {:>7.2} W  [{}]", d.name, socket, d.watts, d.id);
+            }
+            if let Some(total) = backend::RaplTracker::total_w(&breakdown) {
+                println!("  Total: {:.2} W", total);
+            }
+        }
+        Commands::Cooling => {
+            let thermal = backend::ThermalManager::new()?;
+            let zones = thermal.get_all_zones()?;
+            println!("Cooling Devices:");
+            for dev in thermal.get_cooling_devices()? {
+                println!(
+                    "  cooling_device{:<3} {:<20} {}/{} ({})",
+                    dev.id, dev.type_name, dev.cur_state, dev.max_state, dev.kind.state_unit()
+                );
+                for zone in &zones {
+                    for b in zone.bindings.iter().filter(|b| b.cooling_device == dev.id) {
+                        match b.trip_point.and_then(|t| zone.trip_points.get(t)) {
+                            Some(trip) => println!(
+                                "      ← {} ({}) trip {} {} {}, zone at {}",
+                                zone.name, zone.type_name, trip.id, trip.trip_type,
+                                fmt_temp(trip.temp_celsius, 1), fmt_temp(zone.temp_celsius, 1)
+                            ),
+                            None => println!("      ← {} ({})", zone.name, zone.type_name),
+                        }
+                    }
+                }
+            }
+        }
+        Commands::SetCoolingState { device, state } => {
+            backend::ThermalManager::new()?.set_cooling_state(device, state)?;
+            println!("cooling_device{} state set to {}", device, state);
+        }
+        Commands::ChargeThresholds { preset, start, end, behaviour, save } => {
+            use backend::ChargeThresholds;
+            let thresholds = match (preset, end) {
+                (Some(name), _) => Some(ChargeThresholds::preset(&name).with_context(|| {
+                    format!("Unknown preset '{}'. Available: full, balanced, conserve", name)
+                })?),
+                (None, Some(end)) => Some(ChargeThresholds { start: start.unwrap_or(0), end }),
+                (None, None) => None,
+            };
+            if let Some(t) = thresholds {
+                backend::PowerSupplyReader::apply_charge_settings(t, behaviour.as_deref())?;
+                println!("Charge thresholds set to {}–{}%", t.start, t.end);
+                if save {
+                    // Saved where the background service reads it.
+                    let mut mgr = config::ConfigManager::service()?;
+                    let bat = &mut mgr.get_config_mut().battery;
+                    bat.manage_charge_thresholds = true;
+                    bat.charge_start_threshold = t.start;
+                    bat.charge_end_threshold = t.end;
+                    if let Some(b) = &behaviour { bat.charge_behaviour = b.clone(); }
+                    mgr.save()?;
+                    println!("Saved to {}", mgr.path().display());
+                }
+            } else if behaviour.is_some() {
+                anyhow::bail!("--behaviour needs --preset or --end");
+            } else if save {
+                anyhow::bail!("--save needs --preset or --end");
+            }
+            for b in backend::PowerSupplyReader::batteries() {
+                let fmt = |v: Option<u8>| v.map(|v| format!("{}%", v)).unwrap_or_else(|| "n/a".into());
+                println!(
+                    "{}: start {}, end {}, behaviour {}",
+                    b.name, fmt(b.charge_start_threshold), fmt(b.charge_end_threshold),
+                    b.charge_behaviour.as_deref().unwrap_or("n/a"),
+                );
+            }
         }
         Commands::SetGovernor { governor } => {
             cpu_manager.set_governor_all(&governor)?;
@@ -128,9 +410,290 @@ fn handle_cli_command(command: Commands) -> Result<()> {
             profile.apply(&cpu_manager)?;
             println!("Profile '{}' applied", name);
         }
+        Commands::Rules => {
+            let config = config::ConfigManager::new()?.get_config().clone();
+            let rules = config.effective_rules();
+            for rule in &rules {
+                rule.validate().with_context(|| format!("Rule {:?}", rule.name))?;
+            }
+            let mut usage = backend::cpu::CpuUsageTracker::new();
+            usage.get_usage();
+            std::thread::sleep(std::time::Duration::from_millis(500));
+            let bat = backend::PowerSupplyReader::read();
+            let inputs = backend::rules::Inputs {
+                on_ac: Some(bat.on_ac),
+                battery_percent: bat.present.then_some(bat.charge_percent),
+                temp_celsius: configured_thermal_manager().ok().and_then(|t| t.get_cpu_temperature().ok()),
+                load_percent: Some(usage.get_usage()),
+                now: chrono::Local::now().naive_local(),
+            };
+            let eval = backend::RuleEngine::new().evaluate(&rules, &inputs);
+            println!("Rules (highest priority first):");
+            let mut sorted: Vec<_> = rules.iter().collect();
+            sorted.sort_by_key(|r| std::cmp::Reverse(r.priority));
+            for rule in sorted {
+                let state = match eval.active.iter().find(|m| m.name == rule.name) {
+                    Some(m) => format!("active: {}", m.why),
+                    None if !rule.enabled => "disabled".into(),
+                    None => "-".into(),
+                };
+                println!("  {:>5}  {:<28} {}", rule.priority, rule.name, state);
+            }
+            match eval.target {
+                Some(t) => println!("Target: {}\n  because {}", t.summary(), t.reason),
+                None => println!("Target: none (no active rule sets a profile, turbo or limits)"),
+            }
+        }
+        Commands::Override { profile, turbo, min_freq, max_freq, duration, until, pid, remove, clear } => {
+            use backend::overrides::{self, Expiry, Override, OverrideStack};
+            let config_manager = config::ConfigManager::new()?;
+            let profiles = daemon::profile_manager(&config_manager);
+            // The service applies the stack itself; otherwise do it here.
+            let apply = !daemon::is_running();
+            if clear || remove.is_some() {
+                let n = overrides::remove(remove, &profiles, &cpu_manager, apply)?;
+                println!("Removed {} override(s)", n);
+            } else if profile.is_some() || turbo.is_some() || min_freq.is_some() || max_freq.is_some() {
+                let expiry = match (duration, until, pid) {
+                    (Some(d), _, _) => Expiry::after(overrides::parse_duration(&d)?),
+                    (_, Some(t), _) => Expiry::until(&t)?,
+                    (_, _, Some(pid)) => Expiry::process(pid)?,
+                    _ => Expiry::Manual,
+                };
+                if let Some(name) = &profile {
+                    if !profiles.get_profiles().iter().any(|p| p.name.eq_ignore_ascii_case(name)) {
+                        anyhow::bail!("No profile named {:?}", name);
+                    }
+                }
+                let o = Override { profile, turbo, min_freq_mhz: min_freq, max_freq_mhz: max_freq, ..Override::new(expiry) };
+                let id = overrides::push(o, &profiles, &cpu_manager, apply)?;
+                println!("Override #{} active", id);
+                if apply {
+                    println!("Note: expiry is handled by the background service or the GUI; without either, run `override --clear` later");
+                }
+            }
+            let lines = OverrideStack::load().lines(chrono::Local::now().timestamp());
+            if lines.is_empty() {
+                println!("No overrides active");
+            }
+            for line in lines {
+                println!("  {}", line);
+            }
+        }
+        Commands::Run { profile, turbo, min_freq, max_freq, command } => {
+            use backend::control;
+            use backend::overrides::{Expiry, Override};
+            let profiles = daemon::profile_manager(&config::ConfigManager::new()?);
+            if let Some(name) = &profile {
+                if !profiles.get_profiles().iter().any(|p| p.name.eq_ignore_ascii_case(name)) {
+                    anyhow::bail!("No profile named {:?}", name);
+                }
+            }
+            // An override tied to this process: if it is killed outright, the
+            // background service (or GUI) still notices and restores.
+            let expiry = Expiry::process(std::process::id() as i32)?;
+            let o = Override { profile, turbo, min_freq_mhz: min_freq, max_freq_mhz: max_freq, ..Override::new(expiry) };
+            let id = control::push_override(o, &profiles, &cpu_manager)?;
+            let status = backend::launch::run(&command);
+            if let Err(e) = control::remove_override(id, &profiles, &cpu_manager) {
+                log::warn!("Failed to restore the previous state: {:#}", e);
+            }
+            let code = match status {
+                Ok(status) => backend::launch::exit_code(status),
+                Err(e) => {
+                    eprintln!("{:#}", e);
+                    127
+                }
+            };
+            std::process::exit(code);
+        }
+        Commands::Measure { json, interval, command } => {
+            let source = config::ConfigManager::new()
+                .map(|c| c.get_config().thermal.cpu_temp_source.clone())
+                .unwrap_or_default();
+            let interval = std::time::Duration::from_millis(interval.max(100));
+            let m = backend::measure::measure(&command, interval, source)?;
+            if json {
+                let mut value = serde_json::to_value(&m)?;
+                value["command"] = serde_json::json!(command);
+                println!("{}", serde_json::to_string_pretty(&value)?);
+            } else {
+                println!("Command:         {}", command.join(" "));
+                m.print();
+            }
+            std::process::exit(m.exit_code);
+        }
+        Commands::Benchmark { profiles, seconds, threads, cooldown, json, list, command } => {
+            use backend::benchmark::{self, BenchmarkOptions, BenchmarkReport, Workload};
+            if list {
+                let reports = BenchmarkReport::load_all();
+                if json {
+                    println!("{}", serde_json::to_string_pretty(&reports)?);
+                } else if reports.is_empty() {
+                    println!("No saved benchmark results");
+                } else {
+                    for report in &reports {
+                        report.print();
+                        println!();
+                    }
+                }
+                return Ok(());
+            }
+            let cfg = config::ConfigManager::new()?;
+            let workload = if command.is_empty() {
+                let threads = threads.unwrap_or_else(|| std::thread::available_parallelism().map_or(1, |n| n.get()));
+                Workload::Builtin { seconds: seconds.max(1), threads }
+            } else {
+                Workload::Command { argv: command }
+            };
+            let opts = BenchmarkOptions {
+                profiles,
+                workload,
+                cooldown: std::time::Duration::from_secs(cooldown),
+                interval: std::time::Duration::from_millis(250),
+                source: cfg.get_config().thermal.cpu_temp_source.clone(),
+            };
+            // Interrupts stop the run once the current profile is removed;
+            // if this process dies outright, the service or GUI restores.
+            backend::launch::catch_interrupts()?;
+            let report = benchmark::run(&opts, &daemon::profile_manager(&cfg), &cpu_manager, |line| eprintln!("{}", line));
+            if backend::launch::interrupted() {
+                eprintln!("Interrupted; restored the previous settings");
+                std::process::exit(130);
+            }
+            let report = report?;
+            let saved = report.save();
+            if json {
+                println!("{}", serde_json::to_string_pretty(&report)?);
+            } else {
+                println!();
+                report.print();
+            }
+            match saved {
+                Ok(path) => eprintln!("Saved to {}", path.display()),
+                Err(e) => eprintln!("Failed to save the results: {:#}", e),
+            }
+            if report.results.iter().any(|r| r.failed()) {
+                std::process::exit(1);
+            }
+        }
+        Commands::Calibrate { steps, step_seconds, settle, threads, json, show } => {
+            use backend::calibrate::{self, EfficiencyCurve, SweepOptions};
+            let curve = if show {
+                EfficiencyCurve::load().context("No saved calibration for this machine; run `cpu-power-manager calibrate`")?
+            } else {
+                let cfg = config::ConfigManager::new()?;
+                let opts = SweepOptions {
+                    steps,
+                    step_seconds: step_seconds.max(1),
+                    settle_seconds: settle,
+                    threads: threads.unwrap_or_else(|| std::thread::available_parallelism().map_or(1, |n| n.get())),
+                    interval: std::time::Duration::from_millis(250),
+                    source: cfg.get_config().thermal.cpu_temp_source.clone(),
+                };
+                // As for `benchmark`: the current step is removed before exiting.
+                backend::launch::catch_interrupts()?;
+                let curve = calibrate::sweep(&opts, &daemon::profile_manager(&cfg), &cpu_manager, |line| eprintln!("{}", line));
+                if backend::launch::interrupted() {
+                    eprintln!("Interrupted; restored the previous settings");
+                    std::process::exit(130);
+                }
+                let curve = curve?;
+                match curve.save() {
+                    Ok(path) => eprintln!("Saved to {}\n", path.display()),
+                    Err(e) => eprintln!("Failed to save the curve: {:#}\n", e),
+                }
+                curve
+            };
+            if json {
+                println!("{}", serde_json::to_string_pretty(&curve)?);
+            } else {
+                curve.print();
+            }
+        }
+        Commands::FixedFreq { freq, no_cstates, no_smt, settle_timeout, unlock, status, command } => {
+            use backend::fixed_freq::{self, FixedLock, LockOptions, Stabiliser};
+            use backend::launch;
+            let profiles = daemon::profile_manager(&config::ConfigManager::new()?);
+            if unlock {
+                match fixed_freq::unlock(&profiles, &cpu_manager)? {
+                    Some(lock) => println!("Released the lock ({})", lock.describe()),
+                    None => println!("No fixed-frequency lock"),
+                }
+                return Ok(());
+            }
+            if status {
+                match FixedLock::load() {
+                    Some(lock) => {
+                        println!("Locked: {}", lock.describe());
+                        println!("{}", fixed_freq::verify(&cpu_manager, lock.options.freq_mhz).summary());
+                    }
+                    None => println!("No fixed-frequency lock"),
+                }
+                return Ok(());
+            }
+            let opts = LockOptions {
+                freq_mhz: freq.context("--freq is required")?,
+                disable_deep_cstates: no_cstates,
+                disable_smt: no_smt,
+            };
+            // Interrupts release the lock below; if this process dies
+            // outright, the service or GUI notices and restores.
+            launch::catch_interrupts()?;
+            let owner = backend::overrides::Expiry::process(std::process::id() as i32)?;
+            let lock = fixed_freq::lock(opts, owner, &profiles, &cpu_manager)?;
+            println!("Locked at {}", lock.describe());
+            let outcome = (|| -> Result<i32> {
+                let thermal = configured_thermal_manager()?;
+                let mut settle = Stabiliser::new(10, 0.5);
+                let deadline = std::time::Instant::now() + std::time::Duration::from_secs(settle_timeout);
+                println!("Waiting for the temperature to settle…");
+                while std::time::Instant::now() < deadline && !launch::interrupted() {
+                    if thermal.get_cpu_temperature().is_ok_and(|t| settle.push(t)) {
+                        break;
+                    }
+                    std::thread::sleep(std::time::Duration::from_secs(1));
+                }
+                match settle.last() {
+                    Some(t) if settle.is_stable() => println!("Temperature stable at {}", fmt_temp(t, 1)),
+                    Some(t) => println!("Temperature still moving after {} s ({} now)", settle_timeout, fmt_temp(t, 1)),
+                    None => println!("No temperature reading"),
+                }
+                let check = fixed_freq::verify(&cpu_manager, lock.options.freq_mhz);
+                println!("{}", check.summary());
+                if launch::interrupted() {
+                    return Ok(130);
+                }
+                if command.is_empty() {
+                    println!("Holding the lock; press Ctrl-C to restore");
+                    while !launch::interrupted() {
+                        std::thread::sleep(std::time::Duration::from_millis(200));
+                    }
+                    return Ok(0);
+                }
+                let status = launch::run(&command)?;
+                let after = fixed_freq::verify(&cpu_manager, lock.options.freq_mhz);
+                // Idle cores drift once the command ends; only the limits count here.
+                if !after.bad_limits.is_empty() || after.turbo == Some(true) {
+                    eprintln!("Warning: the lock was changed during the run. {}", after.summary());
+                }
+                Ok(launch::exit_code(status))
+            })();
+            match fixed_freq::unlock(&profiles, &cpu_manager) {
+                Ok(_) => println!("Restored the previous settings"),
+                Err(e) => eprintln!("Failed to restore everything: {:#}", e),
+            }
+            match outcome {
+                Ok(code) => std::process::exit(code),
+                Err(e) => {
+                    eprintln!("{:#}", e);
+                    std::process::exit(1);
+                }
+            }
+        }
         Commands::Service => {
             log::info!("Starting background service");
-            println!("Service mode not yet implemented");
+            daemon::run()?;
         }
         Commands::Version => {
             println!("CPU Power Manager v{}", env!("CARGO_PKG_VERSION"));
@@ -143,6 +706,70 @@ fn handle_cli_command(command: Commands) -> Result<()> {
     Ok(())
 }
 
+fn parse_on_off(s: &str) -> std::result::Result<bool, String> {
+    match s.to_lowercase().as_str() {
+        "on" | "true" | "1" => Ok(true),
+        "off" | "false" | "0" => Ok(false),
+        _ => Err(format!("expected on or off, got {:?}", s)),
+    }
+}
+
+fn print_battery_status(bat: &backend::BatteryInfo) {
+    if !bat.present {
+        println!("Battery: none ({})", if bat.on_ac { "AC power" } else { "no AC adapter reported" });
+        return;
+    }
+    let wh = |v: Option<f32>| v.map(|v| format!("{:.1} Wh", v)).unwrap_or_else(|| "--".into());
+    println!("Battery:");
+    println!("  Charge: {:.0}% ({})", bat.charge_percent, bat.status);
+    if let Some(w) = bat.power_now_w {
+        println!("  Power: {:.1} W", w);
+    }
+    // One-shot reading: no smoothing history, so this is the instantaneous estimate.
+    if let Some((secs, charging)) = backend::BatteryEstimator::new(1).update(bat) {
+        println!(
+            "  {}: {}",
+            if charging { "Time to full" } else { "Time to empty" },
+            backend::thermal_forecast::format_duration(secs)
+        );
+    }
+    println!("  Energy: {} / {} (design {})", wh(bat.energy_now_wh), wh(bat.energy_full_wh), wh(bat.energy_full_design_wh));
+    if let Some(h) = bat.health_percent() {
+        println!("  Health: {:.0}%", h);
+    }
+    for b in &bat.batteries {
+        println!("  {}:", b.name);
+        let ident: Vec<&str> = [b.manufacturer.as_deref(), b.model.as_deref(), b.technology.as_deref()]
+            .into_iter().flatten().collect();
+        if !ident.is_empty() {
+            println!("    Model: {}", ident.join(" "));
+        }
+        if let Some(c) = b.capacity_percent { println!("    Capacity: {:.0}% ({})", c, b.status); }
+        println!("    Energy: {} / {} (design {})", wh(b.energy_now_wh), wh(b.energy_full_wh), wh(b.energy_full_design_wh));
+        if let (Some(now), Some(full)) = (b.charge_now_ah, b.charge_full_ah) {
+            match b.charge_full_design_ah {
+                Some(design) => println!("    Charge: {:.2} / {:.2} Ah (design {:.2} Ah)", now, full, design),
+                None => println!("    Charge: {:.2} / {:.2} Ah", now, full),
+            }
+        }
+        if let Some(h) = b.health_percent() { println!("    Health: {:.0}%", h); }
+        if let Some(c) = b.cycle_count { println!("    Cycles: {}", c); }
+        if let Some(v) = b.voltage_v { println!("    Voltage: {:.2} V", v); }
+        if let Some(end) = b.charge_end_threshold {
+            println!("    Charge thresholds: {}–{}%", b.charge_start_threshold.unwrap_or(0), end);
+        }
+    }
+}
+
+/// ThermalManager reading the CPU temperature from the configured source.
+fn configured_thermal_manager() -> Result<backend::ThermalManager> {
+    let mut thermal = backend::ThermalManager::new()?;
+    if let Ok(cfg) = config::ConfigManager::new() {
+        thermal.set_source(cfg.get_config().thermal.cpu_temp_source.clone());
+    }
+    Ok(thermal)
+}
+
 thread_local! {
     static SCALE_PROVIDER: std::cell::RefCell<Option<gtk4::CssProvider>> = std::cell::RefCell::new(None);
 }
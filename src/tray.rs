@@ -2,12 +2,23 @@
 /// Gracefully no-ops if the DBus session bus is unavailable (e.g. sudo without -E).
 use gtk4::prelude::*;
 use gtk4::ApplicationWindow;
+use std::collections::HashMap;
 use std::sync::{Arc, Mutex};
+use zbus::zvariant::{ObjectPath, Value};
+use crate::backend::overrides;
 
 #[derive(Debug)]
-enum TrayMsg { Toggle, Quit }
+enum TrayMsg { Toggle, Quit, Override(Option<usize>) }
 
-pub fn spawn(window: ApplicationWindow) {
+/// Context-menu item ids; presets are `MENU_PRESET + index`, and the id
+/// after `MENU_TOGGLE` and `MENU_CLEAR` is a separator.
+const MENU_TOGGLE: i32 = 1;
+const MENU_PRESET: i32 = 10;
+const MENU_CLEAR: i32 = 30;
+const MENU_QUIT: i32 = 40;
+
+/// `on_override` pushes `overrides::presets()[i]`, or clears them with `None`.
+pub fn spawn(window: ApplicationWindow, on_override: impl Fn(Option<usize>) + 'static) {
     let queue: Arc<Mutex<Vec<TrayMsg>>> = Arc::new(Mutex::new(Vec::new()));
     let queue_tray = queue.clone();
     let queue_gtk  = queue.clone();
@@ -21,6 +32,7 @@ pub fn spawn(window: ApplicationWindow) {
                     if window.is_visible() { window.hide(); } else { window.present(); }
                 }
                 TrayMsg::Quit => window.close(),
+                TrayMsg::Override(preset) => on_override(preset),
             }
         }
         glib::ControlFlow::Continue
@@ -57,6 +69,90 @@ async fn run_tray(queue: Arc<Mutex<Vec<TrayMsg>>>) {
         }
         fn context_menu(&self, _x: i32, _y: i32) {}
         fn scroll(&self, _delta: i32, _orientation: &str) {}
+
+        #[zbus(property)] fn menu(&self) -> ObjectPath<'_> { ObjectPath::from_static_str_unchecked("/MenuBar") }
+    }
+
+    /// Minimal static `com.canonical.dbusmenu` for the tray's context menu.
+    struct Menu { queue: Arc<Mutex<Vec<TrayMsg>>> }
+
+    type Props = HashMap<String, Value<'static>>;
+    type Layout = (i32, Props, Vec<Value<'static>>);
+
+    fn item_props(id: i32) -> Props {
+        let label = match id {
+            MENU_TOGGLE => Some("Show / Hide".to_string()),
+            MENU_CLEAR => Some("Clear Overrides".to_string()),
+            MENU_QUIT => Some("Quit".to_string()),
+            id if (MENU_PRESET..MENU_CLEAR).contains(&id) => overrides::presets()
+                .get((id - MENU_PRESET) as usize)
+                .map(|(label, _)| label.to_string()),
+            _ => None,
+        };
+        let mut props = Props::new();
+        match label {
+            Some(label) => { props.insert("label".into(), Value::from(label)); }
+            None => { props.insert("type".into(), Value::from("separator")); }
+        }
+        props
+    }
+
+    fn item_ids() -> Vec<i32> {
+        let presets = (0..overrides::presets().len() as i32).map(|i| MENU_PRESET + i);
+        [MENU_TOGGLE, MENU_TOGGLE + 1].into_iter().chain(presets).chain([MENU_CLEAR, MENU_CLEAR + 1, MENU_QUIT]).collect()
+    }
+
+    #[interface(name = "com.canonical.dbusmenu")]
+    impl Menu {
+        #[zbus(property)] fn version(&self) -> u32 { 3 }
+        #[zbus(property)] fn status(&self) -> &str { "normal" }
+        #[zbus(property)] fn text_direction(&self) -> &str { "ltr" }
+
+        fn get_layout(&self, parent_id: i32, _recursion_depth: i32, _property_names: Vec<String>) -> (u32, Layout) {
+            let children = if parent_id == 0 {
+                item_ids().into_iter()
+                    .map(|id| Value::from((id, item_props(id), Vec::<Value<'static>>::new())))
+                    .collect()
+            } else {
+                Vec::new()
+            };
+            let mut root = Props::new();
+            root.insert("children-display".into(), Value::from("submenu"));
+            (1, (parent_id, if parent_id == 0 { root } else { item_props(parent_id) }, children))
+        }
+
+        fn get_group_properties(&self, ids: Vec<i32>, _property_names: Vec<String>) -> Vec<(i32, Props)> {
+            ids.into_iter().map(|id| (id, item_props(id))).collect()
+        }
+
+        fn get_property(&self, id: i32, name: String) -> Value<'static> {
+            item_props(id).remove(&name).unwrap_or_else(|| Value::from(""))
+        }
+
+        fn event(&self, id: i32, event_id: String, _data: Value<'_>, _timestamp: u32) {
+            if event_id != "clicked" {
+                return;
+            }
+            let msg = match id {
+                MENU_TOGGLE => TrayMsg::Toggle,
+                MENU_CLEAR => TrayMsg::Override(None),
+                MENU_QUIT => TrayMsg::Quit,
+                id if (MENU_PRESET..MENU_CLEAR).contains(&id) => TrayMsg::Override(Some((id - MENU_PRESET) as usize)),
+                _ => return,
+            };
+            self.queue.lock().unwrap().push(msg);
+        }
+
+        fn event_group(&self, events: Vec<(i32, String, Value<'_>, u32)>) -> Vec<i32> {
+            for (id, event_id, data, timestamp) in events {
+                self.event(id, event_id, data, timestamp);
+            }
+            Vec::new()
+        }
+
+        fn about_to_show(&self, _id: i32) -> bool { false }
+
+        fn about_to_show_group(&self, _ids: Vec<i32>) -> (Vec<i32>, Vec<i32>) { (Vec::new(), Vec::new()) }
     }
 
     let builder = match zbus::connection::Builder::session() {
@@ -71,6 +167,10 @@ async fn run_tray(queue: Arc<Mutex<Vec<TrayMsg>>>) {
         Ok(b) => b,
         Err(e) => { log::warn!("Tray: serve_at failed: {}", e); return; }
     };
+    let builder = match builder.serve_at("/MenuBar", Menu { queue: queue.clone() }) {
+        Ok(b) => b,
+        Err(e) => { log::warn!("Tray: serve_at failed: {}", e); return; }
+    };
     let conn = match builder.build().await {
         Ok(c) => c,
         Err(e) => { log::warn!("Tray: connection failed: {}", e); return; }
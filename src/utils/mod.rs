@@ -1,5 +1,7 @@
 // Utility modules
 pub mod error;
+pub mod smoothing;
+pub mod units;
 
 use thiserror::Error;
 
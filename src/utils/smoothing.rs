@@ -0,0 +1,107 @@
+// Smoothing for displayed readings
+use std::collections::VecDeque;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SmoothingMethod {
+    #[default]
+    None,
+    /// Exponential moving average with alpha = 2 / (window + 1).
+    Ema,
+    /// Median of the last `window` samples; rejects single-sample spikes.
+    Median,
+}
+
+impl SmoothingMethod {
+    pub const ALL: [SmoothingMethod; 3] = [SmoothingMethod::None, SmoothingMethod::Ema, SmoothingMethod::Median];
+
+    /// Parses `monitoring.smoothing`; unknown values disable smoothing.
+    pub fn from_config(s: &str) -> Self {
+        match s.trim().to_lowercase().as_str() {
+            "ema" => Self::Ema,
+            "median" => Self::Median,
+            _ => Self::None,
+        }
+    }
+
+    pub fn config_name(self) -> &'static str {
+        match self {
+            Self::None => "none",
+            Self::Ema => "ema",
+            Self::Median => "median",
+        }
+    }
+}
+
+/// Smooths one stream of samples. Reconfiguring with different settings
+/// starts over so old samples don't leak into the new filter.
+#[derive(Debug, Default)]
+pub struct Smoother {
+    method: SmoothingMethod,
+    window: usize,
+    ema: Option<f32>,
+    samples: VecDeque<f32>,
+}
+
+impl Smoother {
+    pub fn new(method: SmoothingMethod, window: usize) -> Self {
+        Self { method, window: window.max(1), ema: None, samples: VecDeque::new() }
+    }
+
+    pub fn configure(&mut self, method: SmoothingMethod, window: usize) {
+        if self.method != method || self.window != window.max(1) {
+            *self = Self::new(method, window);
+        }
+    }
+
+    /// Drops all history, keeping the settings.
+    pub fn reset(&mut self) {
+        *self = Self::new(self.method, self.window);
+    }
+
+    /// Adds a sample and returns the smoothed value.
+    pub fn push(&mut self, v: f32) -> f32 {
+        match self.method {
+            SmoothingMethod::None => v,
+            SmoothingMethod::Ema => {
+                let alpha = 2.0 / (self.window as f32 + 1.0);
+                let next = match self.ema {
+                    Some(prev) => prev + alpha * (v - prev),
+                    None => v,
+                };
+                self.ema = Some(next);
+                next
+            }
+            SmoothingMethod::Median => {
+                self.samples.push_back(v);
+                while self.samples.len() > self.window {
+                    self.samples.pop_front();
+                }
+                let mut sorted: Vec<f32> = self.samples.iter().copied().collect();
+                sorted.sort_by(|a, b| a.total_cmp(b));
+                let mid = sorted.len() / 2;
+                if sorted.len().is_multiple_of(2) { (sorted[mid - 1] + sorted[mid]) / 2.0 } else { sorted[mid] }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_median_rejects_spike() {
+        let mut s = Smoother::new(SmoothingMethod::Median, 3);
+        s.push(50.0);
+        s.push(51.0);
+        assert_eq!(s.push(90.0), 51.0);
+    }
+
+    #[test]
+    fn test_ema_converges() {
+        let mut s = Smoother::new(SmoothingMethod::Ema, 3);
+        assert_eq!(s.push(40.0), 40.0);
+        assert_eq!(s.push(60.0), 50.0);
+        assert_eq!(s.push(60.0), 55.0);
+    }
+}
@@ -0,0 +1,122 @@
+// Temperature unit conversion and formatting
+use std::sync::atomic::{AtomicU8, Ordering};
+
+/// Display unit for temperatures. Everything is stored and computed in
+/// Celsius; conversion happens only at the display/entry boundary.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TempUnit {
+    #[default]
+    Celsius,
+    Fahrenheit,
+    Kelvin,
+}
+
+/// Unit used by `TempUnit::current()`, set from `general.temperature_unit`.
+static CURRENT_UNIT: AtomicU8 = AtomicU8::new(0);
+
+impl TempUnit {
+    pub const ALL: [TempUnit; 3] = [TempUnit::Celsius, TempUnit::Fahrenheit, TempUnit::Kelvin];
+
+    /// Parses `general.temperature_unit`; unknown values fall back to Celsius.
+    pub fn from_config(s: &str) -> Self {
+        match s.trim().to_lowercase().as_str() {
+            "fahrenheit" | "f" => Self::Fahrenheit,
+            "kelvin" | "k" => Self::Kelvin,
+            _ => Self::Celsius,
+        }
+    }
+
+    pub fn config_name(self) -> &'static str {
+        match self {
+            Self::Celsius => "celsius",
+            Self::Fahrenheit => "fahrenheit",
+            Self::Kelvin => "kelvin",
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Celsius => "Celsius",
+            Self::Fahrenheit => "Fahrenheit",
+            Self::Kelvin => "Kelvin",
+        }
+    }
+
+    pub fn symbol(self) -> &'static str {
+        match self {
+            Self::Celsius => "°C",
+            Self::Fahrenheit => "°F",
+            Self::Kelvin => "K",
+        }
+    }
+
+    pub fn current() -> Self {
+        Self::ALL[CURRENT_UNIT.load(Ordering::Relaxed) as usize]
+    }
+
+    pub fn set_current(unit: Self) {
+        CURRENT_UNIT.store(unit as u8, Ordering::Relaxed);
+    }
+
+    /// Converts a Celsius reading into this unit.
+    pub fn convert(self, c: f32) -> f32 {
+        match self {
+            Self::Celsius => c,
+            Self::Fahrenheit => c * 1.8 + 32.0,
+            Self::Kelvin => c + 273.15,
+        }
+    }
+
+    pub fn to_celsius(self, v: f32) -> f32 {
+        match self {
+            Self::Celsius => v,
+            Self::Fahrenheit => (v - 32.0) / 1.8,
+            Self::Kelvin => v - 273.15,
+        }
+    }
+
+    /// Converts a temperature difference (hysteresis, margin), not a reading.
+    pub fn delta_from_celsius(self, d: f32) -> f32 {
+        match self {
+            Self::Fahrenheit => d * 1.8,
+            _ => d,
+        }
+    }
+
+    pub fn delta_to_celsius(self, d: f32) -> f32 {
+        match self {
+            Self::Fahrenheit => d / 1.8,
+            _ => d,
+        }
+    }
+
+    /// e.g. `54.0°C`, `129.2°F`, `327.2K`.
+    pub fn format(self, celsius: f32, decimals: usize) -> String {
+        format!("{:.*}{}", decimals, self.convert(celsius), self.symbol())
+    }
+
+    pub fn format_delta(self, celsius: f32, decimals: usize) -> String {
+        format!("{:.*}{}", decimals, self.delta_from_celsius(celsius), self.symbol())
+    }
+}
+
+/// Formats a Celsius value in the configured display unit.
+pub fn fmt_temp(celsius: f32, decimals: usize) -> String {
+    TempUnit::current().format(celsius, decimals)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_conversions() {
+        assert_eq!(TempUnit::Fahrenheit.convert(100.0), 212.0);
+        assert!((TempUnit::Kelvin.convert(0.0) - 273.15).abs() < 1e-3);
+        assert!((TempUnit::Fahrenheit.to_celsius(212.0) - 100.0).abs() < 1e-3);
+        assert_eq!(TempUnit::Fahrenheit.delta_from_celsius(5.0), 9.0);
+        assert_eq!(TempUnit::Kelvin.delta_from_celsius(5.0), 5.0);
+        assert_eq!(TempUnit::from_config("Fahrenheit"), TempUnit::Fahrenheit);
+        assert_eq!(TempUnit::from_config("bogus"), TempUnit::Celsius);
+    }
+}